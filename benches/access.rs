@@ -4,7 +4,7 @@ extern crate test;
 extern crate rle_vec;
 
 use std::iter::FromIterator;
-use std::iter::repeat;
+use std::iter::{repeat, repeat_n};
 use test::Bencher;
 use rle_vec::RleVec;
 
@@ -30,7 +30,7 @@ fn vec_iterate_10_000_unique_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_iterate_10_000_equal_values(b: &mut Bencher) {
-    let rle = RleVec::from_iter(repeat(0).take(10_000));
+    let rle = RleVec::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         for v in rle.iter() {
             assert_eq!(*v, 0);
@@ -40,7 +40,7 @@ fn rle_iterate_10_000_equal_values(b: &mut Bencher) {
 
 #[bench]
 fn vec_iterate_10_000_equal_values(b: &mut Bencher) {
-    let vec = Vec::from_iter(repeat(0).take(10_000));
+    let vec = Vec::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         for v in &vec {
             assert_eq!(*v, 0);
@@ -50,9 +50,9 @@ fn vec_iterate_10_000_equal_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_iterate_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let rle = RleVec::from_iter(iter);
     b.iter(|| {
@@ -64,9 +64,9 @@ fn rle_iterate_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn vec_iterate_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let vec = Vec::from_iter(iter);
     b.iter(|| {
@@ -78,9 +78,9 @@ fn vec_iterate_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_random_access_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let rle = RleVec::from_iter(iter);
     let len = rle.len();
@@ -95,9 +95,9 @@ fn rle_random_access_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn vec_random_access_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let vec = Vec::from_iter(iter);
     let len = vec.len();
@@ -109,3 +109,21 @@ fn vec_random_access_1000_runs_of_10_values(b: &mut Bencher) {
         }
     })
 }
+
+#[bench]
+fn rle_indexed_random_access_1000_runs_of_10_values(b: &mut Bencher) {
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
+
+    let rle = RleVec::from_iter(iter);
+    let indexed = rle.build_access_index(16);
+    let len = indexed.len();
+    b.iter(|| {
+        let mut i = 5;
+        while i < len {
+            let _ = indexed[i];
+            i += 10;
+        }
+    })
+}
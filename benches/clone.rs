@@ -0,0 +1,38 @@
+#![feature(test)]
+
+extern crate test;
+extern crate rle_vec;
+
+use std::iter::{repeat, repeat_n};
+use test::Bencher;
+use rle_vec::RleVec;
+
+#[bench]
+fn rle_clone_1000_runs_of_10_values(b: &mut Bencher) {
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
+    let baseline: RleVec<_> = iter.collect();
+    let mut working = baseline.clone();
+
+    b.iter(|| {
+        working = baseline.clone();
+        working.set(5, 1);
+        assert_eq!(working.runs_len(), 1002);
+    })
+}
+
+#[bench]
+fn rle_clone_from_1000_runs_of_10_values(b: &mut Bencher) {
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
+    let baseline: RleVec<_> = iter.collect();
+    let mut working = baseline.clone();
+
+    b.iter(|| {
+        working.clone_from(&baseline);
+        working.set(5, 1);
+        assert_eq!(working.runs_len(), 1002);
+    })
+}
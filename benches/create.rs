@@ -4,7 +4,7 @@ extern crate test;
 extern crate rle_vec;
 
 use std::iter::FromIterator;
-use std::iter::repeat;
+use std::iter::{repeat, repeat_n};
 use test::Bencher;
 use rle_vec::RleVec;
 
@@ -14,7 +14,7 @@ fn rle_create_10_000_unique_values_from_slice(b: &mut Bencher) {
     let slice = vec.as_slice();
 
     b.iter(|| {
-        RleVec::from(&slice[..]);
+        RleVec::from(slice)
     })
 }
 
@@ -24,7 +24,7 @@ fn vec_create_10_000_unique_values_from_slice(b: &mut Bencher) {
     let slice = vec.as_slice();
 
     b.iter(|| {
-        Vec::from(&slice[..]);
+        Vec::from(slice)
     })
 }
 
@@ -45,41 +45,41 @@ fn vec_create_10_000_unique_values_from_iter(b: &mut Bencher) {
 #[bench]
 fn rle_create_10_000_equal_values_from_iter(b: &mut Bencher) {
     b.iter(|| {
-        RleVec::from_iter(repeat(0).take(10_000));
+        RleVec::from_iter(repeat_n(0, 10_000));
     })
 }
 
 #[bench]
 fn vec_create_10_000_equal_values_from_iter(b: &mut Bencher) {
     b.iter(|| {
-        Vec::from_iter(repeat(0).take(10_000));
+        Vec::from_iter(repeat_n(0, 10_000));
     })
 }
 
 #[bench]
 fn rle_create_10_000_equal_values_from_slice(b: &mut Bencher) {
-    let vec = Vec::from_iter(repeat(0).take(10_000));
+    let vec = Vec::from_iter(repeat_n(0, 10_000));
     let slice = vec.as_slice();
     b.iter(|| {
-        RleVec::from(&slice[..]);
+        RleVec::from(slice)
     })
 }
 
 #[bench]
 fn vec_create_10_000_equal_values_from_slice(b: &mut Bencher) {
-    let vec = Vec::from_iter(repeat(0).take(10_000));
+    let vec = Vec::from_iter(repeat_n(0, 10_000));
     let slice = vec.as_slice();
     b.iter(|| {
-        Vec::from(&slice[..]);
+        Vec::from(slice)
     })
 }
 
 #[bench]
 fn rle_create_1000_runs_of_10_values_from_iter(b: &mut Bencher) {
     b.iter(|| {
-        let zeros = repeat(0).take(10);
-        let ones = repeat(1).take(10);
-        let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+        let zeros = repeat_n(0, 10);
+        let ones = repeat_n(1, 10);
+        let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
         RleVec::from_iter(iter);
     })
@@ -87,9 +87,9 @@ fn rle_create_1000_runs_of_10_values_from_iter(b: &mut Bencher) {
 #[bench]
 fn vec_create_1000_runs_of_10_values_from_iter(b: &mut Bencher) {
     b.iter(|| {
-        let zeros = repeat(0).take(10);
-        let ones = repeat(1).take(10);
-        let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+        let zeros = repeat_n(0, 10);
+        let ones = repeat_n(1, 10);
+        let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
         Vec::from_iter(iter);
     })
@@ -97,27 +97,88 @@ fn vec_create_1000_runs_of_10_values_from_iter(b: &mut Bencher) {
 
 #[bench]
 fn rle_create_1000_runs_of_10_values_from_slice(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let vec = Vec::from_iter(iter);
     let slice = vec.as_slice();
 
     b.iter(|| {
-        RleVec::from(&slice[..]);
+        RleVec::from(slice)
     })
 }
 #[bench]
 fn vec_create_1000_runs_of_10_values_from_slice(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let vec = Vec::from_iter(iter);
     let slice = vec.as_slice();
 
     b.iter(|| {
-        Vec::from(&slice[..]);
+        Vec::from(slice)
+    })
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn rle_create_10_000_000_mostly_equal_values_from_slice(b: &mut Bencher) {
+    let mut vec = vec![0; 10_000_000];
+    for i in (0..vec.len()).step_by(100_000) {
+        vec[i] = 1;
+    }
+
+    b.iter(|| {
+        RleVec::from(&vec[..])
+    })
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn rle_create_10_000_000_mostly_equal_values_from_par_slice(b: &mut Bencher) {
+    let mut vec = vec![0; 10_000_000];
+    for i in (0..vec.len()).step_by(100_000) {
+        vec[i] = 1;
+    }
+
+    b.iter(|| {
+        RleVec::from_par_slice(&vec[..])
+    })
+}
+
+#[bench]
+fn rle_create_10_000_linspace_values(b: &mut Bencher) {
+    b.iter(|| {
+        RleVec::linspace(0.0, 1.0, 10_000, 0.001)
+    })
+}
+
+#[bench]
+fn vec_create_10_000_linspace_values(b: &mut Bencher) {
+    let n = 10_000;
+    b.iter(|| {
+        (0..n).map(|i| i as f64 / (n - 1) as f64).collect::<Vec<_>>()
+    })
+}
+
+#[bench]
+fn rle_extend_from_slice_1_000_000_runs_of_2_values(b: &mut Bencher) {
+    let vec: Vec<u32> = (0..2_000_000).map(|i| i / 2).collect();
+    b.iter(|| {
+        let mut rle = RleVec::new();
+        rle.extend_from_slice(&vec);
+        assert_eq!(rle.runs_len(), 1_000_000);
+    })
+}
+
+#[bench]
+fn rle_extend_from_iter_1_000_000_runs_of_2_values(b: &mut Bencher) {
+    let vec: Vec<u32> = (0..2_000_000).map(|i| i / 2).collect();
+    b.iter(|| {
+        let mut rle = RleVec::new();
+        rle.extend(vec.iter().cloned());
+        assert_eq!(rle.runs_len(), 1_000_000);
     })
 }
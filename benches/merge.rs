@@ -0,0 +1,44 @@
+#![feature(test)]
+
+extern crate test;
+extern crate rle_vec;
+
+use test::Bencher;
+use rle_vec::RleVec;
+
+fn tracks() -> Vec<RleVec<u32>> {
+    (0..50u32).map(|t| {
+        let mut rle = RleVec::new();
+        let mut value = 0;
+        while rle.len() < 100_000 {
+            let n = ((t as usize * 31 + rle.runs_len() * 17) % 97 + 1).min(100_000 - rle.len());
+            rle.push_n(n, value);
+            value = (value + t + 1) % 5;
+        }
+        rle
+    }).collect()
+}
+
+#[bench]
+fn rle_merge_many_sum_50_tracks(b: &mut Bencher) {
+    let tracks = tracks();
+    let inputs: Vec<_> = tracks.iter().collect();
+    b.iter(|| {
+        let sum = RleVec::merge_many(&inputs, |values| values.iter().cloned().sum::<u32>());
+        assert_eq!(sum.len(), 100_000);
+    })
+}
+
+#[bench]
+fn vec_sum_50_tracks(b: &mut Bencher) {
+    let tracks: Vec<Vec<u32>> = tracks().iter().map(RleVec::to_vec).collect();
+    b.iter(|| {
+        let mut sum = vec![0; 100_000];
+        for track in &tracks {
+            for (s, v) in sum.iter_mut().zip(track) {
+                *s += v;
+            }
+        }
+        assert_eq!(sum.len(), 100_000);
+    })
+}
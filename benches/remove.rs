@@ -4,15 +4,15 @@ extern crate test;
 extern crate rle_vec;
 
 use std::iter::FromIterator;
-use std::iter::repeat;
+use std::iter::{repeat, repeat_n};
 use test::Bencher;
 use rle_vec::RleVec;
 
 #[bench]
 fn rle_remove_runmids_non_breaking_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
@@ -31,9 +31,9 @@ fn rle_remove_runmids_non_breaking_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_remove_runs_merging_1000_runs(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(1);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(5_500);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 1);
+    let iter = repeat(zeros.chain(ones)).flatten().take(5_500);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
@@ -50,9 +50,9 @@ fn rle_remove_runs_merging_1000_runs(b: &mut Bencher) {
 
 #[bench]
 fn vec_remove_runmids_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
     let vec = Vec::from_iter(iter);
 
     b.iter(|| {
@@ -70,9 +70,9 @@ fn vec_remove_runmids_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn vec_remove_runs_merging_1000_runs(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(1);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(5_500);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 1);
+    let iter = repeat(zeros.chain(ones)).flatten().take(5_500);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
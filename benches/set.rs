@@ -4,15 +4,15 @@ extern crate test;
 extern crate rle_vec;
 
 use std::iter::FromIterator;
-use std::iter::repeat;
+use std::iter::{repeat, repeat_n};
 use test::Bencher;
 use rle_vec::RleVec;
 
 #[bench]
 fn rle_set_runmids_non_breaking_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
     let vec: Vec<_> = iter.collect();
     let mut rle = RleVec::from(&vec[..]);
 
@@ -31,9 +31,9 @@ fn rle_set_runmids_non_breaking_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_set_runmids_breaking_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
@@ -52,9 +52,9 @@ fn rle_set_runmids_breaking_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_set_runs_merging_1000_runs(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(1);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(5_500);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 1);
+    let iter = repeat(zeros.chain(ones)).flatten().take(5_500);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
@@ -71,9 +71,9 @@ fn rle_set_runs_merging_1000_runs(b: &mut Bencher) {
 
 #[bench]
 fn vec_set_runmids_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
     let mut vec = Vec::from_iter(iter);
 
     b.iter(|| {
@@ -90,9 +90,9 @@ fn vec_set_runmids_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn vec_set_runs_merging_1000_runs(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(1);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(5_500);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 1);
+    let iter = repeat(zeros.chain(ones)).flatten().take(5_500);
     let vec: Vec<_> = iter.collect();
 
     b.iter(|| {
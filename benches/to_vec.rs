@@ -4,13 +4,13 @@ extern crate test;
 extern crate rle_vec;
 
 use std::iter::FromIterator;
-use std::iter::repeat;
+use std::iter::{repeat, repeat_n};
 use test::Bencher;
 use rle_vec::RleVec;
 
 #[bench]
 fn rle_to_vec_of_u8_10_000_equal_values(b: &mut Bencher) {
-    let rle = RleVec::<u8>::from_iter(repeat(0).take(10_000));
+    let rle = RleVec::<u8>::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         let vec = rle.to_vec();
         assert_eq!(vec.len(), 10_000);
@@ -19,7 +19,7 @@ fn rle_to_vec_of_u8_10_000_equal_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_to_vec_of_u16_10_000_equal_values(b: &mut Bencher) {
-    let rle = RleVec::<u16>::from_iter(repeat(0).take(10_000));
+    let rle = RleVec::<u16>::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         let vec = rle.to_vec();
         assert_eq!(vec.len(), 10_000);
@@ -37,9 +37,9 @@ fn rle_to_vec_of_u16_10_000_unique_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_to_vec_of_u16_1000_runs_of_10_values(b: &mut Bencher) {
-    let zeros = repeat(0).take(10);
-    let ones = repeat(1).take(10);
-    let iter = repeat(zeros.chain(ones)).flat_map(|x| x).take(10_000);
+    let zeros = repeat_n(0, 10);
+    let ones = repeat_n(1, 10);
+    let iter = repeat(zeros.chain(ones)).flatten().take(10_000);
 
     let rle = RleVec::from_iter(iter);
 
@@ -51,7 +51,7 @@ fn rle_to_vec_of_u16_1000_runs_of_10_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_to_vec_of_u32_10_000_equal_values(b: &mut Bencher) {
-    let rle = RleVec::<u32>::from_iter(repeat(0).take(10_000));
+    let rle = RleVec::<u32>::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         let vec = rle.to_vec();
         assert_eq!(vec.len(), 10_000);
@@ -60,9 +60,35 @@ fn rle_to_vec_of_u32_10_000_equal_values(b: &mut Bencher) {
 
 #[bench]
 fn rle_to_vec_of_u64_10_000_equal_values(b: &mut Bencher) {
-    let rle = RleVec::<u64>::from_iter(repeat(0).take(10_000));
+    let rle = RleVec::<u64>::from_iter(repeat_n(0, 10_000));
     b.iter(|| {
         let vec = rle.to_vec();
         assert_eq!(vec.len(), 10_000);
     })
 }
+
+fn quality_string() -> String {
+    let mut s = String::new();
+    for (i, c) in "IIIIIIIIIIIIIIIIIIIIIIIIIIII:::::FFFFFFFFFFFF,,,,#".chars().enumerate() {
+        s.extend(repeat_n(c, i % 7 + 1));
+    }
+    s
+}
+
+#[bench]
+fn rle_collect_string_of_quality_values(b: &mut Bencher) {
+    let rle = RleVec::from_str_chars(&quality_string());
+    b.iter(|| {
+        let s = rle.collect_string();
+        assert!(s.len() > 150);
+    })
+}
+
+#[bench]
+fn rle_iter_collect_string_of_quality_values(b: &mut Bencher) {
+    let rle = RleVec::from_str_chars(&quality_string());
+    b.iter(|| {
+        let s: String = rle.iter().collect();
+        assert!(s.len() > 150);
+    })
+}
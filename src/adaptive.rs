@@ -0,0 +1,398 @@
+//! A vector that switches between run length encoded and dense storage.
+
+use std::iter::FusedIterator;
+use std::ops::Index;
+use std::slice;
+
+use super::RleVec;
+
+/// The thresholds at which an [`AdaptiveVec`](struct.AdaptiveVec.html) changes its storage.
+///
+/// The storage becomes dense when the number of runs exceeds `dense_above * len` and becomes run
+/// length encoded again when it drops below `rle_below * len`. The gap between both ratios keeps a
+/// vector near a threshold from converting back and forth on every change. Vectors shorter than
+/// `min_len` are never converted to dense storage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptivePolicy {
+    /// The ratio of runs to elements above which the storage becomes dense.
+    pub dense_above: f64,
+    /// The ratio of runs to elements below which the storage becomes run length encoded.
+    pub rle_below: f64,
+    /// The minimal length for dense storage.
+    pub min_len: usize,
+}
+
+impl Default for AdaptivePolicy {
+    /// Converts to dense storage above one run per two elements and back below one run per four
+    /// elements, for vectors of at least 64 elements.
+    fn default() -> Self {
+        AdaptivePolicy { dense_above: 0.5, rle_below: 0.25, min_len: 64 }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Storage<T> {
+    Rle(RleVec<T>),
+    // the values and their number of runs
+    Dense(Vec<T>, usize),
+}
+
+/// A vector that is stored as an [`RleVec`](../struct.RleVec.html) while it compresses well and as
+/// a `Vec` when it does not.
+///
+/// Incompressible data costs more memory as runs than as a plain `Vec`, and every access pays for
+/// a binary search. An `AdaptiveVec` starts run length encoded and converts to dense storage when
+/// the number of runs grows too large compared to the length, and back when it shrinks again.
+/// The thresholds are set with an [`AdaptivePolicy`](struct.AdaptivePolicy.html). The number of
+/// runs is tracked in both storages, so checking the thresholds adds **O(1)** to every mutation.
+/// A conversion takes **O(n)**.
+///
+/// # Example
+/// ```
+/// # use rle_vec::adaptive::AdaptiveVec;
+/// let mut column = AdaptiveVec::new();
+/// column.push_n(100, 0);
+/// assert!(!column.is_dense());
+///
+/// for i in 0..60 {
+///     column.set(i, i);
+/// }
+/// assert!(column.is_dense());
+/// assert_eq!(column[59], 59);
+///
+/// for i in 0..60 {
+///     column.set(i, 0);
+/// }
+/// assert!(!column.is_dense());
+/// assert_eq!(column.runs_len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveVec<T> {
+    storage: Storage<T>,
+    policy: AdaptivePolicy,
+}
+
+impl<T> AdaptiveVec<T> {
+    /// Constructs a new empty `AdaptiveVec<T>` with the default policy.
+    pub fn new() -> AdaptiveVec<T> {
+        AdaptiveVec::with_policy(AdaptivePolicy::default())
+    }
+
+    /// Constructs a new empty `AdaptiveVec<T>` with the given policy.
+    ///
+    /// # Panics
+    /// Panics if `rle_below` is greater than `dense_above`.
+    pub fn with_policy(policy: AdaptivePolicy) -> AdaptiveVec<T> {
+        assert!(policy.rle_below <= policy.dense_above, "rle_below must not be greater than dense_above");
+        AdaptiveVec { storage: Storage::Rle(RleVec::new()), policy }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        match self.storage {
+            Storage::Rle(ref rle) => rle.len(),
+            Storage::Dense(ref values, _) => values.len(),
+        }
+    }
+
+    /// Returns `true` if the vector holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of runs, in both storages.
+    pub fn runs_len(&self) -> usize {
+        match self.storage {
+            Storage::Rle(ref rle) => rle.runs_len(),
+            Storage::Dense(_, runs) => runs,
+        }
+    }
+
+    /// Returns `true` if the values are currently stored in a `Vec`.
+    pub fn is_dense(&self) -> bool {
+        match self.storage {
+            Storage::Rle(_) => false,
+            Storage::Dense(..) => true,
+        }
+    }
+
+    /// Returns the policy of this vector.
+    pub fn policy(&self) -> AdaptivePolicy {
+        self.policy
+    }
+
+    /// Returns a reference to the value at the given index, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match self.storage {
+            Storage::Rle(ref rle) => rle.get(index),
+            Storage::Dense(ref values, _) => values.get(index),
+        }
+    }
+
+    /// Returns an iterator over the values, in either storage.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::adaptive::AdaptiveVec;
+    /// let column: AdaptiveVec<_> = vec![1, 1, 2].into_iter().collect();
+    ///
+    /// assert_eq!(column.iter().rev().collect::<Vec<_>>(), vec![&2, &1, &1]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        match self.storage {
+            Storage::Rle(ref rle) => Iter(IterStorage::Rle(rle.iter())),
+            Storage::Dense(ref values, _) => Iter(IterStorage::Dense(values.iter())),
+        }
+    }
+}
+
+impl<T: Eq + Clone> AdaptiveVec<T> {
+    /// Appends an element to the back.
+    pub fn push(&mut self, value: T) {
+        self.push_n(1, value);
+    }
+
+    /// Appends `n` copies of a value to the back.
+    pub fn push_n(&mut self, n: usize, value: T) {
+        if n == 0 { return }
+        match self.storage {
+            Storage::Rle(ref mut rle) => rle.push_n(n, value),
+            Storage::Dense(ref mut values, ref mut runs) => {
+                if values.last() != Some(&value) {
+                    *runs += 1;
+                }
+                values.resize(values.len() + n, value);
+            }
+        }
+        self.rebalance();
+    }
+
+    /// Modifies the value at the given index.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        match self.storage {
+            Storage::Rle(ref mut rle) => rle.set(index, value),
+            Storage::Dense(ref mut values, ref mut runs) => {
+                let boundaries = |values: &[T]| {
+                    (index > 0 && values[index - 1] != values[index]) as usize
+                        + (index + 1 < values.len() && values[index] != values[index + 1]) as usize
+                };
+                let before = boundaries(values);
+                values[index] = value;
+                *runs = *runs + boundaries(values) - before;
+            }
+        }
+        self.rebalance();
+    }
+
+    /// Returns the values as an `RleVec`.
+    pub fn into_rle(self) -> RleVec<T> {
+        match self.storage {
+            Storage::Rle(rle) => rle,
+            Storage::Dense(values, _) => RleVec::from(&values[..]),
+        }
+    }
+
+    /// Returns the values as a `Vec`.
+    pub fn into_vec(self) -> Vec<T> {
+        match self.storage {
+            Storage::Rle(rle) => rle.to_vec(),
+            Storage::Dense(values, _) => values,
+        }
+    }
+
+    // converts the storage when the number of runs crossed a threshold
+    fn rebalance(&mut self) {
+        let (len, runs) = (self.len() as f64, self.runs_len() as f64);
+        let storage = match self.storage {
+            Storage::Rle(ref rle) if self.len() >= self.policy.min_len && runs > self.policy.dense_above * len => {
+                Storage::Dense(rle.to_vec(), rle.runs_len())
+            }
+            Storage::Dense(ref values, _) if runs < self.policy.rle_below * len => {
+                Storage::Rle(RleVec::from(&values[..]))
+            }
+            _ => return,
+        };
+        self.storage = storage;
+    }
+}
+
+impl<T> Default for AdaptiveVec<T> {
+    fn default() -> Self {
+        AdaptiveVec::new()
+    }
+}
+
+impl<T> Index<usize> for AdaptiveVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.storage {
+            Storage::Rle(ref rle) => &rle[index],
+            Storage::Dense(ref values, _) => &values[index],
+        }
+    }
+}
+
+impl<T: Eq + Clone> From<RleVec<T>> for AdaptiveVec<T> {
+    fn from(rle: RleVec<T>) -> Self {
+        let mut adaptive = AdaptiveVec { storage: Storage::Rle(rle), policy: AdaptivePolicy::default() };
+        adaptive.rebalance();
+        adaptive
+    }
+}
+
+impl<T: Eq + Clone> ::std::iter::FromIterator<T> for AdaptiveVec<T> {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=T> {
+        AdaptiveVec::from(iter.into_iter().collect::<RleVec<T>>())
+    }
+}
+
+impl<'a, T: 'a> IntoIterator for &'a AdaptiveVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over references of the values of an `AdaptiveVec`.
+///
+/// Can be obtained from the [`iter`](struct.AdaptiveVec.html#method.iter) method.
+pub struct Iter<'a, T: 'a>(IterStorage<'a, T>);
+
+enum IterStorage<'a, T: 'a> {
+    Rle(super::Iter<'a, T>),
+    Dense(slice::Iter<'a, T>),
+}
+
+impl<'a, T: 'a> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            IterStorage::Rle(ref mut iter) => iter.next(),
+            IterStorage::Dense(ref mut iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            IterStorage::Rle(ref iter) => iter.size_hint(),
+            IterStorage::Dense(ref iter) => iter.size_hint(),
+        }
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        match self.0 {
+            IterStorage::Rle(ref mut iter) => iter.nth(n),
+            IterStorage::Dense(ref mut iter) => iter.nth(n),
+        }
+    }
+}
+
+impl<'a, T: 'a> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            IterStorage::Rle(ref mut iter) => iter.next_back(),
+            IterStorage::Dense(ref mut iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for Iter<'a, T> { }
+
+impl<'a, T: 'a> FusedIterator for Iter<'a, T> { }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_flop_near_thresholds() {
+        let policy = AdaptivePolicy { dense_above: 0.5, rle_below: 0.25, min_len: 0 };
+        let mut v = AdaptiveVec::with_policy(policy);
+        v.push_n(100, 0);
+        let mut model = vec![0; 100];
+        let mut set = |v: &mut AdaptiveVec<u32>, i: usize, value: u32| {
+            v.set(i, value);
+            model[i] = value;
+            assert_eq!(v.iter().cloned().collect::<Vec<_>>(), model);
+            assert_eq!(v.runs_len(), RleVec::from(&model[..]).runs_len());
+        };
+
+        // every odd index set to 1 adds two runs, 49 runs stay below the upper threshold
+        for i in 0..24 {
+            set(&mut v, 2 * i + 1, 1);
+            assert!(!v.is_dense());
+        }
+        assert_eq!(v.runs_len(), 49);
+        for _ in 0..3 {
+            set(&mut v, 1, 0);
+            assert!(!v.is_dense());
+            set(&mut v, 1, 1);
+            assert!(!v.is_dense());
+        }
+
+        // crossing the upper threshold converts once, toggling around it does not convert back
+        set(&mut v, 49, 1);
+        assert!(v.is_dense());
+        assert_eq!(v.runs_len(), 51);
+        for _ in 0..3 {
+            set(&mut v, 49, 0);
+            assert!(v.is_dense());
+            set(&mut v, 49, 1);
+            assert!(v.is_dense());
+        }
+
+        // dropping below the lower threshold converts back, toggling around it stays encoded
+        for i in 0..13 {
+            set(&mut v, 2 * i + 1, 0);
+            assert!(v.is_dense());
+        }
+        assert_eq!(v.runs_len(), 25);
+        set(&mut v, 27, 0);
+        assert!(!v.is_dense());
+        assert_eq!(v.runs_len(), 23);
+        for _ in 0..3 {
+            set(&mut v, 27, 1);
+            assert!(!v.is_dense());
+            set(&mut v, 27, 0);
+            assert!(!v.is_dense());
+        }
+    }
+
+    #[test]
+    fn read_api_in_both_storages() {
+        let values: Vec<u32> = (0..200).map(|i| if i < 100 { i } else { 7 }).collect();
+        let dense: AdaptiveVec<_> = values.iter().cloned().collect();
+        assert!(dense.is_dense());
+        let sparse: AdaptiveVec<_> = values[100..].iter().cloned().collect();
+        assert!(!sparse.is_dense());
+
+        for (v, expected) in &[(&dense, &values[..]), (&sparse, &values[100..])] {
+            assert_eq!(v.len(), expected.len());
+            assert_eq!(v.get(expected.len()), None);
+            assert_eq!(v[expected.len() - 1], 7);
+            assert!(v.iter().eq(expected.iter()));
+            assert!(v.iter().rev().eq(expected.iter().rev()));
+            assert_eq!(v.iter().nth(3), expected.get(3));
+            assert_eq!(v.iter().len(), expected.len());
+        }
+        assert_eq!(dense.clone().into_rle(), RleVec::from(&values[..]));
+        assert_eq!(dense.into_vec(), values);
+
+        let mut small = AdaptiveVec::new();
+        for i in 0..63 {
+            small.push(i);
+        }
+        assert!(!small.is_dense());
+        small.push(63);
+        assert!(small.is_dense());
+        small.push_n(10, 63);
+        assert_eq!(small.runs_len(), 64);
+    }
+}
@@ -0,0 +1,419 @@
+//! Debugging aids for code using `RleVec`.
+
+use std::iter::FromIterator;
+use std::ops::{Add, Deref, Range, RangeBounds};
+
+use super::{check_canonical_runs, AddOverflow, DrainRuns, MutationReport, OutOfBoundsError, RleVec, Run, TooManyRuns};
+
+/// A wrapper around `RleVec` that validates the internal invariants after every mutation.
+///
+/// All methods taking `&self` are available through `Deref`, the mutating methods are wrapped
+/// and call [`RleVec::assert_valid`](../struct.RleVec.html#method.assert_valid) before returning.
+/// This is useful in tests, fuzzing and debug builds, but adds an **O(n)** check to every
+/// mutation.
+///
+/// # Example
+///
+/// Replace `RleVec::new()` with `InvariantChecker::new()` in a test:
+///
+/// ```
+/// # use rle_vec::debug::InvariantChecker;
+/// let mut rle = InvariantChecker::new();
+///
+/// rle.push(1);
+/// rle.push_n(3, 2);
+/// rle.set(2, 1);
+/// rle.insert(0, 2);
+///
+/// assert_eq!(rle.to_vec(), vec![2, 1, 2, 1, 2]);
+/// assert_eq!(rle[1], 1);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct InvariantChecker<T>(RleVec<T>);
+
+impl<T> InvariantChecker<T> {
+    /// Constructs a new empty `InvariantChecker<T>`.
+    pub fn new() -> InvariantChecker<T> {
+        InvariantChecker(RleVec::new())
+    }
+
+    /// Constructs a new empty `InvariantChecker<T>` with capacity for the number of runs.
+    pub fn with_run_capacity(runs: usize) -> InvariantChecker<T> {
+        InvariantChecker(RleVec::with_run_capacity(runs))
+    }
+
+    /// Constructs a new empty `InvariantChecker<T>` with capacity for the number of runs.
+    #[deprecated(since = "0.5.0", note = "the capacity counts runs, use `with_run_capacity`")]
+    pub fn with_capacity(capacity: usize) -> InvariantChecker<T> {
+        InvariantChecker::with_run_capacity(capacity)
+    }
+
+    /// Returns the wrapped `RleVec`.
+    pub fn into_inner(self) -> RleVec<T> {
+        self.0
+    }
+
+    /// Clears the vector, removing all values.
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Reserves capacity for more runs, see [`RleVec::reserve_runs`](../struct.RleVec.html#method.reserve_runs).
+    pub fn reserve_runs(&mut self, additional: usize) {
+        self.0.reserve_runs(additional)
+    }
+
+    /// Reserves capacity for the runs of more elements, see
+    /// [`RleVec::reserve_runs_for`](../struct.RleVec.html#method.reserve_runs_for).
+    pub fn reserve_runs_for(&mut self, additional_elements: usize, expected_run_len: usize) {
+        self.0.reserve_runs_for(additional_elements, expected_run_len)
+    }
+}
+
+impl<T: Eq> InvariantChecker<T> {
+    /// Wraps an `RleVec`, validating it first.
+    pub fn from_rle(rle: RleVec<T>) -> InvariantChecker<T> {
+        rle.assert_valid();
+        InvariantChecker(rle)
+    }
+
+    /// Appends an element to the back, see [`RleVec::push`](../struct.RleVec.html#method.push).
+    pub fn push(&mut self, value: T) {
+        self.0.push(value);
+        self.0.assert_valid();
+    }
+
+    /// Appends an element unless that needs more than `max_runs` runs, see
+    /// [`RleVec::push_bounded`](../struct.RleVec.html#method.push_bounded).
+    pub fn push_bounded(&mut self, value: T, max_runs: usize) -> Result<(), TooManyRuns> {
+        let result = self.0.push_bounded(value, max_runs);
+        self.0.assert_valid();
+        result
+    }
+
+    /// Resizes with generated values, see [`RleVec::resize_with`](../struct.RleVec.html#method.resize_with).
+    pub fn resize_with<F>(&mut self, new_len: usize, f: F) where F: FnMut() -> T {
+        self.0.resize_with(new_len, f);
+        self.0.assert_valid();
+    }
+
+    /// Resizes with a value, see [`RleVec::resize`](../struct.RleVec.html#method.resize).
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        self.0.resize(new_len, value);
+        self.0.assert_valid();
+    }
+
+    /// Shortens the vector, see [`RleVec::truncate`](../struct.RleVec.html#method.truncate).
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+        self.0.assert_valid();
+    }
+
+    /// Appends the same element n times, see [`RleVec::push_n`](../struct.RleVec.html#method.push_n).
+    pub fn push_n(&mut self, n: usize, value: T) {
+        self.0.push_n(n, value);
+        self.0.assert_valid();
+    }
+
+    /// Moves all elements of `other` to the back, see [`RleVec::append`](../struct.RleVec.html#method.append).
+    pub fn append(&mut self, other: &mut RleVec<T>) {
+        self.0.append(other);
+        self.0.assert_valid();
+    }
+
+    /// Removes a range of runs, see [`RleVec::drain_runs`](../struct.RleVec.html#method.drain_runs).
+    ///
+    /// The remaining runs are validated before the iterator is returned, the iterator only moves
+    /// out runs that are no longer part of the `RleVec`.
+    pub fn drain_runs(&mut self, run_range: Range<usize>) -> DrainRuns<'_, T> {
+        let kept = self.0.detach_runs(run_range);
+        if let Err(err) = check_canonical_runs(&self.0.runs[..kept]) {
+            panic!("{}", err);
+        }
+        DrainRuns { iter: self.0.runs.drain(kept..), start: 0 }
+    }
+
+    /// Removes the first `n` elements, see [`RleVec::drain_front`](../struct.RleVec.html#method.drain_front).
+    pub fn drain_front(&mut self, n: usize) {
+        self.0.drain_front(n);
+        self.0.assert_valid();
+    }
+}
+
+impl<T: Eq + Clone> InvariantChecker<T> {
+    /// Modifies the value at an index, see [`RleVec::set`](../struct.RleVec.html#method.set).
+    pub fn set(&mut self, index: usize, value: T) {
+        self.0.set(index, value);
+        self.0.assert_valid();
+    }
+
+    /// Modifies the values in a range, see [`RleVec::set_range`](../struct.RleVec.html#method.set_range).
+    pub fn set_range<R>(&mut self, range: R, value: T) where R: RangeBounds<usize> {
+        self.0.set_range(range, value);
+        self.0.assert_valid();
+    }
+
+    /// Modifies the value at an index and returns the previous value, see
+    /// [`RleVec::replace`](../struct.RleVec.html#method.replace).
+    pub fn replace(&mut self, index: usize, value: T) -> T {
+        let old = self.0.replace(index, value);
+        self.0.assert_valid();
+        old
+    }
+
+    /// Removes the last element, see [`RleVec::pop`](../struct.RleVec.html#method.pop).
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.0.pop();
+        self.0.assert_valid();
+        value
+    }
+
+    /// Removes the element at an index, see [`RleVec::remove`](../struct.RleVec.html#method.remove).
+    pub fn remove(&mut self, index: usize) -> T {
+        let value = self.0.remove(index);
+        self.0.assert_valid();
+        value
+    }
+
+    /// Inserts a value at an index, see [`RleVec::insert`](../struct.RleVec.html#method.insert).
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.0.insert(index, value);
+        self.0.assert_valid();
+    }
+
+    /// Inserts `n` copies of a value at an index, see
+    /// [`RleVec::insert_n`](../struct.RleVec.html#method.insert_n).
+    pub fn insert_n(&mut self, index: usize, n: usize, value: T) {
+        self.0.insert_n(index, n, value);
+        self.0.assert_valid();
+    }
+
+    /// Modifies the value at an index and reports the change in runs, see
+    /// [`RleVec::set_reporting`](../struct.RleVec.html#method.set_reporting).
+    pub fn set_reporting(&mut self, index: usize, value: T) -> MutationReport {
+        let report = self.0.set_reporting(index, value);
+        self.0.assert_valid();
+        report
+    }
+
+    /// Modifies the values in a range and reports the change in runs, see
+    /// [`RleVec::set_range_reporting`](../struct.RleVec.html#method.set_range_reporting).
+    pub fn set_range_reporting<R>(&mut self, range: R, value: T) -> MutationReport where R: RangeBounds<usize> {
+        let report = self.0.set_range_reporting(range, value);
+        self.0.assert_valid();
+        report
+    }
+
+    /// Inserts a value and reports the change in runs, see
+    /// [`RleVec::insert_reporting`](../struct.RleVec.html#method.insert_reporting).
+    pub fn insert_reporting(&mut self, index: usize, value: T) -> MutationReport {
+        let report = self.0.insert_reporting(index, value);
+        self.0.assert_valid();
+        report
+    }
+
+    /// Removes a value and reports the change in runs, see
+    /// [`RleVec::remove_reporting`](../struct.RleVec.html#method.remove_reporting).
+    pub fn remove_reporting(&mut self, index: usize) -> (T, MutationReport) {
+        let removed = self.0.remove_reporting(index);
+        self.0.assert_valid();
+        removed
+    }
+
+    /// Splits the vector into two, see [`RleVec::split_off`](../struct.RleVec.html#method.split_off).
+    pub fn split_off(&mut self, at: usize) -> InvariantChecker<T> {
+        let tail = self.0.split_off(at);
+        self.0.assert_valid();
+        InvariantChecker::from_rle(tail)
+    }
+
+    /// Exchanges two ranges, see [`RleVec::swap_ranges`](../struct.RleVec.html#method.swap_ranges).
+    pub fn swap_ranges<A, B>(&mut self, a: A, b: B) where A: RangeBounds<usize>, B: RangeBounds<usize> {
+        self.0.swap_ranges(a, b);
+        self.0.assert_valid();
+    }
+
+    /// Appends clones of a slice, see [`RleVec::extend_from_slice`](../struct.RleVec.html#method.extend_from_slice).
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.0.extend_from_slice(slice);
+        self.0.assert_valid();
+    }
+
+    /// Filters the runs, see [`RleVec::retain`](../struct.RleVec.html#method.retain).
+    pub fn retain<F>(&mut self, f: F) -> usize where F: FnMut(&T) -> bool {
+        let removed = self.0.retain(f);
+        self.0.assert_valid();
+        removed
+    }
+
+    /// Removes all elements equal to a value, see
+    /// [`RleVec::remove_all`](../struct.RleVec.html#method.remove_all).
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let removed = self.0.remove_all(value);
+        self.0.assert_valid();
+        removed
+    }
+
+    /// Transforms and filters the runs, see [`RleVec::retain_map_runs`](../struct.RleVec.html#method.retain_map_runs).
+    pub fn retain_map_runs<F>(&mut self, f: F) where F: FnMut(&mut T, usize) -> bool {
+        self.0.retain_map_runs(f);
+        self.0.assert_valid();
+    }
+
+    /// Replaces matching runs with a fill value, see
+    /// [`RleVec::extract_runs_with_fill`](../struct.RleVec.html#method.extract_runs_with_fill).
+    pub fn extract_runs_with_fill<F>(&mut self, f: F, fill: T) -> Vec<(Range<usize>, T)> where F: FnMut(&T) -> bool {
+        let extracted = self.0.extract_runs_with_fill(f, fill);
+        self.0.assert_valid();
+        extracted
+    }
+
+    /// Removes a range and returns the removed values, see
+    /// [`RleVec::drain`](../struct.RleVec.html#method.drain).
+    ///
+    /// The values are collected, so the invariants can be checked before they are returned.
+    pub fn drain<R>(&mut self, range: R) -> Vec<T> where R: RangeBounds<usize> {
+        let drained = self.0.drain(range).collect();
+        self.0.assert_valid();
+        drained
+    }
+
+    /// Removes a range and returns the removed elements, see
+    /// [`RleVec::take_range`](../struct.RleVec.html#method.take_range).
+    pub fn take_range<R>(&mut self, range: R) -> InvariantChecker<T> where R: RangeBounds<usize> {
+        let taken = self.0.take_range(range);
+        self.0.assert_valid();
+        InvariantChecker::from_rle(taken)
+    }
+
+    /// Writes a batch of unsorted updates, see [`RleVec::scatter`](../struct.RleVec.html#method.scatter).
+    pub fn scatter<I>(&mut self, updates: I) where I: IntoIterator<Item=(usize, T)> {
+        self.0.scatter(updates);
+        self.0.assert_valid();
+    }
+
+    /// Writes a batch of unsorted updates or fails without changes, see
+    /// [`RleVec::try_scatter`](../struct.RleVec.html#method.try_scatter).
+    pub fn try_scatter<I>(&mut self, updates: I) -> Result<(), OutOfBoundsError> where I: IntoIterator<Item=(usize, T)> {
+        let result = self.0.try_scatter(updates);
+        self.0.assert_valid();
+        result
+    }
+
+    /// Inserts clones of `other` at the front, see [`RleVec::prepend_rle`](../struct.RleVec.html#method.prepend_rle).
+    pub fn prepend_rle(&mut self, other: &RleVec<T>) {
+        self.0.prepend_rle(other);
+        self.0.assert_valid();
+    }
+
+    /// Adds `delta` to a range of values, see [`RleVec::add_range`](../struct.RleVec.html#method.add_range).
+    pub fn add_range<R>(&mut self, range: R, delta: T) where R: RangeBounds<usize>, T: Add<Output=T> {
+        self.0.add_range(range, delta);
+        self.0.assert_valid();
+    }
+}
+
+macro_rules! impl_integer_add_range {
+    ($($t:ty)*) => ($(
+        impl InvariantChecker<$t> {
+            /// Adds `delta` to a range of values, saturating at the numeric bounds, see
+            /// [`RleVec::saturating_add_range`](../struct.RleVec.html#method.saturating_add_range).
+            pub fn saturating_add_range<R>(&mut self, range: R, delta: $t) where R: RangeBounds<usize> {
+                self.0.saturating_add_range(range, delta);
+                self.0.assert_valid();
+            }
+
+            /// Adds `delta` to a range of values or fails without changes, see
+            /// [`RleVec::checked_add_range`](../struct.RleVec.html#method.checked_add_range).
+            pub fn checked_add_range<R>(&mut self, range: R, delta: $t) -> Result<(), AddOverflow> where R: RangeBounds<usize> {
+                let result = self.0.checked_add_range(range, delta);
+                self.0.assert_valid();
+                result
+            }
+        }
+    )*)
+}
+
+impl_integer_add_range! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+impl<T> Deref for InvariantChecker<T> {
+    type Target = RleVec<T>;
+
+    fn deref(&self) -> &RleVec<T> {
+        &self.0
+    }
+}
+
+impl<T: Eq> From<RleVec<T>> for InvariantChecker<T> {
+    fn from(rle: RleVec<T>) -> Self {
+        InvariantChecker::from_rle(rle)
+    }
+}
+
+impl<T> From<InvariantChecker<T>> for RleVec<T> {
+    fn from(checker: InvariantChecker<T>) -> Self {
+        checker.0
+    }
+}
+
+impl<T: Eq> FromIterator<T> for InvariantChecker<T> {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=T> {
+        InvariantChecker::from_rle(RleVec::from_iter(iter))
+    }
+}
+
+impl<T: Eq> Extend<T> for InvariantChecker<T> {
+    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=T> {
+        self.0.extend(iter);
+        self.0.assert_valid();
+    }
+}
+
+impl<T: Eq> Extend<Run<T>> for InvariantChecker<T> {
+    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=Run<T>> {
+        self.0.extend(iter);
+        self.0.assert_valid();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_mutations() {
+        let mut v = vec![0, 0, 1, 1, 1, 2, 0, 0];
+        let mut rle: InvariantChecker<_> = v.iter().cloned().collect();
+        rle.set(5, 1);
+        v[5] = 1;
+        rle.insert(3, 7);
+        v.insert(3, 7);
+        assert_eq!(rle.remove(3), v.remove(3));
+        rle.swap_ranges(0..2, 6..8);
+        assert_eq!(rle.drain_runs(1..2).next(), Some(Run { len: 4, value: 1 }));
+        v.drain(2..6);
+        rle.extend(vec![0, 0, 3]);
+        v.extend(vec![0, 0, 3]);
+        assert_eq!(rle.to_vec(), v);
+        assert_eq!(rle.into_inner(), RleVec::from(&v[..]));
+    }
+
+    #[test]
+    fn checked_add_ranges() {
+        let mut rle: InvariantChecker<u8> = vec![0, 0, 1, 1, 250].into_iter().collect();
+        rle.saturating_add_range(2..5, 10);
+        assert_eq!(rle.to_vec(), vec![0, 0, 11, 11, 255]);
+        assert_eq!(rle.checked_add_range(0..5, 1), Err(AddOverflow { index: 4 }));
+        assert_eq!(rle.checked_add_range(0..2, 11), Ok(()));
+        assert_eq!(rle.runs_len(), 2);
+    }
+
+    #[test]
+    fn checked_push_bounded() {
+        let mut rle = InvariantChecker::new();
+        assert!(rle.push_bounded(1, 1).is_ok());
+        assert!(rle.push_bounded(1, 1).is_ok());
+        assert_eq!(rle.push_bounded(2, 1), Err(TooManyRuns { max_runs: 1, len: 2 }));
+        assert_eq!(rle.to_vec(), vec![1, 1]);
+    }
+}
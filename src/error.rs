@@ -0,0 +1,396 @@
+//! Error types returned by the fallible methods of `RleVec`.
+//!
+//! Each fallible method returns the specific error for its failure modes, which carries the
+//! offending index, length or value. [`RleVecError`](enum.RleVecError.html) unifies them for code
+//! that wants to propagate any of them with `?`. All error types are also available from the crate
+//! root.
+
+use std::collections::TryReserveError;
+use std::error;
+use std::fmt;
+use std::io;
+
+/// The error returned when an index does not exist in the `RleVec`.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::{RleVec, OutOfBoundsError};
+/// let rle = RleVec::from(&[1, 1, 2][..]);
+/// assert_eq!(rle.try_index(5), Err(OutOfBoundsError { index: 5, len: 3 }));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct OutOfBoundsError {
+    /// The requested index.
+    pub index: usize,
+    /// The length of the `RleVec` at the time of the request.
+    pub len: usize,
+}
+
+impl fmt::Display for OutOfBoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {} out of bounds for RleVec of length {}", self.index, self.len)
+    }
+}
+
+impl error::Error for OutOfBoundsError { }
+
+impl From<OutOfBoundsError> for io::Error {
+    fn from(err: OutOfBoundsError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// The error returned by [`runs_split_at`](../struct.RleVec.html#method.runs_split_at) for invalid
+/// boundaries.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::{RleVec, BoundaryError};
+/// let rle = RleVec::from(&[1, 1, 2][..]);
+/// assert_eq!(rle.runs_split_at(&[2, 1]).err(), Some(BoundaryError::Unsorted { index: 1 }));
+/// assert_eq!(rle.runs_split_at(&[4]).err(), Some(BoundaryError::OutOfBounds { boundary: 4, len: 3 }));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BoundaryError {
+    /// A boundary is greater than the length of the `RleVec`.
+    OutOfBounds {
+        /// The offending boundary.
+        boundary: usize,
+        /// The length of the `RleVec`.
+        len: usize,
+    },
+    /// The boundary at `index` is smaller than the one before it.
+    Unsorted {
+        /// The position of the offending boundary in the boundaries slice.
+        index: usize,
+    },
+}
+
+impl fmt::Display for BoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BoundaryError::OutOfBounds { boundary, len } =>
+                write!(f, "boundary {} out of bounds for RleVec of length {}", boundary, len),
+            BoundaryError::Unsorted { index } =>
+                write!(f, "boundary at position {} is smaller than its predecessor", index),
+        }
+    }
+}
+
+impl error::Error for BoundaryError { }
+
+/// The error returned when constructing an `RleVec` would exceed a maximum number of runs.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::{RleVec, TooManyRuns};
+/// let result = RleVec::try_from_iter(vec![1, 1, 2, 3, 3], 2);
+/// assert_eq!(result, Err(TooManyRuns { max_runs: 2, len: 3 }));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TooManyRuns {
+    /// The maximum number of runs.
+    pub max_runs: usize,
+    /// The number of elements accepted before the limit was hit.
+    pub len: usize,
+}
+
+impl fmt::Display for TooManyRuns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "more than {} runs after {} elements", self.max_runs, self.len)
+    }
+}
+
+impl error::Error for TooManyRuns { }
+
+/// The error returned by [`try_to_vec`](../struct.RleVec.html#method.try_to_vec).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ToVecError {
+    /// The `RleVec` is longer than the allowed maximum.
+    TooLong {
+        /// The length of the `RleVec`.
+        len: usize,
+        /// The maximum length.
+        max_len: usize,
+    },
+    /// The memory for the `Vec` could not be allocated.
+    Alloc(TryReserveError),
+}
+
+impl fmt::Display for ToVecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ToVecError::TooLong { len, max_len } =>
+                write!(f, "RleVec of length {} exceeds the maximum length of {}", len, max_len),
+            ToVecError::Alloc(ref err) => write!(f, "allocation failed: {}", err),
+        }
+    }
+}
+
+impl error::Error for ToVecError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ToVecError::TooLong { .. } => None,
+            ToVecError::Alloc(ref err) => Some(err),
+        }
+    }
+}
+
+/// The error returned by [`try_from_sorted_iter`](../struct.RleVec.html#method.try_from_sorted_iter)
+/// when the input is not sorted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct NotSorted {
+    /// The index of the first value that is smaller than its predecessor.
+    pub index: usize,
+}
+
+impl fmt::Display for NotSorted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value at index {} is smaller than its predecessor", self.index)
+    }
+}
+
+impl error::Error for NotSorted { }
+
+/// The error returned by [`from_dictionary`](../struct.RleVec.html#method.from_dictionary) when a
+/// code has no entry in the dictionary.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::{RleVec, MissingCode};
+/// let codes = RleVec::from(&[0u32, 0, 2][..]);
+/// let result = RleVec::from_dictionary(&codes, &["a", "b"]);
+/// assert_eq!(result, Err(MissingCode { code: 2, dictionary_len: 2 }));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MissingCode {
+    /// The code that was not found.
+    pub code: u32,
+    /// The number of entries in the dictionary.
+    pub dictionary_len: usize,
+}
+
+impl fmt::Display for MissingCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "code {} not found in dictionary of length {}", self.code, self.dictionary_len)
+    }
+}
+
+impl error::Error for MissingCode { }
+
+/// The error returned by `checked_add_range` when adding to a value overflows.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::{AddOverflow, RleVec};
+/// let mut rle = RleVec::from(&[1u8, 1, 250, 250][..]);
+/// assert_eq!(rle.checked_add_range(0..4, 10), Err(AddOverflow { index: 2 }));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct AddOverflow {
+    /// The index of the first value for which the addition overflows.
+    pub index: usize,
+}
+
+impl fmt::Display for AddOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "addition overflows for the value at index {}", self.index)
+    }
+}
+
+impl error::Error for AddOverflow { }
+
+/// The error returned when deserializing an `RleVec` written by a newer version of the
+/// serialized form, see [`FORMAT_VERSION`](../constant.FORMAT_VERSION.html).
+///
+/// Deserializers report it through their own error type, carrying the message of this error.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct UnsupportedFormatVersion {
+    /// The version of the serialized data.
+    pub version: u32,
+    /// The newest version this build can read.
+    pub supported: u32,
+}
+
+impl fmt::Display for UnsupportedFormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unsupported RleVec format version {}, the newest supported version is {}",
+               self.version, self.supported)
+    }
+}
+
+impl error::Error for UnsupportedFormatVersion { }
+
+/// The error returned when deserialized runs are not in canonical form, see
+/// [Guarantees](../struct.RleVec.html#guarantees).
+///
+/// Deserializers report it through their own error type, carrying the message of this error.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum NotCanonical {
+    /// The run at `run` does not end after the run before it.
+    EmptyRun {
+        /// The index of the offending run.
+        run: usize,
+    },
+    /// The run at `run` holds the same value as the run before it.
+    EqualNeighbours {
+        /// The index of the offending run.
+        run: usize,
+    },
+}
+
+impl fmt::Display for NotCanonical {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NotCanonical::EmptyRun { run } => write!(f, "run {} has a length of zero", run),
+            NotCanonical::EqualNeighbours { run } => write!(f, "runs {} and {} have equal values", run - 1, run),
+        }
+    }
+}
+
+impl error::Error for NotCanonical { }
+
+/// Any error returned by an `RleVec` method.
+///
+/// All specific error types convert into `RleVecError` with `From`, so they can be propagated with
+/// `?` from functions calling several fallible methods. An `RleVecError` displays the wrapped
+/// error and forwards its [`source`](https://doc.rust-lang.org/std/error/trait.Error.html#method.source),
+/// so reporting the chain does not print the same message twice. Match on the variant to get the
+/// wrapped error itself.
+///
+/// # Example
+///
+/// ```
+/// # use rle_vec::RleVec;
+/// use rle_vec::error::{NotSorted, OutOfBoundsError, RleVecError};
+///
+/// fn smallest(values: &[u32]) -> Result<u32, RleVecError> {
+///     let rle = RleVec::try_from_sorted_iter(values.iter().cloned())?;
+///     Ok(*rle.try_index(0)?)
+/// }
+///
+/// assert_eq!(smallest(&[1, 1, 3]), Ok(1));
+/// assert_eq!(smallest(&[2, 1]), Err(RleVecError::NotSorted(NotSorted { index: 1 })));
+/// assert_eq!(smallest(&[]), Err(OutOfBoundsError { index: 0, len: 0 }.into()));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RleVecError {
+    /// See [`OutOfBoundsError`](struct.OutOfBoundsError.html).
+    OutOfBounds(OutOfBoundsError),
+    /// See [`BoundaryError`](enum.BoundaryError.html).
+    Boundary(BoundaryError),
+    /// See [`TooManyRuns`](struct.TooManyRuns.html).
+    TooManyRuns(TooManyRuns),
+    /// See [`ToVecError`](enum.ToVecError.html).
+    ToVec(ToVecError),
+    /// See [`NotSorted`](struct.NotSorted.html).
+    NotSorted(NotSorted),
+    /// See [`MissingCode`](struct.MissingCode.html).
+    MissingCode(MissingCode),
+    /// See [`AddOverflow`](struct.AddOverflow.html).
+    AddOverflow(AddOverflow),
+    /// See [`UnsupportedFormatVersion`](struct.UnsupportedFormatVersion.html).
+    FormatVersion(UnsupportedFormatVersion),
+    /// See [`NotCanonical`](enum.NotCanonical.html).
+    NotCanonical(NotCanonical),
+}
+
+impl fmt::Display for RleVecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RleVecError::OutOfBounds(ref err) => err.fmt(f),
+            RleVecError::Boundary(ref err) => err.fmt(f),
+            RleVecError::TooManyRuns(ref err) => err.fmt(f),
+            RleVecError::ToVec(ref err) => err.fmt(f),
+            RleVecError::NotSorted(ref err) => err.fmt(f),
+            RleVecError::MissingCode(ref err) => err.fmt(f),
+            RleVecError::AddOverflow(ref err) => err.fmt(f),
+            RleVecError::FormatVersion(ref err) => err.fmt(f),
+            RleVecError::NotCanonical(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for RleVecError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            RleVecError::OutOfBounds(ref err) => err.source(),
+            RleVecError::Boundary(ref err) => err.source(),
+            RleVecError::TooManyRuns(ref err) => err.source(),
+            RleVecError::ToVec(ref err) => err.source(),
+            RleVecError::NotSorted(ref err) => err.source(),
+            RleVecError::MissingCode(ref err) => err.source(),
+            RleVecError::AddOverflow(ref err) => err.source(),
+            RleVecError::FormatVersion(ref err) => err.source(),
+            RleVecError::NotCanonical(ref err) => err.source(),
+        }
+    }
+}
+
+macro_rules! impl_from_error {
+    ($($err:ident => $variant:ident),*) => {
+        $(
+            impl From<$err> for RleVecError {
+                fn from(err: $err) -> RleVecError {
+                    RleVecError::$variant(err)
+                }
+            }
+        )*
+    }
+}
+
+impl_from_error!(OutOfBoundsError => OutOfBounds, BoundaryError => Boundary, TooManyRuns => TooManyRuns,
+                 ToVecError => ToVec, NotSorted => NotSorted, MissingCode => MissingCode, AddOverflow => AddOverflow,
+                 UnsupportedFormatVersion => FormatVersion, NotCanonical => NotCanonical);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn display_and_source() {
+        let err = RleVecError::from(BoundaryError::Unsorted { index: 3 });
+        assert_eq!(err.to_string(), "boundary at position 3 is smaller than its predecessor");
+        assert!(err.source().is_none());
+
+        let err: RleVecError = MissingCode { code: 7, dictionary_len: 2 }.into();
+        assert_eq!(err.to_string(), "code 7 not found in dictionary of length 2");
+
+        let err: RleVecError = AddOverflow { index: 4 }.into();
+        assert_eq!(err.to_string(), "addition overflows for the value at index 4");
+
+        let err: RleVecError = TooManyRuns { max_runs: 2, len: 5 }.into();
+        assert_eq!(err.to_string(), "more than 2 runs after 5 elements");
+
+        let err: RleVecError = UnsupportedFormatVersion { version: 3, supported: 1 }.into();
+        assert_eq!(err.to_string(), "unsupported RleVec format version 3, the newest supported version is 1");
+
+        let err: RleVecError = NotCanonical::EqualNeighbours { run: 2 }.into();
+        assert_eq!(err.to_string(), "runs 1 and 2 have equal values");
+
+        let err: RleVecError = NotSorted { index: 4 }.into();
+        assert_eq!(err.to_string(), "value at index 4 is smaller than its predecessor");
+
+        let err: RleVecError = ToVecError::TooLong { len: 10, max_len: 4 }.into();
+        assert_eq!(err.to_string(), "RleVec of length 10 exceeds the maximum length of 4");
+        assert!(err.source().is_none());
+
+        // the source of the wrapped error is forwarded, so the chain has no duplicate message
+        let alloc = Vec::<u64>::new().try_reserve_exact(usize::MAX).unwrap_err();
+        let err: RleVecError = ToVecError::Alloc(alloc.clone()).into();
+        assert_eq!(err.to_string(), format!("allocation failed: {}", alloc));
+        assert_eq!(err.source().unwrap().to_string(), alloc.to_string());
+        assert!(err.source().unwrap().source().is_none());
+
+        let err = io::Error::from(OutOfBoundsError { index: 3, len: 1 });
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "index 3 out of bounds for RleVec of length 1");
+    }
+}
@@ -1,4 +1,4 @@
-#![doc(html_root_url = "https://docs.rs/rle_vec/0.4.1")]
+#![doc(html_root_url = "https://docs.rs/rle_vec/0.5.0")]
 
 //! This crate provides `RleVec`, a vector like structure that stores runs of identical values coded
 //! by the value and the number of repeats.
@@ -19,12 +19,30 @@ extern crate serde;
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
+use std::convert::TryFrom;
+use std::fmt;
 use std::io;
 use std::iter::FromIterator;
-use std::iter::{once, repeat};
-use std::cmp;
-use std::ops::Index;
+use std::iter::{once, repeat_with, FusedIterator, Sum};
+use std::cmp::{self, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::mem;
+use std::marker::PhantomData;
+use std::ops::{Add, Bound, Deref, Index, Mul, Range, RangeBounds};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+pub mod adaptive;
+pub mod debug;
+pub mod error;
+
+pub use error::{AddOverflow, BoundaryError, MissingCode, NotCanonical, NotSorted, OutOfBoundsError, RleVecError, ToVecError,
+                TooManyRuns, UnsupportedFormatVersion};
 
 /// The `RleVec` struct handles like a normal vector and supports a subset from the `Vec` methods.
 ///
@@ -120,15 +138,114 @@ use std::ops::Index;
 /// not change its capacity or cause reallocation to occur. However, if the `RleVec`'s length is
 /// increased to 11, it will have to reallocate, which can be slow. For this reason, if you can
 /// predict the number of runs required in your `RleVec`, it is recommended to use
-/// `RleVec::with_capacity` whenever possible to specify how many runs the `RleVec` is expected
-/// to store.
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// `RleVec::with_run_capacity` whenever possible to specify how many runs the `RleVec` is expected
+/// to store. When only the number of elements and their average run length are known,
+/// `reserve_runs_for` converts them into a number of runs.
+///
+/// # Zero-sized types
+///
+/// An `RleVec` of a zero-sized type like `()` stores a single run and can be used to represent
+/// very long sequences cheaply. The length is still limited to `usize::MAX` elements; operations
+/// that would grow the vector beyond that panic. Methods that produce one value per element, like
+/// `to_vec` or `resize_with`, run in time proportional to the length.
+///
+/// ```
+/// # use rle_vec::RleVec;
+/// let mut rle = RleVec::new();
+/// rle.push_n(usize::MAX / 2, ());
+/// assert_eq!(rle.len(), usize::MAX / 2);
+/// assert_eq!(rle.runs_len(), 1);
+/// assert_eq!(rle.iter().nth(usize::MAX / 2 - 1), Some(&()));
+/// ```
+///
+/// # Guarantees
+///
+/// An `RleVec` is always stored in canonical form: no run is empty and adjacent runs hold
+/// different values. Every method maintains this, so the same sequence of values always has
+/// the same runs. This makes `runs_len()` the minimal number of runs, lets equality and hashing
+/// work on the runs, and makes serialized forms unique. It can be checked with
+/// [`is_canonical`](#method.is_canonical).
+///
+/// # Serialization
+///
+/// With the `serialize` feature `RleVec` implements `Serialize` and `Deserialize`. The serialized
+/// form is a struct holding the [`FORMAT_VERSION`](constant.FORMAT_VERSION.html) and the runs, a
+/// run of any length is stored as its end position and its value. Data written by a newer version
+/// of the format is refused with an
+/// [`UnsupportedFormatVersion`](error/struct.UnsupportedFormatVersion.html) error. Data written
+/// before the format was versioned is read as version 0 by self-describing formats like JSON.
+/// Formats that are not self-describing, like bincode, read the first bytes of such data as the
+/// version, so it is refused or misread.
+/// Deserialized runs that are not in canonical form are refused with a
+/// [`NotCanonical`](error/enum.NotCanonical.html) error, so deserializing requires `T: Eq`.
+/// `Run` implements `Serialize` and `Deserialize` as a struct of `len` and `value`.
+#[derive(Debug, Eq, Hash)]
 pub struct RleVec<T> {
     runs: Vec<InternalRun<T>>,
 }
 
-/// Represent a run inside the `RleVec`, can be obtained from the [`runs`](struct.RleVec.html#method.runs). A run is a serie of the same value.
+/// The version of the serialized form of `RleVec`.
+///
+/// The version is stored with every serialized `RleVec` and increased whenever the serialized
+/// form changes. Deserializing accepts this and all earlier versions.
+#[cfg(feature = "serde")]
+pub const FORMAT_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "RleVec")]
+struct RleVecRef<'a, T: 'a> {
+    version: u32,
+    runs: &'a [InternalRun<T>],
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "RleVec")]
+struct RleVecOwned<T> {
+    // the format was not versioned before version 1
+    #[serde(default)]
+    version: u32,
+    runs: Vec<InternalRun<T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for RleVec<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RleVecRef { version: FORMAT_VERSION, runs: &self.runs }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de> + Eq> serde::Deserialize<'de> for RleVec<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RleVecOwned { version, runs } = RleVecOwned::deserialize(deserializer)?;
+        if version > FORMAT_VERSION {
+            let err = UnsupportedFormatVersion { version, supported: FORMAT_VERSION };
+            return Err(serde::de::Error::custom(err));
+        }
+        let rle = RleVec { runs };
+        rle.check_canonical().map_err(serde::de::Error::custom)?;
+        Ok(rle)
+    }
+}
+
+impl<T: Clone> Clone for RleVec<T> {
+    fn clone(&self) -> RleVec<T> {
+        RleVec { runs: self.runs.clone() }
+    }
+
+    /// Reuses the allocation of `self` for the runs of `source`.
+    fn clone_from(&mut self, source: &RleVec<T>) {
+        self.runs.clone_from(&source.runs);
+    }
+}
+
+/// Represent a run inside the `RleVec`. A run is a serie of the same value.
+///
+/// An owned `Run<T>` is used to build an `RleVec`, through `FromIterator` and `Extend`. The
+/// [`runs`](struct.RleVec.html#method.runs) method borrows the runs as [`RunRef`](type.RunRef.html),
+/// a `Run<&T>`, which converts to an owned run with [`cloned`](#method.cloned) or `Run::from`.
 ///
 /// # Example
 ///
@@ -140,7 +257,11 @@ pub struct RleVec<T> {
 /// assert_eq!(iterator.next(), Some(Run{ len: 4, value: &1 }));
 /// assert_eq!(iterator.next(), Some(Run{ len: 2, value: &2 }));
 /// assert_eq!(iterator.next(), Some(Run{ len: 1, value: &3 }));
+///
+/// let copy: RleVec<i32> = rle.runs().map(Run::cloned).collect();
+/// assert_eq!(copy, rle);
 /// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Run<T> {
     /// The length of this run.
@@ -149,6 +270,59 @@ pub struct Run<T> {
     pub value: T,
 }
 
+/// A run borrowed from an `RleVec`, as yielded by [`runs`](struct.RleVec.html#method.runs).
+pub type RunRef<'a, T> = Run<&'a T>;
+
+impl<T> Run<T> {
+    /// Borrows the value of the run.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let run = Run { len: 3, value: String::from("a") };
+    /// let rle = RleVec::from(&["a"; 3][..]);
+    ///
+    /// assert_eq!(run.as_ref().map(|s| s.as_str()), Run { len: 3, value: "a" });
+    /// assert_eq!(rle.runs().next().map(|r| r.map(|s| *s)), Some(run.as_ref().map(|s| s.as_str())));
+    /// ```
+    pub fn as_ref(&self) -> Run<&T> {
+        Run { len: self.len, value: &self.value }
+    }
+
+    /// Maps the value of the run, keeping its length.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::Run;
+    /// assert_eq!(Run { len: 2, value: 3 }.map(|v| v * 2), Run { len: 2, value: 6 });
+    /// ```
+    pub fn map<U, F>(self, f: F) -> Run<U> where F: FnOnce(T) -> U {
+        Run { len: self.len, value: f(self.value) }
+    }
+}
+
+impl<T: Clone> Run<&T> {
+    /// Converts a borrowed run into an owned run by cloning its value.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// let owned: Vec<Run<i32>> = rle.runs().map(Run::cloned).collect();
+    /// assert_eq!(owned, vec![Run { len: 2, value: 1 }, Run { len: 1, value: 2 }]);
+    /// ```
+    pub fn cloned(self) -> Run<T> {
+        Run { len: self.len, value: self.value.clone() }
+    }
+}
+
+impl<'a, T: Clone> From<Run<&'a T>> for Run<T> {
+    fn from(run: Run<&'a T>) -> Run<T> {
+        run.cloned()
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 struct InternalRun<T> {
@@ -156,6 +330,61 @@ struct InternalRun<T> {
     value: T,
 }
 
+/// Statistics about the run lengths of an `RleVec`, obtained from
+/// [`run_length_stats`](struct.RleVec.html#method.run_length_stats).
+///
+/// The histogram uses power of two buckets: bucket `k` counts the runs with a length in
+/// `2^k..2^(k + 1)`, so bucket 0 holds the runs of length 1, bucket 1 the runs of length 2 and 3,
+/// bucket 2 the lengths 4 to 7 and so on. This bucketing scheme is part of the stable API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLengthStats {
+    /// The number of runs.
+    pub runs: usize,
+    /// The length of the shortest run, 0 when there are no runs.
+    pub min: usize,
+    /// The length of the longest run, 0 when there are no runs.
+    pub max: usize,
+    /// The mean run length, 0 when there are no runs.
+    pub mean: f64,
+    /// The population variance of the run lengths, 0 when there are no runs.
+    pub variance: f64,
+    /// The number of runs per power of two length bucket.
+    pub histogram: [usize; 64],
+}
+
+/// An estimate of the storage requirements of an `RleVec`, obtained from
+/// [`encoded_size_estimate`](struct.RleVec.html#method.encoded_size_estimate).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeEstimate {
+    /// The size in bytes of the values stored densely, `len * size_of::<T>()`. Saturates at
+    /// `usize::MAX` if the product overflows.
+    pub dense_bytes: usize,
+    /// The size in bytes when stored as runs, each run taking the LEB128 varint encoded run length
+    /// plus `size_of::<T>()`.
+    pub rle_bytes: usize,
+    /// The number of distinct values.
+    pub distinct_values: usize,
+    /// The Shannon entropy in bits per element of the value frequencies.
+    pub entropy: f64,
+}
+
+/// The structural impact of a single mutation, returned by the `*_reporting` variants of
+/// [`set`](struct.RleVec.html#method.set_reporting), [`set_range`](struct.RleVec.html#method.set_range_reporting),
+/// [`insert`](struct.RleVec.html#method.insert_reporting) and [`remove`](struct.RleVec.html#method.remove_reporting).
+///
+/// A split is a run boundary created by the mutation and a merge is a run boundary removed by it.
+/// Moving a value from one run to its neighbor therefore counts as one split and one merge, even
+/// though the number of runs does not change.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct MutationReport {
+    /// The change in [`runs_len`](struct.RleVec.html#method.runs_len).
+    pub run_delta: isize,
+    /// The number of run boundaries created.
+    pub splits: usize,
+    /// The number of run boundaries removed.
+    pub merges: usize,
+}
+
 impl<T> RleVec<T> {
     /// Constructs a new empty `RleVec<T>`.
     ///
@@ -173,32 +402,94 @@ impl<T> RleVec<T> {
 
     /// Constructs a new empty `RleVec<T>` with capacity for the number of runs.
     ///
-    /// Choosing this value requires knowledge about the composition of the data that is going to be inserted.
+    /// The capacity counts runs, not elements. Choosing this value requires knowledge about the
+    /// composition of the data that is going to be inserted, see
+    /// [`reserve_runs_for`](#method.reserve_runs_for) to derive it from the expected run length.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::with_capacity(10);
+    /// let mut rle = RleVec::with_run_capacity(10);
     ///
     /// // The rle_vector contains no items, even though it has capacity for more
     /// assert_eq!(rle.len(), 0);
+    /// assert!(rle.run_capacity() >= 10);
     ///
-    /// // These are all done without reallocating...
+    /// // Ten different values fill the ten runs without reallocating...
     /// for i in 0..10 {
     ///    rle.push(i);
     /// }
+    /// assert_eq!(rle.runs_len(), 10);
     ///
-    /// // The rle_vector contains 10 runs and 10 elements too...
-    /// assert_eq!(rle.len(), 10);
+    /// // ...while any number of equal values only extends the last run
+    /// for _ in 0..1000 {
+    ///    rle.push(9);
+    /// }
+    /// assert_eq!(rle.len(), 1010);
     /// assert_eq!(rle.runs_len(), 10);
     ///
-    /// // this definitely won't reallocate the runs
+    /// // a new value needs an eleventh run and may reallocate
     /// rle.push(10);
-    /// // while this may make the rle_vector reallocate
-    /// rle.push(11);
     /// ```
+    pub fn with_run_capacity(runs: usize) -> RleVec<T> {
+        RleVec { runs: Vec::with_capacity(runs) }
+    }
+
+    /// Constructs a new empty `RleVec<T>` with capacity for the number of runs.
+    #[deprecated(since = "0.5.0", note = "the capacity counts runs, use `with_run_capacity`")]
     pub fn with_capacity(capacity: usize) -> RleVec<T> {
-        RleVec { runs: Vec::with_capacity(capacity) }
+        RleVec::with_run_capacity(capacity)
+    }
+
+    /// Returns the number of runs the `RleVec` can hold without reallocating.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::<u8>::with_run_capacity(5);
+    /// assert!(rle.run_capacity() >= 5);
+    /// ```
+    pub fn run_capacity(&self) -> usize {
+        self.runs.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more runs.
+    ///
+    /// # Panics
+    /// Panics if the new capacity overflows `usize`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 2][..]);
+    ///
+    /// rle.reserve_runs(10);
+    /// assert!(rle.run_capacity() >= 12);
+    /// ```
+    pub fn reserve_runs(&mut self, additional: usize) {
+        self.runs.reserve(additional);
+    }
+
+    /// Reserves capacity for the runs of `additional_elements` more elements that form runs of
+    /// `expected_run_len` elements on average.
+    ///
+    /// The number of reserved runs is `additional_elements / expected_run_len`, rounded up. An
+    /// expected run length of 1 reserves a run for every element, the worst case.
+    ///
+    /// # Panics
+    /// Panics if `expected_run_len` is zero or if the new capacity overflows `usize`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// // about 5 million elements in runs of about 40
+    /// let mut rle = RleVec::<u32>::new();
+    /// rle.reserve_runs_for(5_000_000, 40);
+    /// assert!(rle.run_capacity() >= 125_000);
+    /// ```
+    pub fn reserve_runs_for(&mut self, additional_elements: usize, expected_run_len: usize) {
+        assert!(expected_run_len > 0, "expected run length must be at least 1");
+        self.reserve_runs(additional_elements.div_ceil(expected_run_len));
     }
 
     /// Returns the number of elements in the rle_vector.
@@ -350,6 +641,53 @@ impl<T> RleVec<T> {
         self.runs.iter().map(|r| r.end).collect()
     }
 
+    /// Computes statistics about the run lengths in a single pass over the runs.
+    ///
+    /// See [`RunLengthStats`](struct.RunLengthStats.html) for the histogram bucketing scheme.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4][..]);
+    ///
+    /// let stats = rle.run_length_stats();
+    /// assert_eq!(stats.runs, 4);
+    /// assert_eq!(stats.min, 1);
+    /// assert_eq!(stats.max, 8);
+    /// assert_eq!(stats.mean, 3.75);
+    /// assert_eq!(&stats.histogram[..5], &[1, 1, 1, 1, 0]);
+    /// ```
+    pub fn run_length_stats(&self) -> RunLengthStats {
+        let mut stats = RunLengthStats {
+            runs: self.runs.len(),
+            min: if self.is_empty() { 0 } else { usize::MAX },
+            max: 0,
+            mean: 0.0,
+            variance: 0.0,
+            histogram: [0; 64],
+        };
+
+        // Welford's online algorithm for the mean and variance
+        let mut m2 = 0.0;
+        let mut last_end = 0;
+        for (i, run) in self.runs.iter().enumerate() {
+            let len = run.end + 1 - last_end;
+            last_end = run.end + 1;
+
+            stats.min = cmp::min(stats.min, len);
+            stats.max = cmp::max(stats.max, len);
+            stats.histogram[(usize::BITS - 1 - len.leading_zeros()) as usize] += 1;
+
+            let delta = len as f64 - stats.mean;
+            stats.mean += delta / (i + 1) as f64;
+            m2 += delta * (len as f64 - stats.mean);
+        }
+        if !self.is_empty() {
+            stats.variance = m2 / self.runs.len() as f64;
+        }
+        stats
+    }
+
     /// Returns an iterator over values. Comparable to a `Vec` iterator.
     ///
     /// # Example
@@ -369,7 +707,7 @@ impl<T> RleVec<T> {
     /// assert_eq!(iterator.next(), Some(&3));
     /// assert_eq!(iterator.next(), None);
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             rle: self,
             run_index: 0,
@@ -397,913 +735,6786 @@ impl<T> RleVec<T> {
     /// assert_eq!(iterator.next(), Some(Run{ len: 1, value: &3 }));
     /// assert_eq!(iterator.next(), None);
     /// ```
-    pub fn runs(&self) -> Runs<T> {
-        Runs { rle: self, run_index: 0, last_end: 0 }
+    pub fn runs(&self) -> Runs<'_, T> {
+        Runs { rle: self, run_index: 0, last_end: 0, run_index_back: self.runs.len() }
     }
 
-    fn run_index(&self, index: usize) -> usize {
-        match self.runs.binary_search_by(|run| run.end.cmp(&index)) {
-            Ok(i) => i,
-            Err(i) if i < self.runs.len() => i,
-            _ => panic!("index out of bounds: the len is {} but the index is {}", self.len(), index)
-        }
+    /// Returns an iterator over the value of every run, which is the sequence of values with
+    /// consecutive duplicates removed.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&["idle", "idle", "busy", "idle"][..]);
+    ///
+    /// let changes: Vec<_> = rle.change_sequence().collect();
+    /// assert_eq!(changes, vec![&"idle", &"busy", &"idle"]);
+    /// ```
+    pub fn change_sequence(&self) -> impl DoubleEndedIterator<Item=&T> + ExactSizeIterator + '_ {
+        self.runs.iter().map(|run| &run.value)
     }
 
-    fn index_info(&self, index: usize) -> (usize, usize, usize) {
-        match self.run_index(index) {
-            0 => (0, 0, self.runs[0].end),
-            index => (index, self.runs[index - 1].end + 1, self.runs[index].end),
+    /// Returns an iterator that yields every run together with the previous and the next run.
+    /// The first item has no previous run and the last item has no next run.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3, 3][..]);
+    ///
+    /// // find runs shorter than both neighbours
+    /// let longer = |neighbour: &Option<Run<&i32>>, run: &Run<&i32>| {
+    ///     neighbour.as_ref().is_some_and(|n| n.len > run.len)
+    /// };
+    /// let dips: Vec<_> = rle.runs_with_context()
+    ///     .filter(|(prev, run, next)| longer(prev, run) && longer(next, run))
+    ///     .map(|(_, run, _)| run)
+    ///     .collect();
+    /// assert_eq!(dips, vec![Run { len: 1, value: &2 }]);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn runs_with_context(&self) -> impl DoubleEndedIterator<Item=(Option<Run<&T>>, Run<&T>, Option<Run<&T>>)> + ExactSizeIterator + '_ {
+        (0..self.runs.len()).map(move |p| {
+            let prev = p.checked_sub(1).map(|p| self.run_at(p));
+            let next = if p + 1 < self.runs.len() { Some(self.run_at(p + 1)) } else { None };
+            (prev, self.run_at(p), next)
+        })
+    }
+
+    fn run_at(&self, p: usize) -> Run<&T> {
+        let run = &self.runs[p];
+        Run { len: run.end + 1 - self.run_start(p), value: &run.value }
+    }
+
+    /// Returns an iterator over the values of consecutive runs. Because adjacent runs always hold
+    /// different values, every pair is a transition between two distinct values.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3, 1][..]);
+    ///
+    /// let pairs: Vec<_> = rle.iter_adjacent_pairs().collect();
+    /// assert_eq!(pairs, vec![(&1, &2), (&2, &3), (&3, &1)]);
+    /// ```
+    pub fn iter_adjacent_pairs(&self) -> impl Iterator<Item=(&T, &T)> + '_ {
+        self.runs.windows(2).map(|pair| (&pair[0].value, &pair[1].value))
+    }
+
+    /// Calls `f` with the values of every pair of consecutive runs.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3, 1][..]);
+    ///
+    /// let mut steps = Vec::new();
+    /// rle.for_each_adjacent_pair(|a, b| steps.push(b - a));
+    /// assert_eq!(steps, vec![1, 1, -2]);
+    /// ```
+    pub fn for_each_adjacent_pair<F>(&self, mut f: F) where F: FnMut(&T, &T) {
+        for (a, b) in self.iter_adjacent_pairs() {
+            f(a, b);
         }
     }
-}
 
-impl<T: Eq> RleVec<T> {
-    /// Appends an element to the back of this rle_vector.
+    /// Returns the number of pairs of consecutive runs for which the predicate returns `true`.
     ///
-    /// # Panics
-    /// Panics if the number of elements in the vector overflows a usize.
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3, 1][..]);
+    ///
+    /// assert_eq!(rle.count_adjacent_pairs_matching(|a, b| a < b), 2);
+    /// ```
+    pub fn count_adjacent_pairs_matching<P>(&self, p: P) -> usize where P: Fn(&T, &T) -> bool {
+        self.iter_adjacent_pairs().filter(|&(a, b)| p(a, b)).count()
+    }
+
+    /// Consumes the `RleVec` and applies `f` to the value of every run, producing an `RleVec<U>`.
+    /// The values are moved into `f` so no clones are made. Adjacent runs that map to equal values
+    /// are merged.
+    ///
+    /// The function is called once per run, not once per element.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::new();
-    /// rle.push(1);
-    /// assert_eq!(rle[0], 1);
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3, 4][..]);
+    ///
+    /// let parity = rle.with_value_map(|v| v % 2 == 0);
+    /// assert_eq!(parity.to_vec(), vec![false, false, true, false, false, true]);
+    /// assert_eq!(parity.runs_len(), 4);
     /// ```
-    #[inline]
-    pub fn push(&mut self, value: T) {
-        self.push_n(1, value);
+    pub fn with_value_map<U, F>(self, f: F) -> RleVec<U> where U: Eq, F: Fn(T) -> U {
+        let mut runs: Vec<InternalRun<U>> = Vec::with_capacity(self.runs.len());
+        for run in self.runs {
+            let value = f(run.value);
+            match runs.last_mut() {
+                Some(last) if last.value == value => last.end = run.end,
+                _ => runs.push(InternalRun { end: run.end, value }),
+            }
+        }
+        RleVec { runs }
     }
 
-    /// Appends the same element n times to the back of this rle_vec.
+    /// Walks all `inputs` simultaneously and calls `f` once for every span in which all inputs are
+    /// constant, with the values of the inputs in that span. The results are collected into a new
+    /// `RleVec<U>`, merging adjacent equal results.
+    ///
+    /// The boundaries of the inputs are visited in order using a heap, so the complexity is
+    /// **O(r log k)** calls for `r` runs in total over `k` inputs. An empty `inputs` slice produces
+    /// an empty `RleVec`.
     ///
     /// # Panics
-    /// Panics if the number of elements in the vector overflows a usize.
+    /// Panics if the inputs differ in length.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::new();
+    /// let a = RleVec::from(&[1, 1, 1, 2][..]);
+    /// let b = RleVec::from(&[0, 5, 5, 5][..]);
+    /// let c = RleVec::from(&[1, 1, 0, 0][..]);
     ///
-    /// // Push 10 times a 2
-    /// rle.push_n(10, 2);
-    /// assert_eq!(rle[9], 2);
+    /// let sum = RleVec::merge_many(&[&a, &b, &c], |values| values.iter().cloned().sum::<i32>());
+    /// assert_eq!(sum.to_vec(), vec![2, 7, 6, 7]);
     /// ```
-    pub fn push_n(&mut self, n: usize, value: T) {
-        if n == 0 { return; }
-
-        let end = match self.runs.last_mut() {
-            Some(ref mut last) if last.value == value => return last.end += n,
-            Some(last) => last.end + n,
-            None => n - 1,
-        };
+    pub fn merge_many<'a, U, F>(inputs: &[&'a RleVec<T>], mut f: F) -> RleVec<U> where U: Eq, F: FnMut(&[&'a T]) -> U {
+        let len = inputs.first().map_or(0, |rle| rle.len());
+        for rle in inputs {
+            assert_eq!(rle.len(), len, "merge_many requires inputs of equal length");
+        }
 
-        self.runs.push(InternalRun { value, end });
+        let mut result = RleVec::new();
+        if len == 0 { return result }
+
+        let mut positions = vec![0; inputs.len()];
+        let mut values: Vec<&'a T> = inputs.iter().map(|rle| &rle.runs[0].value).collect();
+        let mut boundaries: BinaryHeap<_> = inputs.iter().enumerate()
+            .map(|(i, rle)| Reverse((rle.runs[0].end, i)))
+            .collect();
+
+        let mut start = 0;
+        while let Some(&Reverse((end, _))) = boundaries.peek() {
+            result.push_n(end + 1 - start, f(&values));
+            start = end + 1;
+            while let Some(&Reverse((next, i))) = boundaries.peek() {
+                if next != end { break }
+                boundaries.pop();
+                positions[i] += 1;
+                if let Some(run) = inputs[i].runs.get(positions[i]) {
+                    values[i] = &run.value;
+                    boundaries.push(Reverse((run.end, i)));
+                }
+            }
+        }
+        result
     }
-}
 
-impl<T: Clone> RleVec<T> {
-    /// Construct a `Vec<T>` from this `RleVec`.
+    /// Returns the value for which `compare` reports the minimum, calling `compare` once per run
+    /// instead of once per element. Returns `None` for an empty `RleVec`.
     ///
-    /// The values of the `RleVec` are cloned to produce the final `Vec`.
-    /// This can be usefull for debugging.
+    /// If several values are equally minimal the first one is returned.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let slice = &[0, 0, 0, 1, 1, 99, 9];
-    /// let rle = RleVec::from(&slice[..]);
-    /// let vec = rle.to_vec();
+    /// let rle = RleVec::from(&[(1, 'a'), (1, 'a'), (0, 'b'), (0, 'c')][..]);
     ///
-    /// assert_eq!(vec.as_slice(), slice);
+    /// assert_eq!(rle.min_by(|a, b| a.0.cmp(&b.0)), Some(&(0, 'b')));
     /// ```
-    pub fn to_vec(&self) -> Vec<T> {
-        let mut res = Vec::with_capacity(self.len());
-        let mut p = 0;
-        for r in &self.runs {
-            let n = r.end - p + 1;
-            res.extend(repeat(r.value.clone()).take(n));
-            p += n;
-        }
-        res
+    pub fn min_by<F>(&self, compare: F) -> Option<&T> where F: FnMut(&T, &T) -> cmp::Ordering {
+        self.min_by_with_index(compare).map(|(_, value)| value)
     }
-}
 
-impl<T: Eq + Clone> RleVec<T> {
-    /// Modify the value at given index.
+    /// Returns the value for which `compare` reports the maximum, calling `compare` once per run
+    /// instead of once per element. Returns `None` for an empty `RleVec`.
     ///
-    /// This can result in the breaking of a run and therefore be an expensive operation.
-    /// If the value is equal to the value currently present the complexity is
-    /// **O(log n)**. But if the run needs to be broken the complexity increases to a worst case of
-    /// **O((log n) + n)**.
+    /// If several values are equally maximal the first one is returned, unlike `Iterator::max_by`.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+    /// let rle = RleVec::from(&[(1, 'a'), (1, 'a'), (0, 'b'), (1, 'c')][..]);
     ///
-    /// assert_eq!(rle[2], 1);
-    /// assert_eq!(rle.len(), 7);
-    /// assert_eq!(rle.runs_len(), 3);
+    /// assert_eq!(rle.max_by(|a, b| a.0.cmp(&b.0)), Some(&(1, 'a')));
+    /// ```
+    pub fn max_by<F>(&self, compare: F) -> Option<&T> where F: FnMut(&T, &T) -> cmp::Ordering {
+        self.max_by_with_index(compare).map(|(_, value)| value)
+    }
+
+    /// Returns the value with the minimal key, calling `f` once per run instead of once per
+    /// element. Returns `None` for an empty `RleVec`.
     ///
-    /// rle.set(2, 3);
-    /// assert_eq!(rle[2], 3);
-    /// assert_eq!(rle.len(), 7);
-    /// assert_eq!(rle.runs_len(), 5);
+    /// If several values have an equally minimal key the first one is returned.
+    ///
+    /// # Example
     /// ```
-    pub fn set(&mut self, index: usize, value: T) {
-        let (mut p, start, end) = self.index_info(index);
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&["ccc", "ccc", "a", "bb", "d"][..]);
+    ///
+    /// assert_eq!(rle.min_by_key(|s| s.len()), Some(&"a"));
+    /// ```
+    pub fn min_by_key<K, F>(&self, f: F) -> Option<&T> where K: Ord, F: FnMut(&T) -> K {
+        self.min_by_key_with_index(f).map(|(_, value)| value)
+    }
 
-        if self.runs[p].value == value { return }
+    /// Returns the value with the maximal key, calling `f` once per run instead of once per
+    /// element. Returns `None` for an empty `RleVec`.
+    ///
+    /// If several values have an equally maximal key the first one is returned, unlike
+    /// `Iterator::max_by_key`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&["a", "bb", "bb", "cc"][..]);
+    ///
+    /// assert_eq!(rle.max_by_key(|s| s.len()), Some(&"bb"));
+    /// ```
+    pub fn max_by_key<K, F>(&self, f: F) -> Option<&T> where K: Ord, F: FnMut(&T) -> K {
+        self.max_by_key_with_index(f).map(|(_, value)| value)
+    }
 
-        // a size 1 run is replaced with the new value or joined with next or previous
-        if end - start == 0 {
-            // can we join the previous run?
-            if p > 0 && self.runs[p - 1].value == value {
-                self.runs.remove(p);
-                self.runs[p - 1].end += 1;
-                p -= 1;
-            }
-            // can we join the next run?
-            if p < self.runs.len() - 1 && self.runs[p + 1].value == value {
-                self.runs.remove(p);
-                return;
+    /// Like [`min_by`](#method.min_by), but also returns the index at which the run of the
+    /// minimal value starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[3, 3, 1, 1, 2, 1][..]);
+    ///
+    /// assert_eq!(rle.min_by_with_index(|a, b| a.cmp(b)), Some((2, &1)));
+    /// ```
+    pub fn min_by_with_index<F>(&self, compare: F) -> Option<(usize, &T)> where F: FnMut(&T, &T) -> cmp::Ordering {
+        self.best_run_by(compare, cmp::Ordering::Less)
+    }
+
+    /// Like [`max_by`](#method.max_by), but also returns the index at which the run of the
+    /// maximal value starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 3, 3, 2, 3][..]);
+    ///
+    /// assert_eq!(rle.max_by_with_index(|a, b| a.cmp(b)), Some((1, &3)));
+    /// ```
+    pub fn max_by_with_index<F>(&self, compare: F) -> Option<(usize, &T)> where F: FnMut(&T, &T) -> cmp::Ordering {
+        self.best_run_by(compare, cmp::Ordering::Greater)
+    }
+
+    /// Like [`min_by_key`](#method.min_by_key), but also returns the index at which the run of
+    /// the value with the minimal key starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[-3, -3, 1, 1, -1][..]);
+    ///
+    /// assert_eq!(rle.min_by_key_with_index(|v: &i32| v.abs()), Some((2, &1)));
+    /// ```
+    pub fn min_by_key_with_index<K, F>(&self, f: F) -> Option<(usize, &T)> where K: Ord, F: FnMut(&T) -> K {
+        self.best_run_by_key(f, cmp::Ordering::Less)
+    }
+
+    /// Like [`max_by_key`](#method.max_by_key), but also returns the index at which the run of
+    /// the value with the maximal key starts.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, -3, 3][..]);
+    ///
+    /// assert_eq!(rle.max_by_key_with_index(|v: &i32| v.abs()), Some((2, &-3)));
+    /// ```
+    pub fn max_by_key_with_index<K, F>(&self, f: F) -> Option<(usize, &T)> where K: Ord, F: FnMut(&T) -> K {
+        self.best_run_by_key(f, cmp::Ordering::Greater)
+    }
+
+    // returns the start and value of the first run that compares as `wanted` against all runs
+    // before it
+    fn best_run_by<F>(&self, mut compare: F, wanted: cmp::Ordering) -> Option<(usize, &T)> where F: FnMut(&T, &T) -> cmp::Ordering {
+        let mut best = 0;
+        for p in 1..self.runs.len() {
+            if compare(&self.runs[p].value, &self.runs[best].value) == wanted {
+                best = p;
             }
-            // only one size-1 run in Rle replace its value
-            self.runs[p].value = value;
-            return;
         }
+        self.runs.get(best).map(|run| (self.run_start(best), &run.value))
+    }
 
-        // run size > 1, new value can split current run or maybe merge with previous or next
-        if index == start {
-            // compare to previous run
-            if p > 0 {
-                if self.runs[p - 1].value == value {
-                    self.runs[p - 1].end += 1;
-                } else {
-                    self.runs.insert(p, InternalRun { value, end: start });
-                }
-            } else {
-                self.runs.insert(0, InternalRun { value, end: 0 });
+    fn best_run_by_key<K, F>(&self, mut f: F, wanted: cmp::Ordering) -> Option<(usize, &T)> where K: Ord, F: FnMut(&T) -> K {
+        let mut runs = self.runs.iter().enumerate();
+        let (mut best, mut best_key) = match runs.next() {
+            Some((p, run)) => (p, f(&run.value)),
+            None => return None,
+        };
+        for (p, run) in runs {
+            let key = f(&run.value);
+            if key.cmp(&best_key) == wanted {
+                best = p;
+                best_key = key;
             }
-        } else if index == end {
-            // decrease current run length
-            self.runs[p].end -= 1;
+        }
+        Some((self.run_start(best), &self.runs[best].value))
+    }
 
-            // compare to next run
-            if p < self.runs.len() - 1 && self.runs[p + 1].value == value {
-            } else {
-                self.runs.insert(p + 1, InternalRun { value, end });
+    /// Applies the fallible function `f` to the value of every run and collects the results into
+    /// a new `RleVec<U>`. Adjacent runs that map to equal values are merged.
+    ///
+    /// The function is called once per run, in order, and the first error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let labels = RleVec::from(&["1", "1", "2", "02"][..]);
+    ///
+    /// let numbers = labels.try_map(|s| s.parse::<u8>()).unwrap();
+    /// assert_eq!(numbers.to_vec(), vec![1, 1, 2, 2]);
+    /// assert_eq!(numbers.runs_len(), 2);
+    ///
+    /// let labels = RleVec::from(&["1", "x"][..]);
+    /// assert!(labels.try_map(|s| s.parse::<u8>()).is_err());
+    /// ```
+    pub fn try_map<U, E, F>(&self, mut f: F) -> Result<RleVec<U>, E> where U: Eq, F: FnMut(&T) -> Result<U, E> {
+        let mut runs: Vec<InternalRun<U>> = Vec::with_capacity(self.runs.len());
+        for run in &self.runs {
+            let value = f(&run.value)?;
+            match runs.last_mut() {
+                Some(last) if last.value == value => last.end = run.end,
+                _ => runs.push(InternalRun { end: run.end, value }),
             }
-        } else {
-            // split current run
-            self.runs[p].end = index - 1;
-            let v = self.runs[p].value.clone();
-            // this might be more efficient using split_off, push and extend?
-            // this implementation has complexity O((log n) + 2n)
-            self.runs.insert(p + 1, InternalRun { value, end: index });
-            self.runs.insert(p + 2, InternalRun { value: v, end });
         }
+        Ok(RleVec { runs })
     }
 
-    /// Removes and returns the element at position index, shifting all elements after it to the left.
+    /// Builds an index storing the run of every `k`-th element, which bounds the binary search of
+    /// a random access to the runs between two samples. The returned `IndexedRleVec` borrows the
+    /// `RleVec`, so it cannot be mutated while the index is in use.
+    ///
+    /// The index takes about `len / k` words of memory, see
+    /// [`memory_overhead`](struct.IndexedRleVec.html#method.memory_overhead).
     ///
     /// # Panics
-    /// Panics if index is out of bounds.
+    /// Panics if `k` is zero.
     ///
-    /// # Examples
+    /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 1, 1, 4, 4][..]);
+    /// let rle = RleVec::from(&[1, 1, 1, 2, 2, 3, 3, 3, 3][..]);
     ///
-    /// assert_eq!(rle.remove(4), 2);
-    /// assert_eq!(rle.runs_len(), 2);
-    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 4, 4]);
+    /// let indexed = rle.build_access_index(4);
+    /// assert_eq!(indexed[5], 3);
+    /// assert_eq!(indexed.get(2), Some(&1));
+    /// assert_eq!(indexed.get(9), None);
     /// ```
-    pub fn remove(&mut self, index: usize) -> T {
-        let (p, start, end) = self.index_info(index);
-
-        for run in self.runs[p..].iter_mut() {
-            run.end -= 1;
+    pub fn build_access_index(&self, k: usize) -> IndexedRleVec<'_, T> {
+        assert!(k > 0, "the sample interval must be greater than zero");
+        let mut samples = Vec::with_capacity(self.len().div_ceil(k));
+        let mut run_index = 0;
+        for index in (0..self.len()).step_by(k) {
+            while self.runs[run_index].end < index {
+                run_index += 1;
+            }
+            samples.push(run_index);
         }
+        IndexedRleVec { rle: self, k, samples }
+    }
 
-        // if size of the run is 1
-        if end - start == 0 {
-            let InternalRun { value, .. } = self.runs.remove(p); // `p + 1` become p
-            // if value before and after are equal
-            if p > 0 && self.runs_len() > 2 && self.runs[p - 1].value == self.runs[p].value {
-                let after_end = self.runs[p].end;
-                self.runs[p - 1].end = after_end;
-                self.runs.remove(p);
+    /// Returns an iterator over the runs, additionally split at every position in `boundaries`.
+    /// A boundary `b` starts a new run at index `b`. The `RleVec` is not modified and the yielded
+    /// runs tile it exactly, so adjacent runs can hold equal values.
+    ///
+    /// Boundaries at `0` or `len()` and repeated boundaries do not produce empty runs.
+    ///
+    /// # Errors
+    /// Returns a `BoundaryError` if `boundaries` is not sorted or contains a position greater than
+    /// `len()`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let rle = RleVec::from(&[1, 1, 1, 1, 2, 2][..]);
+    ///
+    /// let runs: Vec<_> = rle.runs_split_at(&[2, 5]).unwrap().collect();
+    /// assert_eq!(runs, vec![Run { len: 2, value: &1 }, Run { len: 2, value: &1 },
+    ///                       Run { len: 1, value: &2 }, Run { len: 1, value: &2 }]);
+    /// ```
+    pub fn runs_split_at<'a>(&'a self, boundaries: &'a [usize]) -> Result<impl Iterator<Item=Run<&'a T>> + 'a, BoundaryError> {
+        let len = self.len();
+        for (index, &boundary) in boundaries.iter().enumerate() {
+            if boundary > len {
+                return Err(BoundaryError::OutOfBounds { boundary, len });
+            }
+            if index > 0 && boundary < boundaries[index - 1] {
+                return Err(BoundaryError::Unsorted { index });
             }
-            value
         }
-        else { self.runs[p].value.clone() }
+
+        let segments = boundaries.iter().cloned().chain(once(len)).scan(0, |start, end| {
+            let segment = *start..end;
+            *start = end;
+            Some(segment)
+        });
+        Ok(segments.filter(|segment| segment.start < segment.end)
+            .flat_map(move |segment| self.clipped_runs(segment))
+            .map(|(len, value)| Run { len, value }))
     }
 
-    /// Insert a value at the given index.
+    /// Returns `true` if both `RleVec`s have runs ending at the same indices, regardless of their
+    /// values.
     ///
-    /// Because the positions of the values after the inserted value need to be changed,
-    /// the complexity of this function is **O((log n) + 2n)**.
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[1, 1, 2, 3][..]);
+    /// let b = RleVec::from(&["x", "x", "y", "x"][..]);
+    /// let c = RleVec::from(&[1, 2, 2, 3][..]);
+    ///
+    /// assert!(a.same_run_structure(&b));
+    /// assert!(!a.same_run_structure(&c));
+    /// ```
+    pub fn same_run_structure<U>(&self, other: &RleVec<U>) -> bool {
+        self.runs.len() == other.runs.len()
+            && self.runs.iter().zip(&other.runs).all(|(a, b)| a.end == b.end)
+    }
+
+    /// Returns a reference to the value at the given index, or an `OutOfBoundsError` if the index
+    /// is not smaller than the length of the `RleVec`.
     ///
     /// # Example
     /// ```
     /// # use rle_vec::RleVec;
-    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
     ///
-    /// assert_eq!(rle[2], 1);
-    /// assert_eq!(rle.runs_len(), 3);
+    /// assert_eq!(rle.try_index(2), Ok(&2));
     ///
-    /// rle.insert(2, 3);
-    /// assert_eq!(rle[2], 3);
-    /// assert_eq!(rle.runs_len(), 5);
+    /// let err = rle.try_index(3).unwrap_err();
+    /// assert_eq!(err.index, 3);
+    /// assert_eq!(err.len, 3);
+    /// assert_eq!(err.to_string(), "index 3 out of bounds for RleVec of length 3");
     /// ```
-    pub fn insert(&mut self, index: usize, value: T) {
-        if index == self.len() {
-            return self.push(value);
+    pub fn try_index(&self, index: usize) -> Result<&T, OutOfBoundsError> {
+        match self.try_run_index(index) {
+            Some(p) => Ok(&self.runs[p].value),
+            None => Err(OutOfBoundsError { index, len: self.len() }),
         }
+    }
 
-        let (p, start, end) = self.index_info(index);
-        // increment all run ends from position p
-        for run in self.runs[p..].iter_mut() {
-            run.end += 1;
+    /// Returns a reference to the value at the given index, or `None` if the index is out of
+    /// bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// assert_eq!(rle.get(2), Some(&2));
+    /// assert_eq!(rle.get(3), None);
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.try_run_index(index).map(|p| &self.runs[p].value)
+    }
+
+    /// Returns a reference to the value at the given index, or `default` if the index is out of
+    /// bounds.
+    ///
+    /// This is useful to sample a vector as if it continues with a background value beyond its
+    /// end.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let coverage = RleVec::from(&[0, 3, 3, 1][..]);
+    ///
+    /// let padded: Vec<_> = (0..6).map(|i| *coverage.get_or(i, &0)).collect();
+    /// assert_eq!(padded, vec![0, 3, 3, 1, 0, 0]);
+    /// ```
+    pub fn get_or<'a>(&'a self, index: usize, default: &'a T) -> &'a T {
+        self.get(index).unwrap_or(default)
+    }
+
+    /// Returns a reference to the value at the given index, without bounds checking.
+    ///
+    /// For a safe alternative see [`get`](#method.get).
+    ///
+    /// # Safety
+    /// Calling this method with an index that is not smaller than `len()` is undefined behavior.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// assert_eq!(unsafe { rle.get_unchecked(1) }, &1);
+    /// ```
+    pub unsafe fn get_unchecked(&self, index: usize) -> &T {
+        let p = match self.runs.binary_search_by(|run| run.end.cmp(&index)) {
+            Ok(i) | Err(i) => i,
+        };
+        &self.runs.get_unchecked(p).value
+    }
+
+    fn try_run_index(&self, index: usize) -> Option<usize> {
+        match self.runs.binary_search_by(|run| run.end.cmp(&index)) {
+            Ok(i) => Some(i),
+            Err(i) if i < self.runs.len() => Some(i),
+            _ => None,
         }
+    }
 
-        if self.runs[p].value == value { return }
+    fn run_index(&self, index: usize) -> usize {
+        match self.try_run_index(index) {
+            Some(i) => i,
+            None => panic!("index out of bounds: the len is {} but the index is {}", self.len(), index)
+        }
+    }
 
-        // inserting value can split current run or maybe merge with previous or next
-        if index == start {
-            // compare to previous run
-            if p > 0 && self.runs[p - 1].value == value {
-                self.runs[p - 1].end += 1;
-            } else {
-                self.runs.insert(p, InternalRun { value, end: index });
-            }
-        } else {
-            // split current run
-            self.runs[p].end = index - 1;
-            self.runs.insert(p + 1, InternalRun { value, end: index });
-            let value = self.runs[p].value.clone();
-            self.runs.insert(p + 2, InternalRun { value, end: end + 1 });
+    /// Removes the first `n` elements. Runs before index `n` are dropped, a run containing index
+    /// `n` is trimmed and the ends of the remaining runs are rebased in one pass.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than the length.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2, 2, 3][..]);
+    ///
+    /// rle.drain_front(3);
+    /// assert_eq!(rle.to_vec(), vec![2, 2, 3]);
+    /// assert_eq!(rle.runs_len(), 2);
+    /// ```
+    pub fn drain_front(&mut self, n: usize) {
+        assert!(n <= self.len(), "cannot drain {} elements from RleVec of length {}", n, self.len());
+        if n == 0 { return }
+
+        let dropped = self.runs.partition_point(|run| run.end < n);
+        self.runs.drain(..dropped);
+        for run in &mut self.runs {
+            run.end -= n;
         }
     }
-}
 
-impl<T> Index<usize> for RleVec<T> {
-    type Output = T;
+    /// Shortens the vector, keeping the first `len` elements and dropping the rest.
+    ///
+    /// The runs past `len` are dropped and the run containing the new last element is shortened.
+    /// If `len` is not smaller than the current length this has no effect, truncating to 0 is the
+    /// same as [`clear`](#method.clear).
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// rle.truncate(4);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 2]);
+    /// assert_eq!(rle.runs_len(), 2);
+    ///
+    /// rle.truncate(10);
+    /// assert_eq!(rle.len(), 4);
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() { return }
+        if len == 0 { return self.clear() }
 
-    fn index(&self, index: usize) -> &T {
-        &self.runs[self.run_index(index)].value
+        let p = self.run_index(len - 1);
+        self.runs.truncate(p + 1);
+        self.runs[p].end = len - 1;
     }
-}
 
-impl<T: Clone> Into<Vec<T>> for RleVec<T> {
-    fn into(self) -> Vec<T> {
-        self.to_vec()
+    /// Returns an iterator over the runs overlapping `range` as `(len, value)` pairs, where the
+    /// length is clipped to the range. The range must be within bounds.
+    fn clipped_runs(&self, range: Range<usize>) -> impl Iterator<Item=(usize, &T)> {
+        let first = if range.start < range.end { self.run_index(range.start) } else { self.runs.len() };
+        self.runs[first..].iter().scan(range.start, move |pos, run| {
+            if *pos >= range.end { return None }
+            let stop = cmp::min(run.end + 1, range.end);
+            let len = stop - *pos;
+            *pos = stop;
+            Some((len, &run.value))
+        })
     }
-}
 
-impl<'a, T: Eq + Clone> From<&'a [T]> for RleVec<T> {
-    fn from(slice: &'a [T]) -> Self {
-        if slice.is_empty() {
-            return RleVec::new()
-        }
+    /// Converts any range of indices into a `Range`, panicking with the messages of slices if it
+    /// is not a valid range of indices.
+    fn resolve_range<R>(&self, range: R) -> Range<usize> where R: RangeBounds<usize> {
+        self.try_resolve_range(range).unwrap_or_else(|msg| panic!("{}", msg))
+    }
 
-        let mut runs = Vec::new();
-        let mut last_value = slice[0].clone();
-        for (i, v) in slice[1..].iter().enumerate() {
-            if *v != last_value {
-                runs.push(InternalRun{
-                    end: i,
-                    value: last_value,
-                });
-                last_value = v.clone();
-            }
+    /// Converts any range of indices into a `Range`, or returns the panic message of slices if it
+    /// is not a valid range of indices.
+    fn try_resolve_range<R>(&self, range: R) -> Result<Range<usize>, String> where R: RangeBounds<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).ok_or("attempted to index RleVec from after maximum usize")?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).ok_or("attempted to index RleVec up to maximum usize")?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        if start > end {
+            return Err(format!("slice index starts at {} but ends at {}", start, end))
         }
+        if end > self.len() {
+            return Err(format!("range end index {} out of range for RleVec of length {}", end, self.len()))
+        }
+        Ok(start..end)
+    }
 
-        runs.push(InternalRun{
-            end: slice.len() - 1,
-            value: last_value,
-        });
-
-        RleVec { runs }
+    /// Walks the runs of `self` and `other` simultaneously and calls `f` with the length and the
+    /// values of each segment in which both are constant. Stops and returns `false` as soon as `f`
+    /// returns `false`. If the lengths differ, the walk ends with the shorter vector.
+    fn zip_segments<'a, U, F>(&'a self, other: &'a RleVec<U>, mut f: F) -> bool
+        where F: FnMut(usize, &'a T, &'a U) -> bool
+    {
+        let (mut i, mut j, mut pos) = (0, 0, 0);
+        while i < self.runs.len() && j < other.runs.len() {
+            let (a, b) = (&self.runs[i], &other.runs[j]);
+            let end = cmp::min(a.end, b.end);
+            if !f(end + 1 - pos, &a.value, &b.value) { return false }
+            pos = end + 1;
+            if a.end == end { i += 1 }
+            if b.end == end { j += 1 }
+        }
+        true
     }
-}
 
-impl<T: Eq> FromIterator<T> for RleVec<T> {
-    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=T> {
-        let mut rle = RleVec::new();
-        rle.extend(iter);
-        rle
+    /// Returns the logical start of the run at position `p`, `p` may be equal to the number of runs.
+    fn run_start(&self, p: usize) -> usize {
+        match p {
+            0 => 0,
+            p => self.runs[p - 1].end + 1,
+        }
     }
-}
 
-impl<T: Eq> FromIterator<Run<T>> for RleVec<T> {
-    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=Run<T>> {
-        let iter = iter.into_iter();
-        let (lower, _) = iter.size_hint();
+    // returns true if a run starts at `pos` and another one ends right before it
+    fn is_boundary(&self, pos: usize) -> bool {
+        pos > 0 && pos < self.len() && self.index_info(pos).1 == pos
+    }
 
-        let mut rle = RleVec::with_capacity(lower);
-        rle.extend(iter);
-        rle
+    fn index_info(&self, index: usize) -> (usize, usize, usize) {
+        match self.run_index(index) {
+            0 => (0, 0, self.runs[0].end),
+            index => (index, self.runs[index - 1].end + 1, self.runs[index].end),
+        }
     }
 }
 
-impl<T> Default for RleVec<T> {
+impl<T: Eq> RleVec<T> {
+    /// Appends an element to the back of this rle_vector.
+    ///
+    /// # Panics
+    /// Panics if the number of elements in the vector overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    /// rle.push(1);
+    /// assert_eq!(rle[0], 1);
+    /// ```
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        self.push_n(1, value);
+    }
+
+    /// Appends the same element n times to the back of this rle_vec.
+    ///
+    /// # Panics
+    /// Panics if the number of elements in the vector overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    ///
+    /// // Push 10 times a 2
+    /// rle.push_n(10, 2);
+    /// assert_eq!(rle[9], 2);
+    /// ```
+    pub fn push_n(&mut self, n: usize, value: T) {
+        if n == 0 { return; }
+
+        // the end of the last run is `len - 1`, so a length of `usize::MAX` is the limit
+        let end = self.len().checked_add(n).expect("capacity overflow") - 1;
+
+        match self.runs.last_mut() {
+            Some(last) if last.value == value => last.end = end,
+            _ => self.runs.push(InternalRun { value, end }),
+        }
+    }
+
+    /// Constructs an `RleVec` from a sequence of values and the number of times each value is
+    /// repeated. This is the inverse of [`to_change_sequence`](#method.to_change_sequence)
+    /// combined with the run lengths. Zero lengths are skipped and equal neighbours are merged.
+    ///
+    /// # Panics
+    /// Panics if `values` and `lengths` yield a different number of items.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 2, 1][..]);
+    ///
+    /// let lengths = rle.runs().map(|run| run.len);
+    /// let copy = RleVec::from_changes_with_lengths(rle.to_change_sequence(), lengths);
+    /// assert_eq!(copy, rle);
+    /// ```
+    pub fn from_changes_with_lengths<I, L>(values: I, lengths: L) -> RleVec<T>
+        where I: IntoIterator<Item=T>, L: IntoIterator<Item=usize>
+    {
+        let mut rle = RleVec::new();
+        let mut values = values.into_iter();
+        let mut lengths = lengths.into_iter();
+        loop {
+            match (values.next(), lengths.next()) {
+                (Some(value), Some(len)) => rle.push_n(len, value),
+                (None, None) => return rle,
+                _ => panic!("values and lengths have different lengths"),
+            }
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true` and returns the number of removed
+    /// elements.
+    ///
+    /// The predicate is called once per run and rejected runs are dropped as a whole, the
+    /// complexity is **O(n)** in the number of runs. Runs that become adjacent and equal are
+    /// merged, see [`retain_map_runs`](#method.retain_map_runs).
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[0, 0, 1, 1, 0, 0, 0, 1, 2][..]);
+    ///
+    /// assert_eq!(rle.retain(|&value| value != 0), 5);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 2]);
+    /// assert_eq!(rle.runs_len(), 2);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F) -> usize where F: FnMut(&T) -> bool {
+        let len = self.len();
+        self.retain_map_runs(|value, _| f(value));
+        len - self.len()
+    }
+
+    /// Calls `f` with a mutable reference to the value and the length of every run, and keeps only
+    /// the runs for which it returns `true`. The remaining runs are rebased and adjacent runs that
+    /// became equal are merged, all in one pass.
+    ///
+    /// If `f` panics the `RleVec` keeps the runs that were processed before the panic and is still
+    /// valid.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 3, 3, 3, 4][..]);
+    ///
+    /// // drop single elements and round the rest down to even numbers
+    /// rle.retain_map_runs(|value, len| {
+    ///     *value -= *value % 2;
+    ///     len > 1
+    /// });
+    /// assert_eq!(rle.to_vec(), vec![0, 0, 2, 2, 2]);
+    /// ```
+    pub fn retain_map_runs<F>(&mut self, mut f: F) where F: FnMut(&mut T, usize) -> bool {
+        let runs = mem::take(&mut self.runs);
+        let mut last_end = 0;
+        for run in runs {
+            let len = run.end + 1 - last_end;
+            last_end = run.end + 1;
+            let mut value = run.value;
+            if f(&mut value, len) {
+                self.push_n(len, value);
+            }
+        }
+    }
+
+    /// Appends an element to the back of this rle_vec unless that would create more than
+    /// `max_runs` runs. The `RleVec` is not modified if an error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    ///
+    /// assert!(rle.push_bounded(1, 1).is_ok());
+    /// assert!(rle.push_bounded(1, 1).is_ok());
+    /// assert!(rle.push_bounded(2, 1).is_err());
+    /// assert_eq!(rle.to_vec(), vec![1, 1]);
+    /// ```
+    pub fn push_bounded(&mut self, value: T, max_runs: usize) -> Result<(), TooManyRuns> {
+        let new_run = self.runs.last().map(|last| &last.value) != Some(&value);
+        if new_run && self.runs.len() >= max_runs {
+            return Err(TooManyRuns { max_runs, len: self.len() });
+        }
+        self.push(value);
+        Ok(())
+    }
+
+    /// Constructs an `RleVec` from an iterator, but stops as soon as more than `max_runs` runs
+    /// would be needed. This guards against untrusted input that does not compress.
+    ///
+    /// # Errors
+    /// Returns `TooManyRuns` with the number of elements consumed before the limit was hit.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::try_from_iter("aaabbb".chars(), 2).unwrap();
+    /// assert_eq!(rle.runs_len(), 2);
+    ///
+    /// let err = RleVec::try_from_iter("aaabbbc".chars(), 2).unwrap_err();
+    /// assert_eq!(err.len, 6);
+    /// ```
+    pub fn try_from_iter<I>(iter: I, max_runs: usize) -> Result<RleVec<T>, TooManyRuns> where I: IntoIterator<Item=T> {
+        let mut rle = RleVec::new();
+        for value in iter {
+            rle.push_bounded(value, max_runs)?;
+        }
+        Ok(rle)
+    }
+
+    /// Constructs two `RleVec`s from an iterator of pairs in a single pass, the first holding the
+    /// first components and the second the second components.
+    ///
+    /// Both outputs have the same length, but are run length encoded independently. This is
+    /// usually much more compact than an `RleVec` of the pairs, which starts a new run whenever
+    /// either component changes.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let events = vec![(0, 'a'), (0, 'b'), (1, 'b'), (1, 'b'), (2, 'b')];
+    /// let (buckets, states) = RleVec::unzip_from_iter(events);
+    ///
+    /// assert_eq!(buckets.to_vec(), vec![0, 0, 1, 1, 2]);
+    /// assert_eq!(buckets.runs_len(), 3);
+    /// assert_eq!(states.to_vec(), vec!['a', 'b', 'b', 'b', 'b']);
+    /// assert_eq!(states.runs_len(), 2);
+    /// ```
+    pub fn unzip_from_iter<U: Eq, I>(iter: I) -> (RleVec<T>, RleVec<U>) where I: IntoIterator<Item=(T, U)> {
+        let mut a = RleVec::new();
+        let mut b = RleVec::new();
+        for (x, y) in iter {
+            a.push(x);
+            b.push(y);
+        }
+        (a, b)
+    }
+
+    /// Resizes the `RleVec` in-place so that `len()` is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than `len()`, the `RleVec` is extended by the difference, with each
+    /// additional value generated by calling the closure `f`. Equal generated values are merged
+    /// into runs. If `new_len` is less than `len()`, the `RleVec` is truncated.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// let mut counter = 2;
+    /// rle.resize_with(6, || { counter += 1; counter / 2 });
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 2, 1, 2, 2]);
+    /// assert_eq!(rle.runs_len(), 4);
+    ///
+    /// rle.resize_with(2, || 0);
+    /// assert_eq!(rle.to_vec(), vec![1, 1]);
+    /// ```
+    pub fn resize_with<F>(&mut self, new_len: usize, f: F) where F: FnMut() -> T {
+        let len = self.len();
+        if new_len > len {
+            self.extend(repeat_with(f).take(new_len - len));
+        } else {
+            self.truncate(new_len);
+        }
+    }
+
+    /// Resizes the `RleVec` in-place so that `len()` is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than `len()`, the `RleVec` is extended with `value`, which is merged
+    /// into the last run if it holds the same value. If `new_len` is less than `len()`, the
+    /// `RleVec` is truncated and `value` is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut mask = RleVec::from(&[true, false][..]);
+    ///
+    /// mask.resize(1000, false);
+    /// assert_eq!(mask.len(), 1000);
+    /// assert_eq!(mask.runs_len(), 2);
+    ///
+    /// mask.resize(1, false);
+    /// assert_eq!(mask.to_vec(), vec![true]);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        let len = self.len();
+        if new_len > len {
+            self.push_n(new_len - len, value);
+        } else {
+            self.truncate(new_len);
+        }
+    }
+
+    /// Returns `true` if the runs are in canonical form: every run has a length of at least one and
+    /// adjacent runs hold different values. All methods of `RleVec` maintain this form, see
+    /// [Guarantees](struct.RleVec.html#guarantees).
+    ///
+    /// The complexity is **O(n)** in the number of runs.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+    /// assert!(rle.is_canonical());
+    /// ```
+    pub fn is_canonical(&self) -> bool {
+        self.check_canonical().is_ok()
+    }
+
+    /// Checks the internal invariants of the `RleVec`: every run has a length of at least one and
+    /// adjacent runs hold different values.
+    ///
+    /// # Panics
+    /// Panics if any of the invariants is violated.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+    /// rle.assert_valid();
+    /// ```
+    pub fn assert_valid(&self) {
+        if let Err(err) = self.check_canonical() {
+            panic!("{}", err);
+        }
+    }
+
+    // returns the first violation of the canonical form
+    fn check_canonical(&self) -> Result<(), NotCanonical> {
+        check_canonical_runs(&self.runs)
+    }
+
+    /// Removes the runs with an index in `run_range` and returns them as an iterator of owned
+    /// runs. The runs before and after the removed range are merged if their values are equal.
+    ///
+    /// Like `Vec::drain` the iterator borrows the `RleVec` and moves the runs out one at a time.
+    /// The runs in the range are removed when the iterator is dropped, whether or not it was
+    /// consumed. The remaining runs are already shifted and merged when `drain_runs` returns, so
+    /// leaking the iterator with `mem::forget` leaks the drained runs but leaves a valid `RleVec`.
+    ///
+    /// # Panics
+    /// Panics if `run_range.start > run_range.end` or if `run_range.end > runs_len()`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let mut rle = RleVec::from(&[1, 1, 2, 3, 3, 3, 1][..]);
+    ///
+    /// let drained: Vec<_> = rle.drain_runs(1..3).collect();
+    /// assert_eq!(drained, vec![Run { len: 1, value: 2 }, Run { len: 3, value: 3 }]);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1]);
+    /// assert_eq!(rle.runs_len(), 1);
+    /// ```
+    pub fn drain_runs(&mut self, run_range: Range<usize>) -> DrainRuns<'_, T> {
+        let kept = self.detach_runs(run_range);
+        DrainRuns { iter: self.runs.drain(kept..), start: 0 }
+    }
+
+    /// Removes the runs in `run_range` from the sequence and moves them behind the remaining runs,
+    /// with their ends relative to the start of the range. The neighbours of the range are merged
+    /// if their values are equal. Returns the number of remaining runs.
+    fn detach_runs(&mut self, run_range: Range<usize>) -> usize {
+        assert!(run_range.start <= run_range.end,
+                "run range starts at {} but ends at {}", run_range.start, run_range.end);
+        assert!(run_range.end <= self.runs.len(),
+                "run range end {} out of range for {} runs", run_range.end, self.runs.len());
+
+        let Range { start, end } = run_range;
+        if start == end { return self.runs.len() }
+        let first = self.run_start(start);
+        let removed = self.runs[end - 1].end + 1 - first;
+        for run in self.runs[start..end].iter_mut() {
+            run.end -= first;
+        }
+        for run in self.runs[end..].iter_mut() {
+            run.end -= removed;
+        }
+        self.runs[start..].rotate_left(end - start);
+
+        let mut kept = self.runs.len() - (end - start);
+        if start > 0 && start < kept && self.runs[start - 1].value == self.runs[start].value {
+            self.runs.remove(start - 1);
+            kept -= 1;
+        }
+        kept
+    }
+
+    /// Extends the last run by `n` elements. There must be a last run.
+    fn extend_last(&mut self, n: usize) {
+        let last = self.runs.last_mut().unwrap();
+        last.end += n;
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// The runs are moved, not the elements: the complexity is **O(r)** in the number of runs of
+    /// `other`. The last run of `self` and the first run of `other` are merged when they hold the
+    /// same value.
+    ///
+    /// # Panics
+    /// Panics if the number of elements overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut chr1 = RleVec::from(&[0, 0, 5, 5][..]);
+    /// let mut chr2 = RleVec::from(&[5, 5, 0][..]);
+    ///
+    /// chr1.append(&mut chr2);
+    /// assert_eq!(chr1.to_vec(), vec![0, 0, 5, 5, 5, 5, 0]);
+    /// assert_eq!(chr1.runs_len(), 3);
+    /// assert!(chr2.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut RleVec<T>) {
+        self.len().checked_add(other.len()).expect("capacity overflow");
+        self.append_owned(mem::take(other));
+    }
+
+    /// Moves the runs of `other` to the back of this rle_vec, merging the boundary runs when their
+    /// values are equal.
+    fn append_owned(&mut self, other: RleVec<T>) {
+        let offset = self.len();
+        let mut runs = other.runs.into_iter();
+        if let Some(first) = runs.next() {
+            match self.runs.last_mut() {
+                Some(last) if last.value == first.value => last.end = first.end + offset,
+                _ => self.runs.push(InternalRun { end: first.end + offset, value: first.value }),
+            }
+            self.runs.extend(runs.map(|r| InternalRun { end: r.end + offset, value: r.value }));
+        }
+    }
+}
+
+impl<T: Clone> RleVec<T> {
+    /// Returns a clone of the value at the given index, or `None` if the index is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&["a", "b", "b"][..]);
+    ///
+    /// assert_eq!(rle.get_cloned(1), Some("b"));
+    /// assert_eq!(rle.get_cloned(3), None);
+    /// ```
+    pub fn get_cloned(&self, index: usize) -> Option<T> {
+        self.get(index).cloned()
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is empty.
+    ///
+    /// The last run is shortened by one, which clones its value, or removed when it has a length
+    /// of one, which moves the value out. The complexity is **O(1)**.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 2, 2][..]);
+    ///
+    /// assert_eq!(rle.pop(), Some(2));
+    /// assert_eq!(rle.pop(), Some(2));
+    /// assert_eq!(rle.runs_len(), 1);
+    /// assert_eq!(rle.pop(), Some(1));
+    /// assert_eq!(rle.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let p = self.runs.len().checked_sub(1)?;
+        if self.run_start(p) == self.runs[p].end {
+            return self.runs.pop().map(|run| run.value)
+        }
+        self.runs[p].end -= 1;
+        Some(self.runs[p].value.clone())
+    }
+
+    /// Splits the vector into two at the given index.
+    ///
+    /// Returns a newly allocated `RleVec` containing the elements `[at, len)`, `self` keeps the
+    /// elements `[0, at)`. A run containing both `at - 1` and `at` is split, which clones its value.
+    /// The runs after it are moved and their ends rebased, the complexity is **O(log n + r)**
+    /// where r is the number of moved runs.
+    ///
+    /// # Panics
+    /// Panics if `at > len`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// let tail = rle.split_off(2);
+    /// assert_eq!(rle.to_vec(), vec![1, 1]);
+    /// assert_eq!(tail.to_vec(), vec![1, 2, 2, 3]);
+    /// assert_eq!(tail.runs_len(), 3);
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> RleVec<T> {
+        let len = self.len();
+        assert!(at <= len, "`at` split index (is {}) should be <= len (is {})", at, len);
+        if at == len {
+            return RleVec::new()
+        }
+
+        let p = self.run_index(at);
+        let start = self.run_start(p);
+        let mut tail = self.runs.split_off(p);
+        if start < at {
+            self.runs.push(InternalRun { end: at - 1, value: tail[0].value.clone() });
+        }
+        for run in &mut tail {
+            run.end -= at;
+        }
+        RleVec { runs: tail }
+    }
+
+    /// Copies the elements in `range` into a new `RleVec`. The range must be within bounds.
+    fn sub_rle(&self, range: Range<usize>) -> RleVec<T> {
+        let mut end = 0;
+        let runs = self.clipped_runs(range).map(|(len, value)| {
+            end += len;
+            InternalRun { end: end - 1, value: value.clone() }
+        }).collect();
+        RleVec { runs }
+    }
+
+    /// Returns an iterator over the runs that yields owned runs with cloned values.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let rle = RleVec::from(&["a", "a", "b"][..]);
+    ///
+    /// let mut runs = rle.runs_cloned();
+    /// assert_eq!(runs.len(), 2);
+    /// assert_eq!(runs.next_back(), Some(Run { len: 1, value: "b" }));
+    /// assert_eq!(runs.next(), Some(Run { len: 2, value: "a" }));
+    ///
+    /// let copy: RleVec<&str> = rle.runs_cloned().collect();
+    /// assert_eq!(copy, rle);
+    /// ```
+    pub fn runs_cloned(&self) -> impl DoubleEndedIterator<Item=Run<T>> + ExactSizeIterator + '_ {
+        (0..self.runs.len()).map(move |p| {
+            let run = &self.runs[p];
+            Run { len: run.end + 1 - self.run_start(p), value: run.value.clone() }
+        })
+    }
+
+    /// Returns the value of every run as a `Vec<T>`, which is the `dedup` of the values.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 2, 1][..]);
+    ///
+    /// assert_eq!(rle.to_change_sequence(), vec![1, 2, 1]);
+    /// ```
+    pub fn to_change_sequence(&self) -> Vec<T> {
+        self.change_sequence().cloned().collect()
+    }
+
+    /// Construct a `Vec<T>` from this `RleVec`.
+    ///
+    /// The values of the `RleVec` are cloned to produce the final `Vec`.
+    /// This can be usefull for debugging.
+    ///
+    /// The `Vec` holds `len()` elements, which can be far more than the number of runs suggests.
+    /// If the allocation fails the process aborts, use [`try_to_vec`](#method.try_to_vec) for
+    /// untrusted input.
+    ///
+    /// # Panics
+    /// Panics if `len()` elements take more than `isize::MAX` bytes.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let slice = &[0, 0, 0, 1, 1, 99, 9];
+    /// let rle = RleVec::from(&slice[..]);
+    /// let vec = rle.to_vec();
+    ///
+    /// assert_eq!(vec.as_slice(), slice);
+    /// ```
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut res = Vec::with_capacity(self.len());
+        self.extend_vec(&mut res);
+        res
+    }
+
+    /// Construct a `Vec<T>` from this `RleVec` if its length is at most `max_len`.
+    ///
+    /// The length is checked before anything is allocated, and the allocation itself uses
+    /// `try_reserve_exact` so running out of memory is reported as an error instead of aborting.
+    ///
+    /// # Errors
+    /// Returns `ToVecError::TooLong` if the length exceeds `max_len` and `ToVecError::Alloc` if
+    /// the allocation fails.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, ToVecError};
+    /// let mut rle = RleVec::new();
+    /// rle.push_n(3, 1u8);
+    /// assert_eq!(rle.try_to_vec(10), Ok(vec![1, 1, 1]));
+    ///
+    /// rle.push_n(1_000_000_000, 2u8);
+    /// assert_eq!(rle.try_to_vec(10), Err(ToVecError::TooLong { len: 1_000_000_003, max_len: 10 }));
+    /// ```
+    pub fn try_to_vec(&self, max_len: usize) -> Result<Vec<T>, ToVecError> {
+        let len = self.len();
+        if len > max_len {
+            return Err(ToVecError::TooLong { len, max_len });
+        }
+        let mut res = Vec::new();
+        res.try_reserve_exact(len).map_err(ToVecError::Alloc)?;
+        self.extend_vec(&mut res);
+        Ok(res)
+    }
+
+    /// Decodes this `RleVec` into an existing `Vec<T>`, reusing its allocation.
+    ///
+    /// The vector is cleared first, so after the call it contains exactly the values of the
+    /// `RleVec`. No allocation takes place when the capacity of `out` suffices.
+    ///
+    /// # Panics
+    /// Panics if `len()` elements take more than `isize::MAX` bytes. Like for
+    /// [`to_vec`](#method.to_vec), running out of memory aborts the process.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut buffer = vec![9; 10];
+    ///
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    /// rle.to_vec_into(&mut buffer);
+    /// assert_eq!(buffer, vec![1, 1, 2]);
+    /// assert!(buffer.capacity() >= 10);
+    /// ```
+    pub fn to_vec_into(&self, out: &mut Vec<T>) {
+        out.clear();
+        self.extend_vec(out);
+    }
+
+    /// Appends the values of this `RleVec` to the back of a `Vec<T>`.
+    ///
+    /// # Panics
+    /// Panics if the length of `out` plus `len()` overflows a usize or the elements take more than
+    /// `isize::MAX` bytes. Like for [`to_vec`](#method.to_vec), running out of memory aborts the
+    /// process.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut v = vec![0];
+    ///
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    /// rle.extend_vec(&mut v);
+    /// assert_eq!(v, vec![0, 1, 1, 2]);
+    /// ```
+    pub fn extend_vec(&self, out: &mut Vec<T>) {
+        out.reserve(self.len());
+        let mut p = 0;
+        for r in &self.runs {
+            let n = r.end - p + 1;
+            out.resize(out.len() + n, r.value.clone());
+            p += n;
+        }
+    }
+
+    /// Returns the sum of the values in `range`, computed as the sum of `value * len` over the runs
+    /// overlapping the range. The sum is accumulated in `S`, which can be a wider type than `T`
+    /// to prevent overflow. `S` must be convertible from `usize`, which excludes floating point
+    /// types.
+    ///
+    /// The complexity is **O(log n + r)** where r is the number of runs in the range.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds or a run length cannot be represented in `S`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    /// rle.push_n(1000, 200u8);
+    /// rle.push_n(1000, 100u8);
+    ///
+    /// assert_eq!(rle.range_sum::<u64>(990..1010), 3000);
+    /// assert_eq!(rle.range_sum::<i64>(0..0), 0);
+    /// ```
+    pub fn range_sum<S>(&self, range: impl RangeBounds<usize>) -> S
+        where T: Into<S>, S: Sum + Mul<Output=S> + TryFrom<usize>
+    {
+        let range = self.resolve_range(range);
+        self.clipped_runs(range).map(|(len, value)| {
+            let len = S::try_from(len).ok().expect("run length does not fit in the sum type");
+            value.clone().into() * len
+        }).sum()
+    }
+
+    /// Decodes the values in `range` into the caller provided slice `out`.
+    ///
+    /// The slice is filled run by run, no allocation takes place.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds or if the length of `out` differs from the length of
+    /// the range.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// let mut buffer = [0; 3];
+    /// rle.copy_to_slice(2..5, &mut buffer);
+    /// assert_eq!(buffer, [1, 2, 2]);
+    /// ```
+    pub fn copy_to_slice<R>(&self, range: R, out: &mut [T]) where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        assert_eq!(out.len(), range.end - range.start, "destination and range have different lengths");
+        if range.start == range.end { return }
+
+        let mut pos = 0;
+        for (len, value) in self.clipped_runs(range) {
+            out[pos..pos + len].fill(value.clone());
+            pos += len;
+        }
+    }
+
+    /// Copies the elements in `range` into a new `RleVec`. The range can be given in any form,
+    /// like `a..b`, `a..=b`, `a..`, `..b` or `..`.
+    ///
+    /// The values are cloned once per run overlapping the range.
+    ///
+    /// # Panics
+    /// Panics if the start of the range is greater than its end or if the end is greater than the
+    /// length, with the messages of slices.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle.slice(1..4).to_vec(), vec![1, 2, 2]);
+    /// assert_eq!(rle.slice(..=1).to_vec(), vec![1, 1]);
+    /// assert_eq!(rle.slice(3..).runs_len(), 2);
+    /// assert_eq!(rle.slice(..), rle);
+    /// ```
+    pub fn slice<R>(&self, range: R) -> RleVec<T> where R: RangeBounds<usize> {
+        self.sub_rle(self.resolve_range(range))
+    }
+
+    /// Copies the elements in `range` into a new `RleVec` like [`slice`](#method.slice), or
+    /// returns `None` if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// assert_eq!(rle.slice_checked(1..).map(|s| s.to_vec()), Some(vec![1, 2]));
+    /// assert_eq!(rle.slice_checked(2..4), None);
+    /// assert_eq!(rle.slice_checked(..=usize::MAX), None);
+    /// ```
+    pub fn slice_checked<R>(&self, range: R) -> Option<RleVec<T>> where R: RangeBounds<usize> {
+        let range = self.try_resolve_range(range).ok()?;
+        Some(self.sub_rle(range))
+    }
+
+    /// Splits the `RleVec` into `n` contiguous pieces of (almost) equal length.
+    ///
+    /// The pieces are built by copying whole runs, only the runs that straddle a cut point are
+    /// split. All pieces have a length of `len / n` or `len / n + 1`, the longer pieces come first.
+    /// When `n` is larger than the length of the `RleVec` the trailing pieces are empty.
+    /// Concatenating the pieces results in the original `RleVec`.
+    ///
+    /// # Panics
+    /// Panics if `n` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 1, 1, 1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// let pieces = rle.partition_chunks(3);
+    /// assert_eq!(pieces.len(), 3);
+    /// assert_eq!(pieces[0].to_vec(), vec![1, 1, 1, 1]);
+    /// assert_eq!(pieces[1].to_vec(), vec![1, 1, 1]);
+    /// assert_eq!(pieces[2].to_vec(), vec![2, 2, 3]);
+    /// ```
+    pub fn partition_chunks(&self, n: usize) -> Vec<RleVec<T>> {
+        assert!(n != 0, "number of chunks must be non-zero");
+
+        let (size, extra) = (self.len() / n, self.len() % n);
+        let mut chunks = Vec::with_capacity(n);
+        let mut runs = self.runs.iter().peekable();
+        let mut start = 0;
+
+        for i in 0..n {
+            let end = start + size + if i < extra { 1 } else { 0 };
+            let mut chunk = RleVec::new();
+            let mut pos = start;
+            while pos < end {
+                let run = runs.peek().expect("runs cover the complete length");
+                let run_end = cmp::min(run.end + 1, end);
+                chunk.runs.push(InternalRun { end: run_end - start - 1, value: run.value.clone() });
+                if run_end == run.end + 1 {
+                    runs.next();
+                }
+                pos = run_end;
+            }
+            chunks.push(chunk);
+            start = end;
+        }
+        chunks
+    }
+}
+
+impl<T: Eq + Clone> RleVec<T> {
+    /// Modify the value at given index.
+    ///
+    /// This can result in the breaking of a run and therefore be an expensive operation.
+    /// If the value is equal to the value currently present the complexity is
+    /// **O(log n)**. But if the run needs to be broken the complexity increases to a worst case of
+    /// **O((log n) + n)**.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle[2], 1);
+    /// assert_eq!(rle.len(), 7);
+    /// assert_eq!(rle.runs_len(), 3);
+    ///
+    /// rle.set(2, 3);
+    /// assert_eq!(rle[2], 3);
+    /// assert_eq!(rle.len(), 7);
+    /// assert_eq!(rle.runs_len(), 5);
+    /// ```
+    pub fn set(&mut self, index: usize, value: T) {
+        let (p, start, end) = self.index_info(index);
+        self.set_in_run(p, start, end, index, value);
+    }
+
+    /// Modify the value at given index and return the value previously stored there.
+    ///
+    /// The complexity is the same as for [`set`](#method.set). If the new value equals the old
+    /// one, or the index is in a run of length one, no clone is made. Otherwise the old value
+    /// is cloned from its run, because the run stays in the `RleVec`.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle.replace(2, 3), 1);
+    /// assert_eq!(rle.replace(6, 2), 3);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 3, 1, 2, 2, 2]);
+    /// ```
+    pub fn replace(&mut self, index: usize, value: T) -> T {
+        let (p, start, end) = self.index_info(index);
+        if self.runs[p].value == value { return value }
+
+        let old = if start == end { None } else { Some(self.runs[p].value.clone()) };
+        match self.set_in_run(p, start, end, index, value) {
+            Some(value) => value,
+            None => old.unwrap(),
+        }
+    }
+
+    /// Modify every value in `range`. The runs at the boundaries of the range are split, the runs
+    /// inside the range are replaced by a single run that is merged with equal neighbours.
+    ///
+    /// The complexity is **O(log n + r)** where r is the number of runs in the range, plus the cost
+    /// of moving the runs after the range if the number of runs changes.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 3, 3, 4][..]);
+    ///
+    /// rle.set_range(1..5, 2);
+    /// assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 2, 4]);
+    /// assert_eq!(rle.runs_len(), 3);
+    /// ```
+    pub fn set_range<R>(&mut self, range: R, value: T) where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        self.try_map_range(range, |_| Some(value.clone()));
+    }
+
+    // sets the value at `index` in run `p` spanning `start..=end`, the old value is returned if
+    // its run was removed or overwritten
+    fn set_in_run(&mut self, mut p: usize, start: usize, end: usize, index: usize, value: T) -> Option<T> {
+        if self.runs[p].value == value { return None }
+
+        // a size 1 run is replaced with the new value or joined with next or previous
+        if end - start == 0 {
+            // can we join the previous run?
+            if p > 0 && self.runs[p - 1].value == value {
+                let old = self.runs.remove(p).value;
+                self.runs[p - 1].end += 1;
+                p -= 1;
+                // can we also join the next run?
+                if p < self.runs.len() - 1 && self.runs[p + 1].value == value {
+                    self.runs.remove(p);
+                }
+                return Some(old);
+            }
+            // can we join the next run?
+            if p < self.runs.len() - 1 && self.runs[p + 1].value == value {
+                return Some(self.runs.remove(p).value);
+            }
+            // only one size-1 run in Rle replace its value
+            return Some(mem::replace(&mut self.runs[p].value, value));
+        }
+
+        // run size > 1, new value can split current run or maybe merge with previous or next
+        if index == start {
+            // compare to previous run
+            if p > 0 {
+                if self.runs[p - 1].value == value {
+                    self.runs[p - 1].end += 1;
+                } else {
+                    self.runs.insert(p, InternalRun { value, end: start });
+                }
+            } else {
+                self.runs.insert(0, InternalRun { value, end: 0 });
+            }
+        } else if index == end {
+            // decrease current run length
+            self.runs[p].end -= 1;
+
+            // compare to next run
+            if p < self.runs.len() - 1 && self.runs[p + 1].value == value {
+            } else {
+                self.runs.insert(p + 1, InternalRun { value, end });
+            }
+        } else {
+            // split current run
+            self.runs[p].end = index - 1;
+            let v = self.runs[p].value.clone();
+            // this might be more efficient using split_off, push and extend?
+            // this implementation has complexity O((log n) + 2n)
+            self.runs.insert(p + 1, InternalRun { value, end: index });
+            self.runs.insert(p + 2, InternalRun { value: v, end });
+        }
+        None
+    }
+
+    /// Removes and returns the element at position index, shifting all elements after it to the left.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 1, 1, 4, 4][..]);
+    ///
+    /// assert_eq!(rle.remove(4), 2);
+    /// assert_eq!(rle.runs_len(), 2);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 4, 4]);
+    /// ```
+    pub fn remove(&mut self, index: usize) -> T {
+        let (p, start, end) = self.index_info(index);
+
+        // if size of the run is 1
+        if end - start == 0 {
+            let InternalRun { value, .. } = self.runs.remove(p); // `p + 1` become p
+            for run in self.runs[p..].iter_mut() {
+                run.end -= 1;
+            }
+            // if value before and after are equal
+            if p > 0 && p < self.runs_len() && self.runs[p - 1].value == self.runs[p].value {
+                let after_end = self.runs[p].end;
+                self.runs[p - 1].end = after_end;
+                self.runs.remove(p);
+            }
+            value
+        } else {
+            for run in self.runs[p..].iter_mut() {
+                run.end -= 1;
+            }
+            self.runs[p].value.clone()
+        }
+    }
+
+    /// Merges two sparse vectors of equal length in which `default` marks the absence of a value.
+    ///
+    /// Where both vectors hold a value the value of `self` is used, where only one holds a value
+    /// that value is used and where neither does the result is `default`.
+    ///
+    /// # Panics
+    /// Panics if the vectors have different lengths.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[0, 1, 1, 0, 0, 0][..]);
+    /// let b = RleVec::from(&[0, 0, 2, 2, 0, 3][..]);
+    ///
+    /// assert_eq!(a.sparse_union(&b, &0).to_vec(), vec![0, 1, 1, 2, 0, 3]);
+    /// ```
+    pub fn sparse_union(&self, other: &RleVec<T>, default: &T) -> RleVec<T> {
+        self.sparse_combine(other, |a, b| if a != default { a } else { b })
+    }
+
+    /// Keeps the values of `self` where both sparse vectors hold a value, see
+    /// [`sparse_union`](#method.sparse_union).
+    ///
+    /// # Panics
+    /// Panics if the vectors have different lengths.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[0, 1, 1, 0, 0, 0][..]);
+    /// let b = RleVec::from(&[0, 0, 2, 2, 0, 3][..]);
+    ///
+    /// assert_eq!(a.sparse_intersection(&b, &0).to_vec(), vec![0, 0, 1, 0, 0, 0]);
+    /// ```
+    pub fn sparse_intersection(&self, other: &RleVec<T>, default: &T) -> RleVec<T> {
+        self.sparse_combine(other, |a, b| if b != default { a } else { default })
+    }
+
+    /// Keeps the values of `self` where `other` holds no value, see
+    /// [`sparse_union`](#method.sparse_union).
+    ///
+    /// # Panics
+    /// Panics if the vectors have different lengths.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[0, 1, 1, 0, 0, 0][..]);
+    /// let b = RleVec::from(&[0, 0, 2, 2, 0, 3][..]);
+    ///
+    /// assert_eq!(a.sparse_difference(&b, &0).to_vec(), vec![0, 1, 0, 0, 0, 0]);
+    /// ```
+    pub fn sparse_difference(&self, other: &RleVec<T>, default: &T) -> RleVec<T> {
+        self.sparse_combine(other, |a, b| if b == default { a } else { default })
+    }
+
+    fn sparse_combine<'a, F>(&'a self, other: &'a RleVec<T>, mut f: F) -> RleVec<T>
+        where F: FnMut(&'a T, &'a T) -> &'a T
+    {
+        assert_eq!(self.len(), other.len(), "vectors have different lengths");
+        let mut res = RleVec::new();
+        let mut pos = 0;
+        self.zip_segments(other, |len, a, b| {
+            let value = f(a, b);
+            pos += len;
+            match res.runs.last_mut() {
+                Some(ref mut last) if last.value == *value => last.end = pos - 1,
+                _ => res.runs.push(InternalRun { end: pos - 1, value: value.clone() }),
+            }
+            true
+        });
+        debug_assert_eq!(pos, self.len());
+        res
+    }
+
+    /// Exchanges the values in two non-overlapping ranges of equal length.
+    ///
+    /// Only the runs overlapping the ranges and their direct neighbors are rebuilt, the runs
+    /// before and after are untouched.
+    ///
+    /// # Panics
+    /// Panics if the ranges overlap, have different lengths or are out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2, 3, 3, 4, 4][..]);
+    ///
+    /// rle.swap_ranges(0..2, 5..7);
+    /// assert_eq!(rle.to_vec(), vec![3, 4, 2, 2, 3, 1, 1, 4]);
+    /// ```
+    pub fn swap_ranges<A, B>(&mut self, a: A, b: B) where A: RangeBounds<usize>, B: RangeBounds<usize> {
+        let (a, b) = (self.resolve_range(a), self.resolve_range(b));
+        assert_eq!(a.end - a.start, b.end - b.start, "ranges have different lengths");
+        if a.start == a.end { return }
+
+        let (lo, hi) = if a.start <= b.start { (a, b) } else { (b, a) };
+        assert!(lo.end <= hi.start, "ranges {:?} and {:?} overlap", lo, hi);
+
+        // rebuild the affected runs including their neighbors, which makes merging at the seams
+        // of the rebuilt part unnecessary
+        let first = self.run_index(lo.start).saturating_sub(1);
+        let last = cmp::min(self.run_index(hi.end - 1) + 1, self.runs.len() - 1);
+        let (start, end) = (self.run_start(first), self.runs[last].end + 1);
+
+        let mut rebuilt = RleVec::new();
+        for range in [start..lo.start, hi.clone(), lo.end..hi.start, lo, hi.end..end] {
+            for (len, value) in self.clipped_runs(range) {
+                rebuilt.push_n(len, value.clone());
+            }
+        }
+        let runs = rebuilt.runs.into_iter().map(|r| InternalRun { end: r.end + start, value: r.value });
+        self.runs.splice(first..last + 1, runs);
+    }
+
+    /// Returns a smoothed copy in which every run shorter than `min_len` is absorbed into one of its
+    /// neighbours, which takes over its elements.
+    ///
+    /// The runs are processed in a single pass from front to back. A short run is absorbed into
+    /// the longer of the previous (already smoothed) run and the next run, preferring the previous
+    /// run on ties. The first run has no previous run and is absorbed into the next one, the last
+    /// run into the previous one. Runs shorter than `min_len` only remain if the whole vector is
+    /// shorter than `min_len`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 0, 0, 1, 0, 0, 0, 2, 2, 2, 2, 3][..]);
+    ///
+    /// let smooth = rle.smooth_min_run(2);
+    /// assert_eq!(smooth.to_vec(), vec![0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 2, 2, 2]);
+    /// ```
+    pub fn smooth_min_run(&self, min_len: usize) -> RleVec<T> {
+        let mut smooth = RleVec::with_run_capacity(self.runs.len());
+        let mut carry = 0;
+        let mut runs = self.runs().peekable();
+        while let Some(run) = runs.next() {
+            let len = run.len + carry;
+            carry = 0;
+            if len >= min_len {
+                smooth.push_n(len, run.value.clone());
+                continue;
+            }
+            let prev = smooth.last_run().map(|run| run.len);
+            let next = runs.peek().map(|run| run.len);
+            match (prev, next) {
+                (Some(prev), Some(next)) if prev >= next => smooth.extend_last(len),
+                (Some(_), None) => smooth.extend_last(len),
+                (_, Some(_)) => carry = len,
+                (None, None) => smooth.push_n(len, run.value.clone()),
+            }
+        }
+        smooth
+    }
+
+    /// Returns a lossy copy that uses at most `max_runs` runs, for previews at a lower resolution.
+    ///
+    /// The shortest run is repeatedly absorbed into the longer of its neighbours, preferring the
+    /// previous run on ties, until at most `max_runs` runs remain. Of equally short runs the one
+    /// closest to the front is absorbed first. When the neighbours of an absorbed run hold the same
+    /// value they are joined as well. The length is unchanged and a non-empty vector keeps at
+    /// least one run, even for a `max_runs` of 0.
+    ///
+    /// The complexity is **O(r log r)** where r is the number of runs.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 0, 1, 0, 0, 2, 2, 2, 2, 3, 3][..]);
+    ///
+    /// let preview = rle.compact_to_runs(2);
+    /// assert_eq!(preview.to_vec(), vec![0, 0, 0, 0, 0, 0, 2, 2, 2, 2, 2, 2]);
+    /// assert_eq!(preview.runs_len(), 2);
+    /// ```
+    pub fn compact_to_runs(&self, max_runs: usize) -> RleVec<T> {
+        const NONE: usize = usize::MAX;
+        let n = self.runs.len();
+        if n <= max_runs {
+            return self.clone()
+        }
+
+        let mut lens: Vec<_> = self.runs().map(|run| run.len).collect();
+        let mut prev: Vec<_> = (0..n).map(|i| i.wrapping_sub(1)).collect();
+        let mut next: Vec<_> = (1..n).chain(once(NONE)).collect();
+        let mut alive = vec![true; n];
+        // the runs keep their index, so the smallest index is the run closest to the front
+        let mut heap: BinaryHeap<_> = lens.iter().enumerate().map(|(i, &len)| Reverse((len, i))).collect();
+
+        let mut count = n;
+        while count > cmp::max(max_runs, 1) {
+            let Reverse((len, i)) = heap.pop().expect("heap holds all live runs");
+            if !alive[i] || lens[i] != len {
+                continue
+            }
+
+            let (p, nx) = (prev[i], next[i]);
+            let target = if p == NONE || (nx != NONE && lens[nx] > lens[p]) { nx } else { p };
+            lens[target] += len;
+            alive[i] = false;
+            count -= 1;
+            if p != NONE { next[p] = nx }
+            if nx != NONE { prev[nx] = p }
+
+            let mut survivor = target;
+            if p != NONE && nx != NONE && self.runs[p].value == self.runs[nx].value {
+                lens[p] += lens[nx];
+                alive[nx] = false;
+                count -= 1;
+                next[p] = next[nx];
+                if next[nx] != NONE { prev[next[nx]] = p }
+                survivor = p;
+            }
+            heap.push(Reverse((lens[survivor], survivor)));
+        }
+
+        let mut compact = RleVec::with_run_capacity(count);
+        for i in (0..n).filter(|&i| alive[i]) {
+            compact.push_n(lens[i], self.runs[i].value.clone());
+        }
+        compact
+    }
+
+    /// Removes the runs for which `f` returns `true` and returns them with their index ranges. The
+    /// removed runs are replaced by `fill`, so the length and the positions of the other values do
+    /// not change. Runs of `fill` are merged with their neighbours where needed.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut status = RleVec::from(&["ok", "ok", "err", "ok", "timeout", "timeout"][..]);
+    ///
+    /// let errors = status.extract_runs_with_fill(|s| *s != "ok", "ok");
+    /// assert_eq!(errors, vec![(2..3, "err"), (4..6, "timeout")]);
+    /// assert_eq!(status.to_vec(), vec!["ok"; 6]);
+    /// assert_eq!(status.runs_len(), 1);
+    /// ```
+    pub fn extract_runs_with_fill<F>(&mut self, mut f: F, fill: T) -> Vec<(Range<usize>, T)> where F: FnMut(&T) -> bool {
+        let runs = mem::take(&mut self.runs);
+        let mut extracted = Vec::new();
+        let mut start = 0;
+        for run in runs {
+            let len = run.end + 1 - start;
+            if f(&run.value) {
+                extracted.push((start..run.end + 1, run.value));
+                self.push_n(len, fill.clone());
+            } else {
+                self.push_n(len, run.value);
+            }
+            start = run.end + 1;
+        }
+        extracted
+    }
+
+    /// Adds `delta` to every value in `range`. The runs at the boundaries of the range are split
+    /// and runs that become equal to their neighbours are merged.
+    ///
+    /// The complexity is **O(log n + r)** where r is the number of runs in the range, plus the cost
+    /// of moving the runs after the range if the number of runs changes.
+    ///
+    /// For integer types the `saturating_add_range` and `checked_add_range` variants prevent
+    /// overflow.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut coverage = RleVec::new();
+    /// coverage.push_n(10, 0);
+    ///
+    /// coverage.add_range(2..6, 1);
+    /// coverage.add_range(4..8, 1);
+    /// assert_eq!(coverage.to_vec(), vec![0, 0, 1, 1, 2, 2, 1, 1, 0, 0]);
+    /// assert_eq!(coverage.runs_len(), 5);
+    /// ```
+    pub fn add_range<R>(&mut self, range: R, delta: T) where R: RangeBounds<usize>, T: Add<Output=T> {
+        let range = self.resolve_range(range);
+        self.try_map_range(range, |v| Some(v.clone() + delta.clone()));
+    }
+
+    /// Replaces every value in `range` with the result of `f`, merging runs where needed. If `f`
+    /// returns `None` for any run, `false` is returned and the `RleVec` is not modified. The range
+    /// must be within bounds.
+    fn try_map_range<F>(&mut self, range: Range<usize>, mut f: F) -> bool where F: FnMut(&T) -> Option<T> {
+        if range.start == range.end { return true }
+
+        // rebuild the affected runs including their neighbors, see swap_ranges
+        let first = self.run_index(range.start).saturating_sub(1);
+        let last = cmp::min(self.run_index(range.end - 1) + 1, self.runs.len() - 1);
+        let (start, end) = (self.run_start(first), self.runs[last].end + 1);
+
+        let mut rebuilt = RleVec::new();
+        for (len, value) in self.clipped_runs(start..range.start) {
+            rebuilt.push_n(len, value.clone());
+        }
+        for (len, value) in self.clipped_runs(range.clone()) {
+            match f(value) {
+                Some(value) => rebuilt.push_n(len, value),
+                None => return false,
+            }
+        }
+        for (len, value) in self.clipped_runs(range.end..end) {
+            rebuilt.push_n(len, value.clone());
+        }
+        let runs = rebuilt.runs.into_iter().map(|r| InternalRun { end: r.end + start, value: r.value });
+        self.runs.splice(first..last + 1, runs);
+        true
+    }
+
+    /// Builds an `RleVec` by looking up every code in `dict`. This is the inverse of
+    /// [`to_dictionary`](#method.to_dictionary), the values are cloned once per run.
+    ///
+    /// # Errors
+    /// Returns a `MissingCode` error for the first code that is not a valid index into `dict`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let codes = RleVec::from(&[1u32, 1, 1, 0][..]);
+    ///
+    /// let rle = RleVec::from_dictionary(&codes, &["x", "y"]).unwrap();
+    /// assert_eq!(rle.to_vec(), vec!["y", "y", "y", "x"]);
+    /// ```
+    pub fn from_dictionary(codes: &RleVec<u32>, dict: &[T]) -> Result<RleVec<T>, MissingCode> {
+        let mut rle = RleVec::with_run_capacity(codes.runs_len());
+        for run in codes.runs() {
+            match dict.get(*run.value as usize) {
+                Some(value) => rle.push_n(run.len, value.clone()),
+                None => return Err(MissingCode { code: *run.value, dictionary_len: dict.len() }),
+            }
+        }
+        Ok(rle)
+    }
+
+    /// Inserts clones of the elements of `other` at the front, merging the runs at the seam when
+    /// their values are equal. The ends of the existing runs are shifted in one pass.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[2, 2, 3][..]);
+    ///
+    /// rle.prepend_rle(&RleVec::from(&[1, 2][..]));
+    /// assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 3]);
+    /// assert_eq!(rle.runs_len(), 3);
+    /// ```
+    pub fn prepend_rle(&mut self, other: &RleVec<T>) {
+        let offset = other.len();
+        for run in &mut self.runs {
+            run.end += offset;
+        }
+        let mut runs = &other.runs[..];
+        if let (Some(last), Some(first)) = (runs.last(), self.runs.first()) {
+            // the first run of self already covers the seam
+            if last.value == first.value {
+                runs = &runs[..runs.len() - 1];
+            }
+        }
+        self.runs.splice(0..0, runs.iter().cloned());
+    }
+
+    /// Appends clones of the values in `slice`, merging the first value with the last run when
+    /// they are equal.
+    ///
+    /// For slices with at least 4096 elements the runs are counted first with
+    /// [`count_runs_in_slice`](#method.count_runs_in_slice), so that the run storage grows only
+    /// once instead of being reallocated while encoding.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1][..]);
+    ///
+    /// rle.extend_from_slice(&[1, 2, 2, 3]);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 2, 2, 3]);
+    /// assert_eq!(rle.runs_len(), 3);
+    /// ```
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        if slice.len() >= 4096 {
+            let mut runs = RleVec::count_runs_in_slice(slice);
+            if self.runs.last().is_some_and(|last| last.value == slice[0]) {
+                runs -= 1;
+            }
+            self.runs.reserve_exact(runs);
+        }
+        let offset = self.len();
+        offset.checked_add(slice.len()).expect("capacity overflow");
+        let mut iter = slice.iter().enumerate();
+        let mut current = match iter.next() {
+            Some((_, value)) => value,
+            None => return,
+        };
+        // only the first run can merge with the existing last run
+        if let Some(last) = self.runs.last() {
+            if last.value == *current {
+                self.runs.pop();
+            }
+        }
+        for (i, value) in iter {
+            if *value != *current {
+                self.runs.push(InternalRun { end: offset + i - 1, value: current.clone() });
+                current = value;
+            }
+        }
+        self.runs.push(InternalRun { end: offset + slice.len() - 1, value: current.clone() });
+    }
+
+    /// Returns the elements after `prefix` as a new `RleVec` if the `RleVec` starts with
+    /// `prefix`, or `None` otherwise.
+    ///
+    /// The prefix is compared run by run without expanding the `RleVec`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle.strip_prefix(&[1, 1, 2]).unwrap().to_vec(), vec![2, 2, 3]);
+    /// assert_eq!(rle.strip_prefix(&[1, 2]), None);
+    /// ```
+    pub fn strip_prefix(&self, prefix: &[T]) -> Option<RleVec<T>> {
+        if prefix.len() > self.len() || !self.matches_at(0, prefix) {
+            return None
+        }
+        Some(self.sub_rle(prefix.len()..self.len()))
+    }
+
+    /// Returns the elements before `suffix` as a new `RleVec` if the `RleVec` ends with
+    /// `suffix`, or `None` otherwise.
+    ///
+    /// The suffix is compared run by run without expanding the `RleVec`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle.strip_suffix(&[2, 3]).unwrap().to_vec(), vec![1, 1, 2, 2]);
+    /// assert_eq!(rle.strip_suffix(&[1, 3]), None);
+    /// ```
+    pub fn strip_suffix(&self, suffix: &[T]) -> Option<RleVec<T>> {
+        if suffix.len() > self.len() {
+            return None
+        }
+        let start = self.len() - suffix.len();
+        if !self.matches_at(start, suffix) {
+            return None
+        }
+        Some(self.sub_rle(0..start))
+    }
+
+    /// Returns a new `RleVec` holding the values at the given indices, in the order of the indices.
+    ///
+    /// The indices may be in any order and repeat. While they are ascending the runs are walked
+    /// linearly, otherwise each index is found with a binary search. Consecutive indices into the
+    /// same run extend the last run of the result without cloning the value.
+    ///
+    /// # Panics
+    /// Panics if an index is out of bounds, see [`try_gather`](#method.try_gather).
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2, 2, 3][..]);
+    ///
+    /// let gathered = rle.gather(vec![4, 0, 1, 3, 3]);
+    /// assert_eq!(gathered.to_vec(), vec![3, 1, 1, 2, 2]);
+    /// assert_eq!(gathered.runs_len(), 3);
+    /// ```
+    pub fn gather<I>(&self, indices: I) -> RleVec<T> where I: IntoIterator<Item=usize> {
+        match self.try_gather(indices) {
+            Ok(rle) => rle,
+            Err(err) => panic!("index out of bounds: the len is {} but the index is {}", err.len, err.index),
+        }
+    }
+
+    /// Returns a new `RleVec` holding the values at the given indices like
+    /// [`gather`](#method.gather), or an `OutOfBoundsError` for the first index that is not
+    /// smaller than the length.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{OutOfBoundsError, RleVec};
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// assert_eq!(rle.try_gather(0..3), Ok(rle.clone()));
+    /// assert_eq!(rle.try_gather(vec![2, 3]), Err(OutOfBoundsError { index: 3, len: 3 }));
+    /// ```
+    pub fn try_gather<I>(&self, indices: I) -> Result<RleVec<T>, OutOfBoundsError> where I: IntoIterator<Item=usize> {
+        let len = self.len();
+        let mut gathered = RleVec::new();
+        let mut last: Option<(usize, usize)> = None;
+        for index in indices {
+            if index >= len {
+                return Err(OutOfBoundsError { index, len })
+            }
+            let p = match last {
+                Some((prev, mut p)) if index >= prev => {
+                    while self.runs[p].end < index { p += 1 }
+                    p
+                }
+                _ => self.run_index(index),
+            };
+            match last {
+                Some((_, last_p)) if last_p == p => gathered.runs.last_mut().unwrap().end += 1,
+                _ => gathered.push(self.runs[p].value.clone()),
+            }
+            last = Some((index, p));
+        }
+        Ok(gathered)
+    }
+
+    /// Removes the elements in `range` and returns an iterator over the removed values.
+    ///
+    /// Unlike `Vec::drain` the range is removed immediately like
+    /// [`take_range`](#method.take_range), before the first value is yielded. The removed runs are
+    /// moved into the iterator, which clones each value for all but the last element of its run.
+    /// Dropping or leaking the iterator before it is exhausted therefore always leaves a valid
+    /// `RleVec`.
+    ///
+    /// # Panics
+    /// Panics if the start of the range is greater than its end or if the end is greater than the
+    /// length.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2, 3, 1, 1][..]);
+    ///
+    /// let drained: Vec<_> = rle.drain(1..5).collect();
+    /// assert_eq!(drained, vec![1, 2, 2, 3]);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1]);
+    /// assert_eq!(rle.runs_len(), 1);
+    ///
+    /// rle.drain(..);
+    /// assert!(rle.is_empty());
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T> where R: RangeBounds<usize> {
+        Drain { iter: self.take_range(range).into_iter(), marker: PhantomData }
+    }
+
+    /// Removes the elements in `range` and returns them as a new `RleVec`.
+    ///
+    /// The removed runs are moved into the result and clipped to the range, only the values of
+    /// runs that are split by the bounds of the range are cloned. The neighbouring runs are merged
+    /// if their values are equal. Appending the tail of the `RleVec` after the range to the result
+    /// and both to the head restores the original.
+    ///
+    /// # Panics
+    /// Panics if the start of the range is greater than its end or if the end is greater than the
+    /// length.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2, 3, 1, 1][..]);
+    ///
+    /// let taken = rle.take_range(1..5);
+    /// assert_eq!(taken.to_vec(), vec![1, 2, 2, 3]);
+    /// assert_eq!(taken.runs_len(), 3);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1]);
+    /// ```
+    pub fn take_range<R>(&mut self, range: R) -> RleVec<T> where R: RangeBounds<usize> {
+        let Range { start, end } = self.resolve_range(range);
+        let mut removed = RleVec::new();
+        if start < end {
+            let p = self.run_index(start);
+            let q = self.run_index(end - 1);
+            let mut drained: Vec<_> = self.runs.drain(p..=q).collect();
+
+            // the parts of the first and the last run outside of the range stay
+            let mut kept = Vec::with_capacity(2);
+            let keep_before = self.run_start(p) < start;
+            if keep_before {
+                kept.push(InternalRun { end: start - 1, value: drained[0].value.clone() });
+            }
+            let last = drained.last().unwrap();
+            if last.end >= end {
+                kept.push(InternalRun { end: last.end - (end - start), value: last.value.clone() });
+            }
+            for run in drained.iter_mut() {
+                run.end = cmp::min(run.end, end - 1) - start;
+            }
+
+            let n = kept.len();
+            self.runs.splice(p..p, kept);
+            for run in self.runs[p + n..].iter_mut() {
+                run.end -= end - start;
+            }
+            let junction = p + keep_before as usize;
+            if junction > 0 && junction < self.runs.len() && self.runs[junction - 1].value == self.runs[junction].value {
+                self.runs.remove(junction - 1);
+            }
+            removed.runs = drained;
+        }
+        removed
+    }
+
+    /// Removes all elements equal to `value` and returns the number of removed elements.
+    ///
+    /// The complexity is **O(n)** in the number of runs, see [`retain`](#method.retain).
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[0, 0, 1, 0, 0, 0, 1, 2][..]);
+    ///
+    /// assert_eq!(rle.remove_all(&0), 5);
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 2]);
+    /// assert_eq!(rle.remove_all(&0), 0);
+    /// ```
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        self.retain(|v| v != value)
+    }
+
+    /// Writes a batch of values at unsorted indices, see [`try_scatter`](#method.try_scatter).
+    ///
+    /// # Panics
+    /// Panics if an index is out of bounds, before any value is written.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[0; 8][..]);
+    ///
+    /// rle.scatter(vec![(5, 2), (1, 1), (2, 1), (5, 3)]);
+    /// assert_eq!(rle.to_vec(), vec![0, 1, 1, 0, 0, 3, 0, 0]);
+    /// ```
+    pub fn scatter<I>(&mut self, updates: I) where I: IntoIterator<Item=(usize, T)> {
+        if let Err(err) = self.try_scatter(updates) {
+            panic!("index out of bounds: the len is {} but the index is {}", err.len, err.index)
+        }
+    }
+
+    /// Writes a batch of `(index, value)` updates given in any order.
+    ///
+    /// The updates are collected and sorted by index with a stable sort. When an index occurs more
+    /// than once the last update in the input wins, like calling [`set`](#method.set) for each
+    /// update in order. The sorted updates are then merged with the runs in a single pass, the
+    /// complexity is **O(m log m + n + m)** for `m` updates instead of **O(m n)** for separate
+    /// calls to `set`.
+    ///
+    /// # Errors
+    /// Returns an `OutOfBoundsError` for the first update in the input with an index that is not
+    /// smaller than the length. All indices are checked before the first write, the `RleVec` is
+    /// unchanged on error.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{OutOfBoundsError, RleVec};
+    /// let mut rle = RleVec::from(&[1, 1, 1, 2, 2][..]);
+    ///
+    /// assert_eq!(rle.try_scatter(vec![(0, 2), (5, 3)]), Err(OutOfBoundsError { index: 5, len: 5 }));
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 1, 2, 2]);
+    ///
+    /// assert_eq!(rle.try_scatter(vec![(2, 2), (0, 2), (1, 3), (1, 2)]), Ok(()));
+    /// assert_eq!(rle.to_vec(), vec![2; 5]);
+    /// assert_eq!(rle.runs_len(), 1);
+    /// ```
+    pub fn try_scatter<I>(&mut self, updates: I) -> Result<(), OutOfBoundsError> where I: IntoIterator<Item=(usize, T)> {
+        let len = self.len();
+        let mut updates: Vec<(usize, T)> = updates.into_iter().collect();
+        if let Some(&(index, _)) = updates.iter().find(|&&(index, _)| index >= len) {
+            return Err(OutOfBoundsError { index, len })
+        }
+        if updates.is_empty() { return Ok(()) }
+
+        updates.sort_by_key(|&(index, _)| index);
+        let mut deduped: Vec<(usize, T)> = Vec::with_capacity(updates.len());
+        for (index, value) in updates {
+            match deduped.last_mut() {
+                Some(last) if last.0 == index => last.1 = value,
+                _ => deduped.push((index, value)),
+            }
+        }
+
+        let runs = mem::take(&mut self.runs);
+        self.runs.reserve(runs.len() + 2 * deduped.len());
+        let mut updates = deduped.into_iter().peekable();
+        let mut pos = 0;
+        for run in runs {
+            while let Some((index, value)) = updates.next_if(|&(index, _)| index <= run.end) {
+                if index > pos {
+                    self.push_n(index - pos, run.value.clone());
+                }
+                self.push(value);
+                pos = index + 1;
+            }
+            self.push_n(run.end + 1 - pos, run.value);
+            pos = run.end + 1;
+        }
+        Ok(())
+    }
+
+    /// Insert a value at the given index.
+    ///
+    /// Because the positions of the values after the inserted value need to be changed,
+    /// the complexity of this function is **O((log n) + 2n)**.
+    ///
+    /// The index must be at most `len`, inserting at `len` appends the value like `push`.
+    ///
+    /// # Panics
+    /// Panics if `index > len` or if the number of elements in the vector overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// assert_eq!(rle[2], 1);
+    /// assert_eq!(rle.runs_len(), 3);
+    ///
+    /// rle.insert(2, 3);
+    /// assert_eq!(rle[2], 3);
+    /// assert_eq!(rle.runs_len(), 5);
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len();
+        if index == len {
+            return self.push(value);
+        }
+        assert!(index < len, "insertion index (is {}) should be <= len (is {})", index, len);
+        assert!(len < usize::MAX, "capacity overflow");
+
+        let (p, start, end) = self.index_info(index);
+        // increment all run ends from position p
+        for run in self.runs[p..].iter_mut() {
+            run.end += 1;
+        }
+
+        if self.runs[p].value == value { return }
+
+        // inserting value can split current run or maybe merge with previous or next
+        if index == start {
+            // compare to previous run
+            if p > 0 && self.runs[p - 1].value == value {
+                self.runs[p - 1].end += 1;
+            } else {
+                self.runs.insert(p, InternalRun { value, end: index });
+            }
+        } else {
+            // split current run
+            self.runs[p].end = index - 1;
+            self.runs.insert(p + 1, InternalRun { value, end: index });
+            let value = self.runs[p].value.clone();
+            self.runs.insert(p + 2, InternalRun { value, end: end + 1 });
+        }
+    }
+
+    /// Insert `n` copies of a value at the given index.
+    ///
+    /// Unlike calling [`insert`](#method.insert) in a loop, the run ends after the index are
+    /// shifted only once. At most one run is split, the complexity is **O((log n) + 2n)**.
+    ///
+    /// Inserting zero values does nothing, inserting at `len` appends the values like `push_n`.
+    ///
+    /// # Panics
+    /// Panics if `index > len` or if the number of elements in the vector overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2][..]);
+    ///
+    /// rle.insert_n(1, 3, 3);
+    /// assert_eq!(rle.to_vec(), vec![1, 3, 3, 3, 1, 2, 2]);
+    /// assert_eq!(rle.runs_len(), 4);
+    ///
+    /// rle.insert_n(4, 2, 3);
+    /// assert_eq!(rle.to_vec(), vec![1, 3, 3, 3, 3, 3, 1, 2, 2]);
+    /// assert_eq!(rle.runs_len(), 4);
+    /// ```
+    pub fn insert_n(&mut self, index: usize, n: usize, value: T) {
+        let len = self.len();
+        assert!(index <= len, "insertion index (is {}) should be <= len (is {})", index, len);
+        if index == len {
+            return self.push_n(n, value);
+        }
+        if n == 0 { return }
+        assert!(len.checked_add(n).is_some(), "capacity overflow");
+
+        let (p, start, end) = self.index_info(index);
+        for run in self.runs[p..].iter_mut() {
+            run.end += n;
+        }
+
+        if self.runs[p].value == value { return }
+
+        if index == start {
+            if p > 0 && self.runs[p - 1].value == value {
+                self.runs[p - 1].end += n;
+            } else {
+                self.runs.insert(p, InternalRun { value, end: index + n - 1 });
+            }
+        } else {
+            // split current run around the new run
+            self.runs[p].end = index - 1;
+            let rest = InternalRun { value: self.runs[p].value.clone(), end: end + n };
+            self.runs.splice(p + 1..p + 1, vec![InternalRun { value, end: index + n - 1 }, rest]);
+        }
+    }
+
+    /// Modify the value at given index like [`set`](#method.set) and report the change in the run
+    /// structure.
+    ///
+    /// Only the boundaries before and after the index are inspected, this adds **O(log n)** to
+    /// the cost of `set`.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{MutationReport, RleVec};
+    /// let mut rle = RleVec::from(&[1, 1, 1, 2, 2][..]);
+    ///
+    /// // the value moves from the run of 1s to the run of 2s
+    /// let report = rle.set_reporting(2, 2);
+    /// assert_eq!(report, MutationReport { run_delta: 0, splits: 1, merges: 1 });
+    ///
+    /// let report = rle.set_reporting(3, 3);
+    /// assert_eq!(report, MutationReport { run_delta: 2, splits: 2, merges: 0 });
+    /// assert_eq!(rle.to_vec(), vec![1, 1, 2, 3, 2]);
+    /// ```
+    pub fn set_reporting(&mut self, index: usize, value: T) -> MutationReport {
+        let runs_before = self.runs_len();
+        let before = [self.is_boundary(index), self.is_boundary(index + 1)];
+        self.set(index, value);
+        let after = [self.is_boundary(index), self.is_boundary(index + 1)];
+
+        let mut report = MutationReport { run_delta: self.runs_len() as isize - runs_before as isize, ..Default::default() };
+        for (&b, &a) in before.iter().zip(after.iter()) {
+            if a && !b { report.splits += 1 }
+            if b && !a { report.merges += 1 }
+        }
+        report
+    }
+
+    /// Insert a value at the given index like [`insert`](#method.insert) and report the change in
+    /// the run structure.
+    ///
+    /// # Panics
+    /// Panics if `index > len` or if the number of elements in the vector overflows a usize.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{MutationReport, RleVec};
+    /// let mut rle = RleVec::from(&[1, 1, 2, 2][..]);
+    ///
+    /// assert_eq!(rle.insert_reporting(1, 3), MutationReport { run_delta: 2, splits: 2, merges: 0 });
+    /// assert_eq!(rle.insert_reporting(4, 2), MutationReport { run_delta: 0, splits: 0, merges: 0 });
+    /// assert_eq!(rle.to_vec(), vec![1, 3, 1, 2, 2, 2]);
+    /// ```
+    pub fn insert_reporting(&mut self, index: usize, value: T) -> MutationReport {
+        let runs_before = self.runs_len();
+        let before = self.is_boundary(index) as usize;
+        self.insert(index, value);
+        let after = self.is_boundary(index) as usize + self.is_boundary(index + 1) as usize;
+        MutationReport {
+            run_delta: self.runs_len() as isize - runs_before as isize,
+            splits: after.saturating_sub(before),
+            merges: before.saturating_sub(after),
+        }
+    }
+
+    /// Removes and returns the element at position index like [`remove`](#method.remove) and
+    /// report the change in the run structure.
+    ///
+    /// # Panics
+    /// Panics if index is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{MutationReport, RleVec};
+    /// let mut rle = RleVec::from(&[1, 1, 2, 1, 1][..]);
+    ///
+    /// let (value, report) = rle.remove_reporting(2);
+    /// assert_eq!(value, 2);
+    /// assert_eq!(report, MutationReport { run_delta: -2, splits: 0, merges: 2 });
+    /// ```
+    pub fn remove_reporting(&mut self, index: usize) -> (T, MutationReport) {
+        let runs_before = self.runs_len();
+        let before = self.is_boundary(index) as usize + self.is_boundary(index + 1) as usize;
+        let value = self.remove(index);
+        let after = self.is_boundary(index) as usize;
+        (value, MutationReport {
+            run_delta: self.runs_len() as isize - runs_before as isize,
+            splits: after.saturating_sub(before),
+            merges: before.saturating_sub(after),
+        })
+    }
+
+    /// Modify every value in `range` like [`set_range`](#method.set_range) and report the change in
+    /// the run structure. Every run boundary inside the range counts as a merge.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{MutationReport, RleVec};
+    /// let mut rle = RleVec::from(&[1, 1, 2, 3, 3, 4][..]);
+    ///
+    /// // the boundaries at 2 and 3 are removed and one is created at 1
+    /// let report = rle.set_range_reporting(1..5, 2);
+    /// assert_eq!(report, MutationReport { run_delta: -1, splits: 1, merges: 2 });
+    /// ```
+    pub fn set_range_reporting<R>(&mut self, range: R, value: T) -> MutationReport where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        if range.start == range.end { return MutationReport::default() }
+
+        let runs_before = self.runs_len();
+        let inner = self.run_index(range.end - 1) - self.run_index(range.start);
+        let before = [self.is_boundary(range.start), self.is_boundary(range.end)];
+        self.set_range(range.clone(), value);
+        let after = [self.is_boundary(range.start), self.is_boundary(range.end)];
+
+        let mut report = MutationReport {
+            run_delta: self.runs_len() as isize - runs_before as isize,
+            merges: inner,
+            ..Default::default()
+        };
+        for (&b, &a) in before.iter().zip(after.iter()) {
+            if a && !b { report.splits += 1 }
+            if b && !a { report.merges += 1 }
+        }
+        report
+    }
+}
+
+impl<T: PartialEq> RleVec<T> {
+    /// Returns `true` if the elements of `self` in `range` are equal to the elements of `other` in
+    /// `other_range`. Ranges of different lengths are never equal.
+    ///
+    /// Both windows are walked run by run, the complexity is **O(log n + r)** where r is the number
+    /// of runs in the windows.
+    ///
+    /// # Panics
+    /// Panics if either range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    /// let b = RleVec::from(&[0, 1, 1, 2, 2, 2][..]);
+    ///
+    /// assert!(a.eq_range(1..5, &b, 1..5));
+    /// assert!(!a.eq_range(1..6, &b, 1..6));
+    /// assert!(!a.eq_range(0..2, &b, 0..3));
+    /// ```
+    pub fn eq_range<A, B>(&self, range: A, other: &RleVec<T>, other_range: B) -> bool
+        where A: RangeBounds<usize>, B: RangeBounds<usize>
+    {
+        let range = self.resolve_range(range);
+        let other_range = other.resolve_range(other_range);
+        if range.end - range.start != other_range.end - other_range.start {
+            return false
+        }
+
+        let mut a = self.clipped_runs(range);
+        let mut b = other.clipped_runs(other_range);
+        let (mut left_a, mut left_b) = (0, 0);
+        let (mut value_a, mut value_b) = (None, None);
+        loop {
+            if left_a == 0 {
+                match a.next() {
+                    Some((len, value)) => { left_a = len; value_a = Some(value); }
+                    None => return true,
+                }
+            }
+            if left_b == 0 {
+                let (len, value) = b.next().unwrap();
+                left_b = len;
+                value_b = Some(value);
+            }
+            if value_a != value_b { return false }
+            let step = cmp::min(left_a, left_b);
+            left_a -= step;
+            left_b -= step;
+        }
+    }
+
+    /// Returns `true` if the elements of `self` in `range` are equal to `values`.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// assert!(rle.eq_range_slice(2..5, &[1, 2, 2]));
+    /// assert!(!rle.eq_range_slice(2..5, &[1, 2, 3]));
+    /// ```
+    pub fn eq_range_slice<R>(&self, range: R, values: &[T]) -> bool where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        range.end - range.start == values.len() && self.matches_at(range.start, values)
+    }
+
+    /// Returns `true` if both `RleVec`s have the same runs, comparing run ends and values.
+    ///
+    /// An `RleVec` always stores the minimal number of runs, so this gives the same result as
+    /// `==`, which compares the values logically. It is useful to state the intent of a test that
+    /// checks an encoding.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::from(&[1, 1, 2][..]);
+    /// let b: RleVec<_> = vec![1, 1, 2].into_iter().collect();
+    ///
+    /// assert!(a.identical_runs(&b));
+    /// assert!(!a.identical_runs(&RleVec::from(&[1, 1, 3][..])));
+    /// ```
+    pub fn identical_runs(&self, other: &RleVec<T>) -> bool {
+        self.runs == other.runs
+    }
+
+    /// Returns the number of runs needed to store `slice`. Use it to reserve capacity before
+    /// encoding a large slice.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let data = [0, 0, 0, 1, 1, 0];
+    /// assert_eq!(RleVec::count_runs_in_slice(&data), 3);
+    ///
+    /// let mut rle = RleVec::with_run_capacity(RleVec::count_runs_in_slice(&data));
+    /// rle.extend(data.iter().cloned());
+    /// assert_eq!(rle.runs_len(), 3);
+    /// ```
+    pub fn count_runs_in_slice(slice: &[T]) -> usize {
+        match slice.len() {
+            0 => 0,
+            _ => 1 + slice.windows(2).filter(|pair| pair[0] != pair[1]).count(),
+        }
+    }
+
+    fn matches_at(&self, start: usize, needle: &[T]) -> bool {
+        let mut pos = 0;
+        self.clipped_runs(start..start + needle.len()).all(|(len, value)| {
+            let values = &needle[pos..pos + len];
+            pos += len;
+            values.iter().all(|v| v == value)
+        })
+    }
+
+    /// Returns the number of elements at the start of the `RleVec` that are equal to `value`.
+    ///
+    /// Because adjacent runs hold different values only the first run is checked, the complexity
+    /// is **O(1)**.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 0, 5, 6, 0, 0][..]);
+    ///
+    /// assert_eq!(rle.leading_count(&0), 3);
+    /// assert_eq!(rle.leading_count(&5), 0);
+    /// ```
+    pub fn leading_count(&self, value: &T) -> usize {
+        match self.runs.first() {
+            Some(run) if run.value == *value => run.end + 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns the number of elements at the end of the `RleVec` that are equal to `value`.
+    ///
+    /// Because adjacent runs hold different values only the last run is checked, the complexity
+    /// is **O(1)**.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 0, 5, 6, 0, 0][..]);
+    ///
+    /// assert_eq!(rle.trailing_count(&0), 2);
+    /// assert_eq!(rle.trailing_count(&6), 0);
+    /// ```
+    pub fn trailing_count(&self, value: &T) -> usize {
+        match self.runs.last() {
+            Some(run) if run.value == *value => run.end + 1 - self.run_start(self.runs.len() - 1),
+            _ => 0,
+        }
+    }
+}
+
+impl<T: PartialOrd> RleVec<T> {
+    /// Returns the smallest value in `range`, or `None` if the range is empty. If several values
+    /// are equally small, or cannot be compared, the first one is returned.
+    ///
+    /// Only one comparison per run overlapping the range is made.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[5, 5, 1, 1, 3, 7][..]);
+    ///
+    /// assert_eq!(rle.range_min(0..2), Some(&5));
+    /// assert_eq!(rle.range_min(1..6), Some(&1));
+    /// assert_eq!(rle.range_min(3..3), None);
+    /// ```
+    pub fn range_min<R>(&self, range: R) -> Option<&T> where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        self.clipped_runs(range).map(|(_, value)| value)
+            .fold(None, |min, value| match min {
+                Some(min) if value.partial_cmp(min) != Some(cmp::Ordering::Less) => Some(min),
+                _ => Some(value),
+            })
+    }
+
+    /// Returns the largest value in `range`, or `None` if the range is empty. If several values
+    /// are equally large, or cannot be compared, the first one is returned.
+    ///
+    /// Only one comparison per run overlapping the range is made.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[5, 5, 1, 1, 3, 7][..]);
+    ///
+    /// assert_eq!(rle.range_max(1..5), Some(&5));
+    /// assert_eq!(rle.range_max(2..6), Some(&7));
+    /// ```
+    pub fn range_max<R>(&self, range: R) -> Option<&T> where R: RangeBounds<usize> {
+        let range = self.resolve_range(range);
+        self.clipped_runs(range).map(|(_, value)| value)
+            .fold(None, |max, value| match max {
+                Some(max) if value.partial_cmp(max) != Some(cmp::Ordering::Greater) => Some(max),
+                _ => Some(value),
+            })
+    }
+
+    /// Returns the number of runs with a value greater than `threshold`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 5, 5, 6, 0, 0, 7, 1][..]);
+    ///
+    /// assert_eq!(rle.segment_count_above(&4), 3);
+    /// assert_eq!(rle.segment_count_above(&7), 0);
+    /// ```
+    pub fn segment_count_above(&self, threshold: &T) -> usize {
+        self.runs.iter().filter(|r| r.value > *threshold).count()
+    }
+
+    /// Returns the total number of elements with a value greater than `threshold`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[0, 0, 5, 5, 6, 0, 0, 7, 1][..]);
+    ///
+    /// assert_eq!(rle.total_length_above(&4), 4);
+    /// assert_eq!(rle.total_length_above(&0), 5);
+    /// ```
+    pub fn total_length_above(&self, threshold: &T) -> usize {
+        self.runs().filter(|r| *r.value > *threshold).map(|r| r.len).sum()
+    }
+
+    /// Returns the longest run with a value greater than `threshold`, or `None` if no such run
+    /// exists. When multiple runs have the same length the first one is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, Run};
+    /// let rle = RleVec::from(&[0, 0, 5, 5, 6, 0, 0, 7, 1][..]);
+    ///
+    /// assert_eq!(rle.longest_segment_above(&4), Some(Run { len: 2, value: &5 }));
+    /// assert_eq!(rle.longest_segment_above(&7), None);
+    /// ```
+    pub fn longest_segment_above(&self, threshold: &T) -> Option<Run<&T>> {
+        self.runs()
+            .filter(|r| *r.value > *threshold)
+            .fold(None, |longest: Option<Run<&T>>, r| match longest {
+                Some(ref l) if l.len >= r.len => longest,
+                _ => Some(r),
+            })
+    }
+}
+
+impl RleVec<char> {
+    /// Constructs an `RleVec` from the characters of a string.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from_str_chars("IIIIIIIII#####");
+    ///
+    /// assert_eq!(rle.len(), 14);
+    /// assert_eq!(rle.runs_len(), 2);
+    /// assert_eq!(rle.to_string(), "IIIIIIIII#####");
+    /// ```
+    pub fn from_str_chars(s: &str) -> RleVec<char> {
+        s.chars().collect()
+    }
+
+    /// Expands the `RleVec` into a `String`. The string is allocated once and filled run by run.
+    ///
+    /// The `Display` implementation produces the same text.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    /// rle.push_n(3, 'a');
+    /// rle.push_n(2, 'é');
+    ///
+    /// assert_eq!(rle.collect_string(), "aaaéé");
+    /// ```
+    pub fn collect_string(&self) -> String {
+        let capacity = self.runs().map(|run| run.len * run.value.len_utf8()).sum();
+        let mut s = String::with_capacity(capacity);
+        for run in self.runs() {
+            s.extend((0..run.len).map(|_| *run.value));
+        }
+        s
+    }
+}
+
+impl fmt::Display for RleVec<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0; 4];
+        for run in self.runs() {
+            let encoded = run.value.encode_utf8(&mut buf);
+            for _ in 0..run.len {
+                f.write_str(encoded)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RleVec<u8> {
+    /// Constructs an `RleVec` from a byte slice. This is the same as `RleVec::from(bytes)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from_bytes(b"FFFF::::,");
+    ///
+    /// assert_eq!(rle.runs_len(), 3);
+    /// assert_eq!(rle.to_vec(), b"FFFF::::,".to_vec());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> RleVec<u8> {
+        RleVec::from(bytes)
+    }
+}
+
+impl RleVec<f64> {
+    /// Creates an `RleVec` of `n` evenly spaced values from `start` to `end` (inclusive), merging
+    /// consecutive values that differ by less than `epsilon` into a single run.
+    ///
+    /// Each run stores the first value that was assigned to it, the following values are compared
+    /// against that value. Note that `f64` does not implement `Eq`, so methods requiring it are
+    /// not available on the result.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::linspace(0.0, 8.0, 33, 1.0);
+    ///
+    /// assert_eq!(rle.len(), 33);
+    /// assert_eq!(rle.runs_len(), 9);
+    /// assert_eq!(rle[0], 0.0);
+    /// assert_eq!(rle[10], 2.0);
+    /// assert_eq!(rle[32], 8.0);
+    /// ```
+    pub fn linspace(start: f64, end: f64, n: usize, epsilon: f64) -> RleVec<f64> {
+        let mut rle = RleVec::new();
+        let step = if n > 1 && start != end { (end - start) / (n - 1) as f64 } else { 0.0 };
+        for i in 0..n {
+            let value = if i + 1 == n && n > 1 { end } else { start + step * i as f64 };
+            match rle.runs.last_mut() {
+                Some(ref mut last) if value == last.value || (value - last.value).abs() < epsilon => {
+                    last.end += 1;
+                }
+                _ => rle.runs.push(InternalRun { end: i, value }),
+            }
+        }
+        rle
+    }
+
+    /// Returns `true` if both vectors have the same length and all corresponding values differ
+    /// by at most `epsilon`.
+    ///
+    /// The values are compared once for each segment where both vectors are constant, so aligned
+    /// runs are compared directly.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::linspace(0.0, 1.0, 5, 0.0);
+    /// let b = RleVec::linspace(0.0, 1.0 + 1e-12, 5, 0.0);
+    ///
+    /// assert!(a != b);
+    /// assert!(a.approx_eq(&b, 1e-9));
+    /// ```
+    pub fn approx_eq(&self, other: &RleVec<f64>, epsilon: f64) -> bool {
+        self.len() == other.len() &&
+            self.zip_segments(other, |_, a, b| a == b || (a - b).abs() <= epsilon)
+    }
+
+    /// Returns `true` if both vectors have the same length and all corresponding values differ
+    /// by at most `rel_tolerance` times the largest absolute value of the pair.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let a = RleVec::linspace(1000.0, 2000.0, 3, 0.0);
+    /// let b = RleVec::linspace(1000.1, 2000.0, 3, 0.0);
+    ///
+    /// assert!(a.approx_eq_relative(&b, 1e-3));
+    /// assert!(!a.approx_eq_relative(&b, 1e-6));
+    /// ```
+    pub fn approx_eq_relative(&self, other: &RleVec<f64>, rel_tolerance: f64) -> bool {
+        self.len() == other.len() &&
+            self.zip_segments(other, |_, a, b| {
+                a == b || (a - b).abs() <= rel_tolerance * a.abs().max(b.abs())
+            })
+    }
+}
+
+macro_rules! impl_integer_add_range {
+    ($($t:ty)*) => ($(
+        impl RleVec<$t> {
+            /// Adds `delta` to every value in `range`, saturating at the numeric bounds instead of
+            /// overflowing. See [`add_range`](#method.add_range).
+            ///
+            /// # Panics
+            /// Panics if the range is out of bounds.
+            ///
+            /// # Example
+            /// ```
+            /// # use rle_vec::RleVec;
+            #[doc = concat!("let mut rle: RleVec<", stringify!($t), "> = RleVec::from(&[0, 0, 100, 100][..]);")]
+            #[doc = concat!("rle.saturating_add_range(1..4, ", stringify!($t), "::MAX - 50);")]
+            #[doc = concat!("assert_eq!(rle.to_vec(), vec![0, ", stringify!($t), "::MAX - 50, ", stringify!($t), "::MAX, ", stringify!($t), "::MAX]);")]
+            /// ```
+            pub fn saturating_add_range<R>(&mut self, range: R, delta: $t) where R: RangeBounds<usize> {
+                let range = self.resolve_range(range);
+                self.try_map_range(range, |v| Some(v.saturating_add(delta)));
+            }
+
+            /// Adds `delta` to every value in `range`. See [`add_range`](#method.add_range).
+            ///
+            /// # Errors
+            /// Returns an `AddOverflow` error with the index of the first value for which the
+            /// addition overflows. The `RleVec` is not modified in that case.
+            ///
+            /// # Panics
+            /// Panics if the range is out of bounds.
+            ///
+            /// # Example
+            /// ```
+            /// # use rle_vec::{AddOverflow, RleVec};
+            #[doc = concat!("let mut rle: RleVec<", stringify!($t), "> = RleVec::from(&[0, 0, 100, 100][..]);")]
+            /// assert_eq!(rle.checked_add_range(0..2, 1), Ok(()));
+            #[doc = concat!("assert_eq!(rle.checked_add_range(1..4, ", stringify!($t), "::MAX - 50), Err(AddOverflow { index: 2 }));")]
+            /// assert_eq!(rle.to_vec(), vec![1, 1, 100, 100]);
+            /// ```
+            pub fn checked_add_range<R>(&mut self, range: R, delta: $t) -> Result<(), AddOverflow> where R: RangeBounds<usize> {
+                let range = self.resolve_range(range);
+                if self.try_map_range(range.clone(), |v| v.checked_add(delta)) { return Ok(()) }
+
+                // nothing was modified, find the run that overflowed
+                let mut index = range.start;
+                for (len, value) in self.clipped_runs(range) {
+                    if value.checked_add(delta).is_none() { break }
+                    index += len;
+                }
+                Err(AddOverflow { index })
+            }
+        }
+    )*)
+}
+
+impl_integer_add_range! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+impl<T: Eq + Clone> RleVec<RleVec<T>> {
+    /// Concatenates the nested `RleVec`s into a single `RleVec`, merging equal runs at the
+    /// boundaries of the inner vectors.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut nested = RleVec::new();
+    /// nested.push_n(2, RleVec::from(&[1, 2, 2][..]));
+    /// nested.push(RleVec::from(&[2, 3][..]));
+    ///
+    /// let flat = nested.flatten();
+    /// assert_eq!(flat.to_vec(), vec![1, 2, 2, 1, 2, 2, 2, 3]);
+    /// assert_eq!(flat.runs_len(), 5);
+    /// ```
+    pub fn flatten(self) -> RleVec<T> {
+        let mut res = RleVec::with_run_capacity(self.runs.iter().map(|r| r.value.runs_len()).sum());
+        let mut last_end = 0;
+        for run in self.runs {
+            for _ in 1..run.end + 1 - last_end {
+                res.append_owned(run.value.clone());
+            }
+            res.append_owned(run.value);
+            last_end = run.end + 1;
+        }
+        res
+    }
+}
+
+/// Concatenates an iterator of `RleVec`s into a single `RleVec`, merging equal runs at the
+/// boundaries.
+///
+/// # Example
+/// ```
+/// # use rle_vec::{RleVec, flatten_iter};
+/// let parts = vec![RleVec::from(&[1, 1][..]), RleVec::new(), RleVec::from(&[1, 2][..])];
+///
+/// let flat = flatten_iter(parts);
+/// assert_eq!(flat.to_vec(), vec![1, 1, 1, 2]);
+/// assert_eq!(flat.runs_len(), 2);
+/// ```
+pub fn flatten_iter<T: Eq, I>(iter: I) -> RleVec<T> where I: IntoIterator<Item=RleVec<T>> {
+    let mut res = RleVec::new();
+    for rle in iter {
+        res.append_owned(rle);
+    }
+    res
+}
+
+impl<T: Ord> RleVec<T> {
+    /// Constructs an `RleVec` from an iterator of non-decreasing values. The result has exactly
+    /// one run per distinct value.
+    ///
+    /// The order is checked with a debug assertion only, use
+    /// [`try_from_sorted_iter`](#method.try_from_sorted_iter) for input that might be unsorted.
+    ///
+    /// # Panics
+    /// Panics in debug builds if a value is smaller than its predecessor.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from_sorted_iter(vec![1, 1, 2, 5, 5, 5]);
+    ///
+    /// assert_eq!(rle.runs_len(), 3);
+    /// assert_eq!(rle.len(), 6);
+    /// ```
+    pub fn from_sorted_iter<I>(iter: I) -> RleVec<T> where I: IntoIterator<Item=T> {
+        let mut rle = RleVec::new();
+        for value in iter {
+            match rle.runs.last_mut() {
+                Some(last) if last.value == value => last.end += 1,
+                last => {
+                    debug_assert!(last.is_none_or(|last| last.value < value), "input of from_sorted_iter is not sorted");
+                    let end = rle.len();
+                    rle.runs.push(InternalRun { end, value });
+                }
+            }
+        }
+        rle
+    }
+
+    /// Constructs an `RleVec` from an iterator of non-decreasing values, or returns an error at
+    /// the first value that is smaller than its predecessor.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::{RleVec, NotSorted};
+    /// let rle = RleVec::try_from_sorted_iter(vec!['a', 'b', 'b']).unwrap();
+    /// assert_eq!(rle.runs_len(), 2);
+    ///
+    /// assert_eq!(RleVec::try_from_sorted_iter(vec![1, 3, 2]), Err(NotSorted { index: 2 }));
+    /// ```
+    pub fn try_from_sorted_iter<I>(iter: I) -> Result<RleVec<T>, NotSorted> where I: IntoIterator<Item=T> {
+        let mut rle = RleVec::new();
+        for value in iter {
+            match rle.runs.last_mut() {
+                Some(last) if last.value == value => last.end += 1,
+                Some(last) if last.value > value => return Err(NotSorted { index: last.end + 1 }),
+                _ => {
+                    let end = rle.len();
+                    rle.runs.push(InternalRun { end, value });
+                }
+            }
+        }
+        Ok(rle)
+    }
+
+    /// Returns the range of indices holding `value` in a sorted `RleVec`.
+    ///
+    /// Because equal values are always stored in a single run, the range is found with a binary
+    /// search over the runs in **O(log n)**. If `value` is not present an empty range is
+    /// returned, positioned at the index where `value` could be inserted while keeping the
+    /// `RleVec` sorted. The result is unspecified when the `RleVec` is not sorted.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 3, 3, 3, 7][..]);
+    ///
+    /// assert_eq!(rle.equal_range(&3), 2..5);
+    /// assert_eq!(rle.equal_range(&7), 5..6);
+    /// assert_eq!(rle.equal_range(&4), 5..5);
+    /// ```
+    pub fn equal_range(&self, value: &T) -> Range<usize> {
+        let runs = self.run_equal_range(value);
+        self.run_start(runs.start)..self.run_start(runs.end)
+    }
+
+    /// Returns the range of run indices holding `value` in a sorted `RleVec`.
+    ///
+    /// The range contains at most one run. See [`equal_range`](#method.equal_range) for the
+    /// logical indices.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 3, 3, 3, 7][..]);
+    ///
+    /// assert_eq!(rle.run_equal_range(&3), 1..2);
+    /// assert_eq!(rle.run_equal_range(&0), 0..0);
+    /// ```
+    pub fn run_equal_range(&self, value: &T) -> Range<usize> {
+        match self.runs.binary_search_by(|run| run.value.cmp(value)) {
+            Ok(p) => p..p + 1,
+            Err(p) => p..p,
+        }
+    }
+}
+
+impl<T: Eq + Hash> RleVec<T> {
+    /// Estimates how many bytes this `RleVec` takes when stored densely and as runs, and computes
+    /// the entropy of the values weighted by their run lengths.
+    ///
+    /// The complexity is **O(n + d log d)** where n is the number of runs and d the number of
+    /// distinct values. The values are counted in a `HashMap` and the counts are sorted before they
+    /// are summed, so the entropy does not depend on the iteration order of the map.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let mut rle = RleVec::new();
+    /// rle.push_n(200, 1u32);
+    /// rle.push_n(200, 2u32);
+    ///
+    /// let estimate = rle.encoded_size_estimate();
+    /// assert_eq!(estimate.dense_bytes, 1600);
+    /// assert_eq!(estimate.rle_bytes, 2 * (2 + 4));
+    /// assert_eq!(estimate.distinct_values, 2);
+    /// assert_eq!(estimate.entropy, 1.0);
+    /// ```
+    pub fn encoded_size_estimate(&self) -> SizeEstimate {
+        let width = mem::size_of::<T>();
+        let mut counts = HashMap::new();
+        let mut rle_bytes = 0;
+        for run in self.runs() {
+            let varint_bytes = cmp::max(1, (usize::BITS - run.len.leading_zeros()).div_ceil(7) as usize);
+            rle_bytes += varint_bytes + width;
+            *counts.entry(run.value).or_insert(0) += run.len;
+        }
+
+        let distinct_values = counts.len();
+        let mut counts: Vec<usize> = counts.into_values().collect();
+        counts.sort_unstable();
+
+        let len = self.len() as f64;
+        let entropy = counts.iter()
+            .map(|&count| count as f64 / len)
+            .map(|p| -p * p.log2())
+            .sum::<f64>();
+
+        SizeEstimate {
+            dense_bytes: self.len().saturating_mul(width),
+            rle_bytes,
+            distinct_values,
+            entropy: if entropy == 0.0 { 0.0 } else { entropy },
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> RleVec<T> {
+    /// Splits the `RleVec` into a track of codes and a dictionary holding every distinct value
+    /// once. Codes are assigned in order of first appearance, so the code track has the same run
+    /// structure as `self`.
+    ///
+    /// Use [`from_dictionary`](#method.from_dictionary) for the inverse.
+    ///
+    /// # Panics
+    /// Panics if there are more than `u32::MAX` distinct values.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&["b", "b", "a", "a", "b"][..]);
+    ///
+    /// let (codes, dict) = rle.to_dictionary();
+    /// assert_eq!(codes.to_vec(), vec![0, 0, 1, 1, 0]);
+    /// assert_eq!(dict, vec!["b", "a"]);
+    /// assert_eq!(RleVec::from_dictionary(&codes, &dict), Ok(rle));
+    /// ```
+    pub fn to_dictionary(&self) -> (RleVec<u32>, Vec<T>) {
+        let mut dict = Vec::new();
+        let mut lookup = HashMap::new();
+        let runs = self.runs.iter().map(|run| {
+            let code = *lookup.entry(&run.value).or_insert_with(|| {
+                let code = u32::try_from(dict.len()).expect("too many distinct values for u32 codes");
+                dict.push(run.value.clone());
+                code
+            });
+            InternalRun { end: run.end, value: code }
+        }).collect();
+
+        (RleVec { runs }, dict)
+    }
+}
+
+// returns the first violation of the canonical form in `runs`
+fn check_canonical_runs<T: Eq>(runs: &[InternalRun<T>]) -> Result<(), NotCanonical> {
+    for (i, pair) in runs.windows(2).enumerate() {
+        if pair[0].end >= pair[1].end {
+            return Err(NotCanonical::EmptyRun { run: i + 1 })
+        }
+        if pair[0].value == pair[1].value {
+            return Err(NotCanonical::EqualNeighbours { run: i + 1 })
+        }
+    }
+    Ok(())
+}
+
+/// Compares the values logically, an `RleVec<String>` can be compared with an `RleVec<&str>`.
+///
+/// Two `RleVec`s holding the same sequence of values always compare equal, however they were
+/// built, because the runs are canonical, see [Guarantees](struct.RleVec.html#guarantees).
+impl<T: PartialEq<U>, U> PartialEq<RleVec<U>> for RleVec<T> {
+    fn eq(&self, other: &RleVec<U>) -> bool {
+        self.len() == other.len() && self.zip_segments(other, |_, a, b| a == b)
+    }
+}
+
+/// Compares the values lexicographically, like `Vec`.
+///
+/// Equal runs are skipped as a whole, the values are compared once per pair of overlapping runs.
+/// A prefix is less than the longer sequence.
+impl<T: PartialOrd> PartialOrd for RleVec<T> {
+    fn partial_cmp(&self, other: &RleVec<T>) -> Option<cmp::Ordering> {
+        let mut ordering = Some(cmp::Ordering::Equal);
+        self.zip_segments(other, |_, a, b| {
+            ordering = a.partial_cmp(b);
+            ordering == Some(cmp::Ordering::Equal)
+        });
+        match ordering {
+            Some(cmp::Ordering::Equal) => self.len().partial_cmp(&other.len()),
+            ordering => ordering,
+        }
+    }
+}
+
+/// Orders the values lexicographically, like `Vec`.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let a = RleVec::from(&[1, 1, 2][..]);
+/// let b = RleVec::from(&[1, 2][..]);
+///
+/// assert!(a < b);
+/// assert!(b < RleVec::from(&[1, 2, 0][..]));
+/// assert_eq!(a.cmp(&b), a.to_vec().cmp(&b.to_vec()));
+/// ```
+impl<T: Ord> Ord for RleVec<T> {
+    fn cmp(&self, other: &RleVec<T>) -> cmp::Ordering {
+        let mut ordering = cmp::Ordering::Equal;
+        self.zip_segments(other, |_, a, b| {
+            ordering = a.cmp(b);
+            ordering == cmp::Ordering::Equal
+        });
+        ordering.then(self.len().cmp(&other.len()))
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for RleVec<T> {
+    fn eq(&self, other: &[U]) -> bool {
+        if self.len() != other.len() { return false }
+        let mut start = 0;
+        self.runs.iter().all(|run| {
+            let values = &other[start..run.end + 1];
+            start = run.end + 1;
+            values.iter().all(|v| run.value == *v)
+        })
+    }
+}
+
+impl<'a, T: PartialEq<U>, U> PartialEq<&'a [U]> for RleVec<T> {
+    fn eq(&self, other: &&'a [U]) -> bool {
+        *self == **other
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for RleVec<T> {
+    fn eq(&self, other: &Vec<U>) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<T> Index<usize> for RleVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.runs[self.run_index(index)].value
+    }
+}
+
+impl<T: Clone> From<RleVec<T>> for Vec<T> {
+    fn from(rle: RleVec<T>) -> Vec<T> {
+        rle.to_vec()
+    }
+}
+
+impl<'a, T: Eq + Clone> From<&'a [T]> for RleVec<T> {
+    fn from(slice: &'a [T]) -> Self {
+        if slice.is_empty() {
+            return RleVec::new()
+        }
+
+        let mut runs = Vec::new();
+        let mut last_value = slice[0].clone();
+        for (i, v) in slice[1..].iter().enumerate() {
+            if *v != last_value {
+                runs.push(InternalRun{
+                    end: i,
+                    value: last_value,
+                });
+                last_value = v.clone();
+            }
+        }
+
+        runs.push(InternalRun{
+            end: slice.len() - 1,
+            value: last_value,
+        });
+
+        RleVec { runs }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Eq + Clone + Send + Sync> RleVec<T> {
+    /// Construct an `RleVec` from a slice using multiple threads.
+    ///
+    /// The slice is split into chunks that are encoded in parallel. The resulting runs are joined
+    /// by merging the runs at the seams, so the result is identical to `RleVec::from(slice)`.
+    /// Only available with the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let v = vec![0; 100_000];
+    /// let rle = RleVec::from_par_slice(&v);
+    ///
+    /// assert_eq!(rle.len(), 100_000);
+    /// assert_eq!(rle.runs_len(), 1);
+    /// ```
+    pub fn from_par_slice(slice: &[T]) -> RleVec<T> {
+        let chunk_size = cmp::max(slice.len() / rayon::current_num_threads(), 4096);
+        slice.par_chunks(chunk_size)
+            .map(RleVec::from)
+            .reduce(RleVec::new, |mut rle, other| { rle.append_owned(other); rle })
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Eq + Send> FromParallelIterator<T> for RleVec<T> {
+    fn from_par_iter<I>(par_iter: I) -> Self where I: IntoParallelIterator<Item=T> {
+        par_iter.into_par_iter()
+            .fold(RleVec::new, |mut rle, value| { rle.push(value); rle })
+            .reduce(RleVec::new, |mut rle, other| { rle.append_owned(other); rle })
+    }
+}
+
+impl<T: Eq> FromIterator<T> for RleVec<T> {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=T> {
+        let mut rle = RleVec::new();
+        rle.extend(iter);
+        rle
+    }
+}
+
+impl<T: Eq> FromIterator<Run<T>> for RleVec<T> {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=Run<T>> {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+
+        let mut rle = RleVec::with_run_capacity(lower);
+        rle.extend(iter);
+        rle
+    }
+}
+
+/// Returns an empty `RleVec`, like [`new`](struct.RleVec.html#method.new).
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// #[derive(Default)]
+/// struct Track {
+///     name: String,
+///     coverage: RleVec<u32>,
+/// }
+///
+/// let track = Track::default();
+/// assert!(track.name.is_empty());
+/// assert_eq!(track.coverage, RleVec::new());
+/// ```
+impl<T> Default for RleVec<T> {
     fn default() -> Self {
         RleVec::new()
     }
-}
+}
+
+/// Appends the values, merging the first values into the last run if they are equal.
+///
+/// Unlike `Vec::extend` no room is reserved from the iterator's `size_hint`. Equal values share a
+/// run, so the number of values says little about the number of runs that are needed.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let mut rle = RleVec::from(&[1, 2, 2][..]);
+///
+/// rle.extend(vec![2, 2, 3]);
+/// assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 2, 3]);
+/// assert_eq!(rle.runs_len(), 3);
+/// ```
+impl<T: Eq> Extend<T> for RleVec<T> {
+    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=T> {
+        let mut iter = iter.into_iter();
+        if let Some(next_value) = iter.next() {
+            // In order te possibly longer use the last run for extending the run-end we do not use the
+            // push function to add values. This gives higher performance to extending the RleVec
+            // with data consisting of large runs.
+            let (pop, end) = if let Some(last_run) = self.runs.last() {
+                if last_run.value == next_value {
+                    (true, last_run.end + 1)
+                } else {
+                    (false, last_run.end + 1)
+                }
+            } else {
+                (false, 0)
+            };
+
+            let mut rle_last = if pop {
+                let mut run = self.runs.pop().unwrap();
+                run.end = end;
+                run
+            } else {
+                InternalRun { value: next_value, end }
+            };
+
+            for value in iter {
+                if value != rle_last.value {
+                    let next_end = rle_last.end;
+                    self.runs.push(rle_last);
+                    rle_last = InternalRun { value, end: next_end };
+                }
+                rle_last.end += 1;
+            }
+            self.runs.push(rle_last);
+        }
+    }
+}
+
+/// Appends the runs like [`push_n`](struct.RleVec.html#method.push_n). Runs of length zero are
+/// skipped and runs equal to the last run are merged into it.
+///
+/// # Example
+/// ```
+/// # use rle_vec::{RleVec, Run};
+/// let mut rle = RleVec::from(&[1, 2][..]);
+///
+/// rle.extend(vec![Run { len: 2, value: 2 }, Run { len: 0, value: 5 }, Run { len: 1, value: 3 }]);
+/// assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 3]);
+/// assert_eq!(rle.runs_len(), 3);
+/// ```
+impl<T: Eq> Extend<Run<T>> for RleVec<T> {
+    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=Run<T>> {
+        for Run{ len, value } in iter {
+            self.push_n(len, value)
+        }
+    }
+}
+
+impl io::Write for RleVec<u8> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok( () )
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok( () ) }
+}
+
+/// Immutable `RelVec` iterator over references of values.
+///
+/// Can be obtained from the [`iter`](struct.RleVec.html#method.iter) or the `into_iter` methods.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+///
+/// let mut iterator = rle.iter();
+/// assert_eq!(iterator.len(), 7);
+/// assert_eq!(iterator.next(), Some(&1));
+/// assert_eq!(iterator.next(), Some(&1));
+/// assert_eq!(iterator.next(), Some(&1));
+/// assert_eq!(iterator.next(), Some(&1));
+/// assert_eq!(iterator.len(), 3);
+/// assert_eq!(iterator.next(), Some(&2));
+/// assert_eq!(iterator.next(), Some(&2));
+/// assert_eq!(iterator.next(), Some(&3));
+/// assert_eq!(iterator.next(), None);
+/// ```
+///
+/// `position` and `rposition` skip whole runs and call the predicate once per run instead of once
+/// per element:
+///
+/// ```
+/// # use rle_vec::RleVec;
+/// let rle: RleVec<_> = (0..1_000_000).map(|i| i / 1000).collect();
+///
+/// let mut calls = 0;
+/// assert_eq!(rle.iter().position(|&v| { calls += 1; v == 500 }), Some(500_000));
+/// assert_eq!(calls, 501);
+/// ```
+pub struct Iter<'a, T: 'a> {
+    rle: &'a RleVec<T>,
+    run_index: usize,
+    index: usize,
+    index_back: usize,
+    run_index_back: usize,
+}
+
+impl<'a, T: 'a> Iter<'a, T> {
+    /// Returns the value that the next call to `next` will return, without advancing the
+    /// iterator.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 2][..]);
+    ///
+    /// let mut iterator = rle.iter();
+    /// assert_eq!(iterator.current_value(), Some(&1));
+    /// iterator.nth(1);
+    /// assert_eq!(iterator.current_value(), Some(&2));
+    /// iterator.next();
+    /// assert_eq!(iterator.current_value(), None);
+    /// ```
+    pub fn current_value(&self) -> Option<&'a T> {
+        if self.index == self.index_back {
+            return None
+        }
+        Some(&self.rle.runs[self.run_index].value)
+    }
+
+    /// Returns how many of the remaining elements, starting with the one the next call to `next`
+    /// will return, belong to the same run. Elements already consumed from the back are not
+    /// counted. Returns `0` if the iterator is exhausted.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// let mut iterator = rle.iter();
+    /// let mut runs = Vec::new();
+    /// while let Some(&value) = iterator.current_value() {
+    ///     runs.push((value, iterator.current_run_remaining()));
+    ///     iterator.nth(iterator.current_run_remaining() - 1);
+    /// }
+    /// assert_eq!(runs, vec![(1, 3), (2, 2), (3, 1)]);
+    /// ```
+    pub fn current_run_remaining(&self) -> usize {
+        if self.index == self.index_back {
+            return 0
+        }
+        cmp::min(self.rle.runs[self.run_index].end + 1, self.index_back) - self.index
+    }
+}
+
+impl<'a, T: 'a> IntoIterator for &'a RleVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter {
+            rle: self,
+            run_index: 0,
+            index: 0,
+            run_index_back: self.runs.len().saturating_sub(1),
+            index_back: self.len(), // starts out of range
+        }
+    }
+}
+
+/// Consumes the `RleVec` into an iterator of owned values, see [`IntoIter`](struct.IntoIter.html).
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let rle = RleVec::from(&["a", "a", "b"][..]);
+///
+/// let mut joined = String::new();
+/// for value in rle {
+///     joined.push_str(value);
+/// }
+/// assert_eq!(joined, "aab");
+/// ```
+impl<T: Clone> IntoIterator for RleVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            len: self.len(),
+            runs: self.runs.into_iter(),
+            value: None,
+            run_remaining: 0,
+            last_end: 0,
+        }
+    }
+}
+
+/// Owning `RleVec` iterator over the values.
+///
+/// Can be obtained by calling `into_iter` on an `RleVec`. Each value is cloned for all but the
+/// last element of its run, the last element moves the value out, so runs of length one are
+/// never cloned.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let rle = RleVec::from(&["a", "a", "b"][..]);
+///
+/// let mut iterator = rle.into_iter();
+/// assert_eq!(iterator.len(), 3);
+/// assert_eq!(iterator.next(), Some("a"));
+/// assert_eq!(iterator.next(), Some("a"));
+/// assert_eq!(iterator.next(), Some("b"));
+/// assert_eq!(iterator.next(), None);
+/// ```
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    runs: std::vec::IntoIter<InternalRun<T>>,
+    value: Option<T>,
+    run_remaining: usize,
+    last_end: usize,
+    len: usize,
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.run_remaining == 0 {
+            let InternalRun { end, value } = self.runs.next()?;
+            self.run_remaining = end + 1 - self.last_end;
+            self.last_end = end + 1;
+            self.value = Some(value);
+        }
+        self.run_remaining -= 1;
+        self.len -= 1;
+        if self.run_remaining == 0 {
+            self.value.take()
+        } else {
+            self.value.clone()
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn count(self) -> usize {
+        self.len
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IntoIter<T> { }
+
+impl<T: Clone> FusedIterator for IntoIter<T> { }
+
+/// Iterator over the values removed by [`drain`](struct.RleVec.html#method.drain).
+///
+/// The values are already removed from the `RleVec`, dropping the iterator early discards the
+/// remaining values.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let mut rle = RleVec::from(&[1, 2, 2, 3][..]);
+///
+/// let mut iterator = rle.drain(1..);
+/// assert_eq!(iterator.len(), 3);
+/// assert_eq!(iterator.next(), Some(2));
+/// drop(iterator);
+/// assert_eq!(rle.to_vec(), vec![1]);
+/// ```
+#[derive(Debug)]
+pub struct Drain<'a, T: 'a> {
+    iter: IntoIter<T>,
+    marker: PhantomData<&'a mut RleVec<T>>,
+}
+
+impl<'a, T: Clone> Iterator for Drain<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: Clone> ExactSizeIterator for Drain<'a, T> { }
+
+impl<'a, T: Clone> FusedIterator for Drain<'a, T> { }
+
+impl<'a, T: 'a> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.index_back {
+            return None
+        }
+        let run = &self.rle.runs[self.run_index];
+        self.index += 1;
+        if self.index > run.end {
+            self.run_index += 1;
+        }
+        Some(&run.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.index_back - self.index;
+        (len, Some(len))
+    }
+
+    fn count(self) -> usize {
+        // thanks to the ExactSizeIterator impl
+        self.len()
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+
+    fn position<P>(&mut self, mut predicate: P) -> Option<usize> where P: FnMut(Self::Item) -> bool {
+        let start = self.index;
+        while self.index < self.index_back {
+            let run = &self.rle.runs[self.run_index];
+            if predicate(&run.value) {
+                let position = self.index - start;
+                self.next();
+                return Some(position)
+            }
+            self.index = cmp::min(run.end + 1, self.index_back);
+            if self.index > run.end {
+                self.run_index += 1;
+            }
+        }
+        None
+    }
+
+    fn rposition<P>(&mut self, mut predicate: P) -> Option<usize> where P: FnMut(Self::Item) -> bool {
+        let start = self.index;
+        while self.index_back > self.index {
+            // the back run index points at the run of `index_back` or the one before
+            let mut p = self.run_index_back;
+            if p > 0 && self.index_back - 1 <= self.rle.runs[p - 1].end {
+                p -= 1;
+            }
+            self.run_index_back = p;
+            if predicate(&self.rle.runs[p].value) {
+                self.index_back -= 1;
+                return Some(self.index_back - start)
+            }
+            self.index_back = cmp::max(self.rle.run_start(p), self.index);
+        }
+        None
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // compare against the remaining length so `index + n` cannot overflow
+        if n >= self.index_back - self.index {
+            self.index = self.index_back;
+            return None
+        }
+        self.index += n;
+        self.run_index = self.rle.run_index(self.index);
+        self.next()
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for Iter<'a, T> { }
+
+impl<'a, T: 'a> FusedIterator for Iter<'a, T> { }
+
+impl<'a, T: 'a> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index_back == self.index {
+            return None
+        }
+        self.index_back -= 1;
+        if self.run_index_back > 0 && self.index_back <= self.rle.runs[self.run_index_back - 1].end {
+            self.run_index_back -= 1;
+        }
+        Some(&self.rle.runs[self.run_index_back].value)
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.index_back - self.index {
+            self.index_back = self.index;
+            return None
+        }
+        if n > 0 {
+            self.index_back -= n;
+            self.run_index_back = self.rle.run_index(self.index_back);
+        }
+        self.next_back()
+    }
+}
+
+/// An `RleVec` with a sampled index for fast random access.
+///
+/// Can be obtained from the [`build_access_index`](struct.RleVec.html#method.build_access_index)
+/// method. All methods taking `&self` on `RleVec` are available through `Deref`.
+///
+/// # Example
+/// ```
+/// # use rle_vec::RleVec;
+/// let rle: RleVec<_> = (0..1000).map(|i| i / 10).collect();
+///
+/// let indexed = rle.build_access_index(64);
+/// assert_eq!(indexed[512], 51);
+/// assert_eq!(indexed.iter_range(98..102).collect::<Vec<_>>(), vec![&9, &9, &10, &10]);
+/// assert_eq!(indexed.runs_len(), 100);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedRleVec<'a, T: 'a> {
+    rle: &'a RleVec<T>,
+    k: usize,
+    samples: Vec<usize>,
+}
+
+impl<'a, T: 'a> IndexedRleVec<'a, T> {
+    /// Returns a reference to the value at `index`, or `None` if it is out of bounds.
+    ///
+    /// Only the runs between two samples are searched.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        self.run_index(index).map(|p| &self.rle.runs[p].value)
+    }
+
+    /// Returns an iterator over the values in `range`.
+    ///
+    /// # Panics
+    /// Panics if the start of the range is greater than its end or if the end is greater than the
+    /// length.
+    pub fn iter_range<R>(&self, range: R) -> Iter<'a, T> where R: RangeBounds<usize> {
+        let range = self.rle.resolve_range(range);
+        let run_index_back = match range.end {
+            0 => 0,
+            end => self.run_index(end - 1).unwrap(),
+        };
+        Iter {
+            rle: self.rle,
+            run_index: self.run_index(range.start).unwrap_or(run_index_back),
+            index: range.start,
+            run_index_back,
+            index_back: range.end,
+        }
+    }
+
+    /// Returns the sample interval `k` of the index.
+    pub fn sample_interval(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of bytes used by the index, not including the `RleVec` itself.
+    pub fn memory_overhead(&self) -> usize {
+        self.samples.capacity() * mem::size_of::<usize>()
+    }
+
+    /// Returns the indexed `RleVec`.
+    pub fn inner(&self) -> &'a RleVec<T> {
+        self.rle
+    }
+
+    fn run_index(&self, index: usize) -> Option<usize> {
+        if index >= self.rle.len() { return None }
+        let sample = index / self.k;
+        let lo = self.samples[sample];
+        let hi = self.samples.get(sample + 1).map_or(self.rle.runs.len(), |&p| p + 1);
+        match self.rle.runs[lo..hi].binary_search_by(|run| run.end.cmp(&index)) {
+            Ok(i) | Err(i) => Some(lo + i),
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for IndexedRleVec<'a, T> {
+    type Target = RleVec<T>;
+
+    fn deref(&self) -> &RleVec<T> {
+        self.rle
+    }
+}
+
+impl<'a, T: 'a> Index<usize> for IndexedRleVec<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(value) => value,
+            None => panic!("index out of bounds: the len is {} but the index is {}", self.rle.len(), index)
+        }
+    }
+}
+
+/// Immutable `RelVec` iterator over runs.
+///
+/// Can be obtained from the [`runs`](struct.RleVec.html#method.runs) method.
+/// Because internally runs are stored using the end values a new Run is
+/// allocated in each iteration.
+///
+/// The iterator position is a pair of run indices: the runs before the front index and the runs
+/// from the back index onward have been yielded, the runs in between have not. Runs are always
+/// yielded whole, so both ends meet at a run boundary. `len()` returns the number of runs not yet
+/// yielded and [`remaining_elements`](#method.remaining_elements) the number of elements they
+/// cover.
+/// Cloning the iterator copies this position, which makes it cheap to store a checkpoint and
+/// iterate again from there.
+///
+/// # Example
+/// ```
+/// # use rle_vec::{RleVec, Run};
+/// let rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
+///
+/// let mut iterator = rle.runs();
+/// assert_eq!(iterator.next(), Some(Run{ len: 4, value: &1 }));
+///
+/// let checkpoint = iterator.clone();
+/// assert_eq!(iterator.next(), Some(Run{ len: 2, value: &2 }));
+/// assert_eq!(iterator.next(), Some(Run{ len: 1, value: &3 }));
+/// assert_eq!(iterator.next(), None);
+///
+/// assert_eq!(checkpoint.remaining_elements(), 3);
+/// assert_eq!(checkpoint.count(), 2);
+/// ```
+///
+/// The iterator is double ended, so the last run matching a predicate is found without visiting
+/// the runs before it:
+///
+/// ```
+/// # use rle_vec::{RleVec, Run};
+/// let rle = RleVec::from(&[1, 1, 2, 3, 3, 3, 1][..]);
+///
+/// assert_eq!(rle.runs().rev().find(|run| run.len > 1), Some(Run{ len: 3, value: &3 }));
+/// ```
+pub struct Runs<'a, T:'a> {
+    rle: &'a RleVec<T>,
+    run_index: usize,
+    last_end: usize,
+    run_index_back: usize,
+}
+
+impl<'a, T: 'a> Iterator for Runs<'a, T> {
+    type Item = Run<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.run_index == self.run_index_back {
+            return None
+        }
+        let &InternalRun { ref value, end } = self.rle.runs.index(self.run_index);
+        let len = end - self.last_end + 1;
+        self.run_index += 1;
+        self.last_end = end + 1;
+        Some(Run { len, value })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.run_index_back - self.run_index;
+        (len, Some(len))
+    }
+
+    fn count(self) -> usize {
+        // thanks to the ExactSizeIterator impl
+        self.len()
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.run_index += cmp::min(n, self.run_index_back - self.run_index);
+        self.last_end = if self.run_index != 0 {
+            self.rle.runs[self.run_index - 1].end + 1
+        } else { 0 };
+        self.next()
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for Runs<'a, T> { }
+
+impl<'a, T: 'a> FusedIterator for Runs<'a, T> { }
+
+impl<'a, T: 'a> DoubleEndedIterator for Runs<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.run_index == self.run_index_back {
+            return None
+        }
+        self.run_index_back -= 1;
+        let start = self.rle.run_start(self.run_index_back);
+        let InternalRun { ref value, end } = self.rle.runs[self.run_index_back];
+        Some(Run { len: end + 1 - start, value })
+    }
+}
+
+impl<'a, T: 'a> Clone for Runs<'a, T> {
+    fn clone(&self) -> Self {
+        Runs { rle: self.rle, run_index: self.run_index, last_end: self.last_end, run_index_back: self.run_index_back }
+    }
+}
+
+impl<'a, T: 'a> Runs<'a, T> {
+    /// Returns the number of elements in the runs that have not been yielded yet.
+    ///
+    /// # Example
+    /// ```
+    /// # use rle_vec::RleVec;
+    /// let rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+    ///
+    /// let mut runs = rle.runs();
+    /// assert_eq!(runs.remaining_elements(), 6);
+    /// runs.next();
+    /// assert_eq!(runs.remaining_elements(), 3);
+    /// runs.nth(5);
+    /// assert_eq!(runs.remaining_elements(), 0);
+    /// ```
+    pub fn remaining_elements(&self) -> usize {
+        if self.run_index == self.run_index_back {
+            return 0
+        }
+        self.rle.runs[self.run_index_back - 1].end + 1 - self.last_end
+    }
+}
+
+/// Iterator over the owned runs removed from an `RleVec`.
+///
+/// Can be obtained from the [`drain_runs`](struct.RleVec.html#method.drain_runs) method. The runs
+/// that were not yielded are dropped together with the iterator.
+///
+/// # Example
+/// ```
+/// # use rle_vec::{RleVec, Run};
+/// let mut rle = RleVec::from(&[1, 1, 2, 3][..]);
+///
+/// let mut iterator = rle.drain_runs(0..2);
+/// assert_eq!(iterator.next_back(), Some(Run{ len: 1, value: 2 }));
+/// assert_eq!(iterator.next(), Some(Run{ len: 2, value: 1 }));
+/// assert_eq!(iterator.next(), None);
+/// ```
+#[derive(Debug)]
+pub struct DrainRuns<'a, T: 'a> {
+    iter: std::vec::Drain<'a, InternalRun<T>>,
+    // the start of the next run relative to the start of the drained range
+    start: usize,
+}
+
+impl<'a, T: 'a> Iterator for DrainRuns<'a, T> {
+    type Item = Run<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let run = self.iter.next()?;
+        let len = run.end + 1 - self.start;
+        self.start = run.end + 1;
+        Some(Run { len, value: run.value })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for DrainRuns<'a, T> { }
+
+impl<'a, T: 'a> DoubleEndedIterator for DrainRuns<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let run = self.iter.next_back()?;
+        let start = self.iter.as_slice().last().map_or(self.start, |prev| prev.end + 1);
+        Some(Run { len: run.end + 1 - start, value: run.value })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::iter_nth_zero, clippy::iter_skip_next, clippy::iter_skip_zero, clippy::manual_repeat_n,
+        clippy::clone_on_copy, clippy::unused_io_amount)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rare_usage() {
+        // from slice
+
+        let rle: RleVec<i32> = RleVec::from(&[][..]);
+        assert_eq!(rle.to_vec(), vec![]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![]);
+
+        let rle: RleVec<i32> = RleVec::from(&[1][..]);
+        assert_eq!(rle.to_vec(), vec![1]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 1, value: &1 }]);
+
+        let rle: RleVec<i32> = RleVec::from(&[1, 2][..]);
+        assert_eq!(rle.to_vec(), vec![1, 2]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 1, value: &1 }, Run { len: 1, value: &2 }]);
+
+        let rle: RleVec<i32> = RleVec::from(&[1, 1][..]);
+        assert_eq!(rle.to_vec(), vec![1, 1]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 2, value: &1 }]);
+
+        // from iter
+
+        let rle: RleVec<i32> = RleVec::from_iter(0..0);
+        assert_eq!(rle.to_vec(), vec![]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![]);
+
+        let rle: RleVec<i32> = RleVec::from_iter(1..2);
+        assert_eq!(rle.to_vec(), vec![1]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 1, value: &1 }]);
+
+        let rle: RleVec<i32> = RleVec::from_iter(1..3);
+        assert_eq!(rle.to_vec(), vec![1, 2]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 1, value: &1 }, Run { len: 1, value: &2 }]);
+
+        use std::iter::repeat;
+        let rle: RleVec<i32> = RleVec::from_iter(repeat(1).take(2));
+        assert_eq!(rle.to_vec(), vec![1, 1]);
+        let runs: Vec<_> = rle.runs().collect();
+        assert_eq!(runs, vec![Run{ len: 2, value: &1 }]);
+    }
+
+    #[test]
+    fn basic_usage() {
+        let mut rle = RleVec::<i64>::new();
+        rle.push(1);
+        rle.push(1);
+        rle.push(1);
+        rle.push(1);
+        rle.push(2);
+        rle.push(2);
+        rle.push(2);
+        rle.push(3);
+        rle.push(3);
+        rle.push(4);
+        assert_eq!(rle.len(), 10);
+        assert_eq!(rle.runs_len(), 4);
+
+        rle.push_n(3, 4);
+        assert_eq!(rle.len(), 13);
+        assert_eq!(rle.runs_len(), 4);
+        assert_eq!(rle.last(), Some(&4));
+        rle.push_n(3, 5);
+        assert_eq!(rle.len(), 16);
+        assert_eq!(rle.runs_len(), 5);
+        assert_eq!(rle.last(), Some(&5));
+        assert_eq!(rle.last_run(), Some(Run {value: &5, len: 3}));
+        rle.clear();
+        assert_eq!(rle.len(), 0);
+        assert_eq!(rle.runs_len(), 0);
+        assert_eq!(rle.last(), None);
+        assert_eq!(rle.last_run(), None);
+
+        let mut rle = RleVec::default();
+        rle.push(1);
+        assert_eq!(rle.len(), 1);
+    }
+
+    #[test]
+    fn setting_values() {
+        let mut rle = RleVec::<i64>::new();
+        rle.push(1);
+        rle.set(0, 10);
+        assert_eq!(rle.len(), 1);
+        assert_eq!(rle.runs_len(), 1);
+        assert_eq!(rle[0], 10);
+
+        let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 2, 3, 3, 4, 5][..]);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+
+        //set no change
+        //run size > 1
+        rle.set(0, 1);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+        rle.set(2, 1);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+        rle.set(4, 2);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+        rle.set(6, 2);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+        //run size == 1
+        rle.set(9, 4);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+        rle.set(10, 5);
+        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+
+        //set change no joins
+        //run size > 1
+        rle.set(0, 2);
+        assert_eq!(rle.to_vec(), vec![2,1,1,1,2,2,2,3,3,4, 5]);
+        rle.set(2, 2);
+        assert_eq!(rle.to_vec(), vec![2,1,2,1,2,2,2,3,3,4, 5]);
+        rle.set(4, 3);
+        assert_eq!(rle.to_vec(), vec![2,1,2,1,3,2,2,3,3,4, 5]);
+        rle.set(8, 7);
+        assert_eq!(rle.to_vec(), vec![2,1,2,1,3,2,2,3,7,4, 5]);
+        //run size == 1
+        rle.set(0, 3);
+        assert_eq!(rle.to_vec(), vec![3,1,2,1,3,2,2,3,7,4, 5]);
+        rle.set(3, 4);
+        assert_eq!(rle.to_vec(), vec![3,1,2,4,3,2,2,3,7,4, 5]);
+        rle.set(10, 7);
+        assert_eq!(rle.to_vec(), vec![3,1,2,4,3,2,2,3,7,4, 7]);
+        assert_eq!(rle.runs_len(), 10);
+
+        //set change, with join
+        rle.set(0, 1);
+        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,2,2,3,7,4, 7]);
+        assert_eq!(rle.runs_len(), 9);
+        rle.set(5, 3);
+        assert_eq!(rle.runs_len(), 9);
+        rle.set(6, 3);
+        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,3,3,3,7,4, 7]);
+        assert_eq!(rle.runs_len(), 7);
+        rle.set(10, 4);
+        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,3,3,3,7,4, 4]);
+        assert_eq!(rle.runs_len(), 6);
+    }
+
+    #[test]
+    fn removing_values() {
+        let mut rle = RleVec::from(&[1, 1, 1, 1, 1, 2, 1, 1, 1, 4, 4, 3, 3][..]);
+        assert_eq!(rle.len(), 13);
+        assert_eq!(rle.runs_len(), 5);
+
+        let value = rle.remove(5);
+        assert_eq!(value, 2);
+        assert_eq!(rle.len(), 12);
+        assert_eq!(rle.runs_len(), 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 1, 4, 4, 3, 3]);
+
+        let value = rle.remove(7);
+        assert_eq!(value, 1);
+        assert_eq!(rle.len(), 11);
+        assert_eq!(rle.runs_len(), 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 4, 4, 3, 3]);
+
+        let value = rle.remove(10);
+        assert_eq!(value, 3);
+        assert_eq!(rle.len(), 10);
+        assert_eq!(rle.runs_len(), 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 4, 4, 3]);
+
+        // removing the only element
+        let mut rle = RleVec::from(&[1][..]);
+        assert_eq!(rle.remove(0), 1);
+        assert!(rle.is_empty());
+
+        // neighbors merge when only two runs remain
+        let mut rle = RleVec::from(&[1, 2, 1][..]);
+        assert_eq!(rle.remove(1), 2);
+        assert_eq!(rle.runs_len(), 1);
+        assert_eq!(rle.to_vec(), vec![1, 1]);
+
+        // removing a size 1 last run
+        let mut rle = RleVec::from(&[1, 2, 3, 4][..]);
+        assert_eq!(rle.remove(3), 4);
+        assert_eq!(rle.to_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn run_capacity() {
+        let mut rle = RleVec::new();
+        rle.reserve_runs_for(100, 10);
+        assert!(rle.run_capacity() >= 10);
+        rle.reserve_runs_for(101, 10);
+        assert!(rle.run_capacity() >= 11);
+
+        // the reservation is not used up by equal values
+        let capacity = rle.run_capacity();
+        for i in 0..100 {
+            rle.push(i / 10);
+        }
+        assert_eq!(rle.runs_len(), 10);
+        assert_eq!(rle.run_capacity(), capacity);
+
+        rle.reserve_runs_for(0, 1);
+        assert_eq!(rle.run_capacity(), capacity);
+        rle.reserve_runs_for(7, 1);
+        assert!(rle.run_capacity() >= 17);
+        rle.reserve_runs_for(usize::MAX, usize::MAX);
+        assert!(rle.run_capacity() >= 11);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected run length must be at least 1")]
+    fn reserve_runs_for_zero_run_len() {
+        RleVec::<u8>::new().reserve_runs_for(10, 0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_with_capacity() {
+        let rle = RleVec::<u8>::with_capacity(3);
+        assert!(rle.run_capacity() >= 3);
+        assert!(rle.is_empty());
+    }
+
+    #[test]
+    fn inserting_values() {
+        let mut v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let mut rle = RleVec::from(&v[..]);
+        rle.insert(0,1);
+        v.insert(0,1);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.len(),18);
+        rle.insert(18,9);
+        v.insert(18,9);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        rle.insert(19,10);
+        v.insert(19,10);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+
+        rle.insert(2,0);
+        v.insert(2,0);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.runs_len(), 9);
+
+        rle.insert(8,0);
+        v.insert(8,0);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.runs_len(), 11);
+
+        rle.insert(13,4);
+        v.insert(13,4);
+        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.runs_len(), 12);
+
+        let v = vec![0,0,0,1,1,1,1,2,2,3];
+        let mut rle: RleVec<_> = v.into_iter().collect();
+        rle.set(1,2);
+        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,1,1,1,2,2,3]);
+        rle.insert(4,4);
+        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,2,2,3]);
+        rle.insert(7,1);
+        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,1,2,2,3]);
+        rle.insert(8,8);
+        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,8,1,2,2,3]);
+    }
+
+    #[test]
+    fn from_slice() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let rle = RleVec::from(&v[..]);
+        assert_eq!((0..v.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.len(),17);
+
+        let v2: Vec<_> = rle.into();
+        assert_eq!(v2,v);
+    }
+
+    #[test]
+    fn iterators() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,123,0,90,90,99];
+        let rle = v.iter().cloned().collect::<RleVec<_>>();
+        assert_eq!((0..v.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        assert_eq!(rle.len(), 17);
+
+        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), v);
+        assert_eq!(RleVec::<i64>::new().iter().next(), None);
+
+        let v2 = (0..100).collect::<Vec<usize>>();
+        let rle2 = v2.iter().cloned().collect::<RleVec<_>>();
+        assert_eq!(rle2.iter().cloned().collect::<Vec<_>>(), v2);
+        assert_eq!(rle2.iter().skip(0).cloned().collect::<Vec<_>>(), v2);
+
+        assert_eq!(rle2.iter().nth(0), Some(&0));
+        assert_eq!(rle2.iter().nth(5), Some(&5));
+        assert_eq!(rle2.iter().nth(99), Some(&99));
+        assert_eq!(rle2.iter().nth(100), None);
+        let mut it = rle2.iter();
+        it.nth(0);
+        assert_eq!(it.nth(0), Some(&1));
+
+        assert_eq!(rle.iter().nth(3), Some(&1));
+        assert_eq!(rle.iter().nth(14), Some(&90));
+        assert_eq!(rle.iter().nth(15), Some(&90));
+
+        assert_eq!(rle.iter().skip(2).next(), Some(&0));
+        assert_eq!(rle.iter().skip(3).next(), Some(&1));
+
+        assert_eq!(rle.iter().max(), Some(&123));
+        assert_eq!(rle.iter().min(), Some(&0));
+        assert_eq!(rle.iter().skip(13).max(), Some(&99));
+        assert_eq!(rle.iter().skip(13).min(), Some(&0));
+        assert_eq!(rle.iter().skip(13).take(2).max(), Some(&90));
+        assert_eq!(rle.iter().skip(13).take(2).min(), Some(&0));
+
+        assert_eq!(rle.iter().count(), 17);
+        assert_eq!(rle.iter().skip(10).last(), Some(&99));
+        assert_eq!(rle.iter().skip(30).last(), None);
+
+        //runiters
+        assert_eq!(rle.runs().map(|r| r.value).collect::<Vec<_>>(), vec![&0,&1,&3,&123,&0,&90,&99]);
+        assert_eq!(rle.runs().map(|r| r.len).collect::<Vec<_>>(), vec![3,7,2,1,1,2,1]);
+
+        let mut copy = RleVec::new();
+        for r in rle.runs() {
+            copy.push_n(r.len, r.value.clone());
+        }
+        assert_eq!(copy.iter().cloned().collect::<Vec<_>>(), v);
+        let copy2: RleVec<i32> = rle.runs().map(|r| Run { value: r.value.clone(), len: r.len }).collect();
+        assert_eq!(copy2.iter().cloned().collect::<Vec<_>>(), v);
+    }
+
+    #[test]
+    fn back_iterators() {
+        let rle = RleVec::from(&[0,1,1,3,3,9,99][..]);
+
+        // only next_back()
+        let mut iter = rle.iter();
+        assert_eq!(iter.next_back(), Some(&99));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&0));
+        assert_eq!(iter.next_back(), None);
+
+        // next_back() combine with next()
+        let mut iter = rle.iter();
+        assert_eq!(iter.next_back(), Some(&99));
+        assert_eq!(iter.next(),      Some(&0));
+        assert_eq!(iter.next(),      Some(&1));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(),      Some(&1));
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(),      None);
+
+        // rare usages of next_back() combine with next()
+        let rle = RleVec::from(&[0][..]);
+        let mut iter = rle.iter();
+        assert_eq!(iter.next_back(), Some(&0));
+        assert_eq!(iter.next(),      None);
+
+        let rle = RleVec::<i32>::from(&[][..]);
+        let mut iter = rle.iter();
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(),      None);
+
+        // front and back cursors meeting inside a run
+        let v = [0, 1, 1, 1, 1, 1, 2, 2, 3];
+        let rle = RleVec::from(&v[..]);
+        let mut iter = rle.iter();
+        assert_eq!(iter.nth(2), Some(&1));
+        assert_eq!(iter.nth_back(4), Some(&1));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&1));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        for front in 0..v.len() + 1 {
+            for back in 0..v.len() + 1 {
+                for reverse in &[false, true] {
+                    let (mut a, mut b) = (rle.iter(), v.iter());
+                    assert_eq!(a.nth(front), b.nth(front));
+                    assert_eq!(a.nth_back(back), b.nth_back(back));
+                    assert_eq!(a.len(), b.len());
+                    if *reverse {
+                        assert!(a.rev().eq(b.rev()));
+                    } else {
+                        assert!(a.eq(b));
+                    }
+                }
+            }
+        }
+        assert!(rle.iter().rev().eq(v.iter().rev()));
+        assert_eq!(rle.iter().rfind(|&&x| x == 1), Some(&1));
+        assert_eq!(rle.iter().rposition(|&x| x == 1), Some(5));
+    }
+
+    #[test]
+    fn interleaved_back_iterators() {
+        let equal = [7; 6];
+        let unique = [0, 1, 2, 3, 4, 5];
+        let mixed = [0, 0, 1, 1, 1, 2];
+        for values in &[&equal[..], &unique[..], &mixed[..]] {
+            let rle = RleVec::from(*values);
+            // every sequence of front and back steps, with more steps than elements
+            for pattern in 0..1u32 << 8 {
+                let (mut a, mut b) = (rle.iter(), values.iter());
+                for step in 0..8 {
+                    if pattern & (1 << step) == 0 {
+                        assert_eq!(a.next(), b.next());
+                    } else {
+                        assert_eq!(a.next_back(), b.next_back());
+                    }
+                    assert_eq!(a.len(), b.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn back_run_iters() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 3, 3, 4][..]);
+        let forward: Vec<_> = rle.runs().collect();
+        let mut backward: Vec<_> = rle.runs().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(rle.runs().last(), Some(Run { len: 1, value: &4 }));
+
+        // both ends meet at a run boundary and every run is yielded once
+        let mut runs = rle.runs();
+        assert_eq!(runs.next_back(), Some(Run { len: 1, value: &4 }));
+        assert_eq!(runs.next(), Some(Run { len: 3, value: &1 }));
+        assert_eq!(runs.remaining_elements(), 3);
+        assert_eq!(runs.next_back(), Some(Run { len: 2, value: &3 }));
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs.clone().last(), Some(Run { len: 1, value: &2 }));
+        assert_eq!(runs.next(), Some(Run { len: 1, value: &2 }));
+        assert_eq!(runs.remaining_elements(), 0);
+        assert_eq!(runs.next_back(), None);
+        assert_eq!(runs.next(), None);
+
+        let mut runs = rle.runs();
+        runs.next_back();
+        assert_eq!(runs.nth(5), None);
+        assert_eq!(runs.next_back(), None);
+
+        let empty = RleVec::<u8>::new();
+        assert_eq!(empty.runs().next_back(), None);
+        assert_eq!(empty.runs().remaining_elements(), 0);
+    }
+
+    #[test]
+    fn iter_position() {
+        let values = [0, 0, 1, 1, 1, 2, 0, 0, 3, 1];
+        let rle = RleVec::from(&values[..]);
+        for target in 0..5 {
+            for skip_front in 0..values.len() + 1 {
+                for skip_back in 0..values.len() + 1 {
+                    let start = || {
+                        let (mut a, mut b) = (rle.iter(), values.iter());
+                        for _ in 0..skip_front {
+                            assert_eq!(a.next(), b.next());
+                        }
+                        for _ in 0..skip_back {
+                            assert_eq!(a.next_back(), b.next_back());
+                        }
+                        (a, b)
+                    };
+                    let (mut a, mut b) = start();
+                    let (mut ra, mut rb) = start();
+
+                    // repeated searches continue after the previous match
+                    for _ in 0..3 {
+                        assert_eq!(a.position(|&v| v == target), b.position(|&v| v == target));
+                        assert_eq!(a.len(), b.len());
+                        assert_eq!(ra.rposition(|&v| v == target), rb.rposition(|&v| v == target));
+                        assert_eq!(ra.len(), rb.len());
+                    }
+                    assert!(a.eq(b));
+                    assert!(ra.rev().eq(rb.rev()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn iter_exact_size() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 3, 3][..]);
+        let mut iter = rle.iter();
+        for remaining in (0..rle.len() + 1).rev() {
+            assert_eq!(iter.len(), remaining);
+            assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+            iter.next();
+        }
+        assert_eq!(iter.len(), 0);
+        assert_eq!(rle.iter().map(|v| v * 2).size_hint(), (6, Some(6)));
+    }
+
+    #[test]
+    fn runs_exact_size() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 3, 3][..]);
+        let mut runs = rle.runs();
+        for remaining in (0..rle.runs_len() + 1).rev() {
+            assert_eq!(runs.len(), remaining);
+            assert_eq!(runs.size_hint(), (remaining, Some(remaining)));
+            runs.next();
+        }
+
+        // the exact size flows through adapters
+        let lens = rle.runs().map(|run| run.len);
+        assert_eq!(lens.len(), 3);
+        let indexed: Vec<_> = rle.runs().zip(0..).map(|(run, i)| (i, *run.value)).collect();
+        assert_eq!(indexed, vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(rle.runs().rev().len(), 3);
+    }
+
+    #[test]
+    fn fused_iterators() {
+        fn assert_fused<I: FusedIterator>(_: &I) { }
+
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        let mut iter = rle.iter();
+        assert_fused(&iter);
+        assert_eq!(iter.nth(3), Some(&3));
+        assert_eq!(iter.nth(1), None);
+        for _ in 0..3 {
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next_back(), None);
+            assert_eq!(iter.nth(0), None);
+        }
+
+        let mut iter = rle.iter();
+        assert_eq!(iter.nth(10), None);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.len(), 0);
+
+        let mut runs = rle.runs();
+        assert_fused(&runs);
+        assert_eq!(runs.nth(5), None);
+        for _ in 0..3 {
+            assert_eq!(runs.next(), None);
+            assert_eq!(runs.next_back(), None);
+        }
+
+        let mut values = rle.clone().into_iter();
+        assert_fused(&values);
+        assert_eq!(values.by_ref().count(), 5);
+        let mut values = rle.clone().into_iter();
+        assert_eq!(values.nth(4), Some(3));
+        for _ in 0..3 {
+            assert_eq!(values.next(), None);
+            assert_eq!(values.len(), 0);
+        }
+
+        let mut copy = rle.clone();
+        let mut drain = copy.drain(3..);
+        assert_fused(&drain);
+        assert_eq!(drain.by_ref().last(), Some(3));
+        assert_eq!(drain.next(), None);
+        assert_eq!(drain.next(), None);
+    }
+
+    #[test]
+    fn run_iters() {
+        let rle = RleVec::from(&[1,1,1,1,1,2,2,2,2,3,3,3,5,5,5,5][..]);
+
+        let mut iterator = rle.runs();
+
+        assert_eq!(iterator.next(), Some(Run{ len: 5, value: &1 }));
+        assert_eq!(iterator.next(), Some(Run{ len: 4, value: &2 }));
+        assert_eq!(iterator.next(), Some(Run{ len: 3, value: &3 }));
+        assert_eq!(iterator.next(), Some(Run{ len: 4, value: &5 }));
+        assert_eq!(iterator.next(), None);
+        assert_eq!(iterator.next(), None);
+
+        let mut iterator = rle.runs();
+
+        assert_eq!(iterator.nth(0), Some(Run{ len: 5, value: &1 }));
+        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &2 }));
+        assert_eq!(iterator.nth(0), Some(Run{ len: 3, value: &3 }));
+        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &5 }));
+        assert_eq!(iterator.nth(0), None);
+
+        let mut iterator = rle.runs();
+
+        assert_eq!(iterator.nth(0), Some(Run{ len: 5, value: &1 }));
+        assert_eq!(iterator.nth(1), Some(Run{ len: 3, value: &3 }));
+        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &5 }));
+        assert_eq!(iterator.nth(0), None);
+
+        assert_eq!(rle.runs().count(), 4);
+        assert_eq!(rle.runs().last(), Some(Run{ len: 4, value: &5 }));
+        assert_eq!(rle.runs().skip(10).last(), None);
+
+    }
+
+    #[test]
+    fn starts_ends() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let rle = v.iter().cloned().collect::<RleVec<_>>();
+        assert_eq!(rle.starts(), vec![0,3,10,12,13,14,16]);
+        assert_eq!(rle.ends(),   vec![2,9,11,12,13,15,16]);
+
+        let rle = RleVec::<i64>::new();
+        assert!(rle.starts().is_empty());
+        assert!(rle.ends().is_empty());
+    }
+
+    #[test]
+    fn try_index() {
+        let rle = RleVec::from(&[0,0,0,1,1,3][..]);
+        for i in 0..rle.len() {
+            assert_eq!(rle.try_index(i), Ok(&rle[i]));
+        }
+        assert_eq!(rle.try_index(6), Err(OutOfBoundsError { index: 6, len: 6 }));
+        assert!(RleVec::<i32>::new().try_index(0).is_err());
+
+        fn lookup(rle: &RleVec<i32>, index: usize) -> io::Result<i32> {
+            Ok(*rle.try_index(index)?)
+        }
+        assert_eq!(lookup(&rle, 5).unwrap(), 3);
+        let err = lookup(&rle, 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "index 10 out of bounds for RleVec of length 6");
+    }
+
+    #[test]
+    fn segments_above() {
+        let v = vec![0, 0, 20, 30, 30, 30, 1, 25, 25, 25, 10];
+        let rle = RleVec::from(&v[..]);
+        assert_eq!(rle.segment_count_above(&10), 3);
+        assert_eq!(rle.total_length_above(&10), v.iter().filter(|&&x| x > 10).count());
+        assert_eq!(rle.longest_segment_above(&10), Some(Run { len: 3, value: &30 }));
+        assert_eq!(rle.longest_segment_above(&25), Some(Run { len: 3, value: &30 }));
+        assert_eq!(rle.longest_segment_above(&30), None);
+        assert_eq!(rle.segment_count_above(&-1), rle.runs_len());
+
+        let rle = RleVec::<i32>::new();
+        assert_eq!(rle.segment_count_above(&0), 0);
+        assert_eq!(rle.total_length_above(&0), 0);
+        assert_eq!(rle.longest_segment_above(&0), None);
+    }
+
+    #[test]
+    fn to_vec_into() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let rle = RleVec::from(&v[..]);
+        let mut buffer = Vec::with_capacity(100);
+        let ptr = buffer.as_ptr();
+        for _ in 0..3 {
+            rle.to_vec_into(&mut buffer);
+            assert_eq!(buffer, v);
+            assert_eq!(buffer.as_ptr(), ptr);
+        }
+
+        RleVec::<i32>::new().to_vec_into(&mut buffer);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), ptr);
+
+        rle.extend_vec(&mut buffer);
+        rle.extend_vec(&mut buffer);
+        assert_eq!(buffer.len(), 2 * v.len());
+        assert_eq!(&buffer[v.len()..], &v[..]);
+    }
+
+    #[test]
+    fn equal_range() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,5,9,99,99];
+        let rle = RleVec::from(&v[..]);
+        for x in -1..101 {
+            let start = v.iter().position(|&y| y >= x).unwrap_or(v.len());
+            let end = v.iter().position(|&y| y > x).unwrap_or(v.len());
+            assert_eq!(rle.equal_range(&x), start..end);
+        }
+        assert_eq!(rle.run_equal_range(&99), 5..6);
+        assert_eq!(rle.run_equal_range(&100), 6..6);
+        assert_eq!(RleVec::<i32>::new().equal_range(&1), 0..0);
+    }
+
+    #[test]
+    fn copy_to_slice() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let rle = RleVec::from(&v[..]);
+        for start in 0..=v.len() {
+            for end in start..=v.len() {
+                let mut out = vec![-1; end - start];
+                rle.copy_to_slice(start..end, &mut out);
+                assert_eq!(&out[..], &v[start..end]);
+            }
+        }
+        RleVec::<i32>::new().copy_to_slice(0..0, &mut []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_to_slice_out_of_bounds() {
+        let rle = RleVec::from(&[1, 1, 2][..]);
+        rle.copy_to_slice(1..4, &mut [0; 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_to_slice_wrong_length() {
+        let rle = RleVec::from(&[1, 1, 2][..]);
+        rle.copy_to_slice(0..2, &mut [0; 3]);
+    }
+
+    #[test]
+    fn linspace() {
+        let rle = RleVec::linspace(0.0, 1.0, 100, 0.01);
+        assert_eq!(rle.len(), 100);
+        assert!(rle.runs_len() <= 100);
+        assert_eq!(rle[0], 0.0);
+        assert_eq!(rle[99], 1.0);
+
+        let rle = RleVec::linspace(0.0, 1.0, 1000, 0.25);
+        assert_eq!(rle.runs_len(), 4);
+        let values: Vec<_> = rle.runs().map(|r| *r.value).collect();
+        for w in values.windows(2) {
+            assert!(w[1] - w[0] >= 0.25);
+        }
+
+        let rle = RleVec::linspace(2.0, 2.0, 10, 0.0);
+        assert_eq!(rle.runs_len(), 1);
+        let rle = RleVec::linspace(f64::INFINITY, f64::INFINITY, 3, 0.0);
+        assert_eq!(rle.to_vec(), vec![f64::INFINITY; 3]);
+        assert_eq!(RleVec::linspace(0.0, 1.0, 1, 0.1).to_vec(), vec![0.0]);
+        assert!(RleVec::linspace(0.0, 1.0, 0, 0.1).is_empty());
+    }
+
+    #[test]
+    fn swap_ranges() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        for n in 1..8 {
+            for a in 0..=v.len() - 2 * n {
+                for b in a + n..=v.len() - n {
+                    let mut expected = v.clone();
+                    let (left, right) = expected.split_at_mut(b);
+                    left[a..a + n].swap_with_slice(&mut right[..n]);
+
+                    let mut rle = RleVec::from(&v[..]);
+                    rle.swap_ranges(a..a + n, b..b + n);
+                    assert_eq!(rle.to_vec(), expected);
+                    assert_eq!(rle, RleVec::from(&expected[..]));
+
+                    let mut rle = RleVec::from(&v[..]);
+                    rle.swap_ranges(b..b + n, a..a + n);
+                    assert_eq!(rle, RleVec::from(&expected[..]));
+                }
+            }
+        }
+
+        let mut rle = RleVec::from(&v[..]);
+        rle.swap_ranges(3..3, 5..5);
+        assert_eq!(rle.to_vec(), v);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_overlap() {
+        let mut rle = RleVec::from(&[1, 2, 3, 4, 5][..]);
+        rle.swap_ranges(0..3, 2..5);
+    }
 
-impl<T: Eq> Extend<T> for RleVec<T> {
-    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=T> {
-        let mut iter = iter.into_iter();
-        if let Some(next_value) = iter.next() {
-            // In order te possibly longer use the last run for extending the run-end we do not use the
-            // push function to add values. This gives higher performance to extending the RleVec
-            // with data consisting of large runs.
-            let (pop, end) = if let Some(last_run) = self.runs.last() {
-                if last_run.value == next_value {
-                    (true, last_run.end + 1)
-                } else {
-                    (false, last_run.end + 1)
-                }
-            } else {
-                (false, 0)
-            };
+    #[test]
+    #[should_panic]
+    fn swap_ranges_different_length() {
+        let mut rle = RleVec::from(&[1, 2, 3, 4, 5][..]);
+        rle.swap_ranges(0..1, 2..4);
+    }
 
-            let mut rle_last = if pop {
-                let mut run = self.runs.pop().unwrap();
-                run.end = end;
-                run
-            } else {
-                InternalRun { value: next_value, end }
-            };
+    #[test]
+    fn resize_with() {
+        let mut rle = RleVec::from(&[1, 1, 2][..]);
+        rle.resize_with(10, || 5);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 5, 5, 5, 5, 5, 5, 5]);
+        assert_eq!(rle.runs_len(), 3);
 
-            for value in iter {
-                if value != rle_last.value {
-                    let next_end = rle_last.end;
-                    self.runs.push(rle_last);
-                    rle_last = InternalRun { value, end: next_end };
-                }
-                rle_last.end += 1;
+        rle.resize_with(10, || unreachable!());
+        assert_eq!(rle.len(), 10);
+
+        let mut counter = 0;
+        rle.resize_with(14, || { counter += 1; counter });
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 5, 5, 5, 5, 5, 5, 5, 1, 2, 3, 4]);
+        assert_eq!(rle.runs_len(), 7);
+
+        rle.resize_with(5, || 0);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 5, 5]);
+        rle.resize_with(3, || 0);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2]);
+        assert_eq!(rle.runs_len(), 2);
+        rle.resize_with(0, || 0);
+        assert!(rle.is_empty());
+        rle.assert_valid();
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = RleVec::linspace(0.0, 1.0, 20, 0.1);
+        let b = RleVec::linspace(0.0, 1.0, 20, 0.0);
+        assert!(a.approx_eq(&b, 0.1));
+        assert!(!a.approx_eq(&b, 0.01));
+        assert!(b.approx_eq(&a, 0.1));
+        assert!(a.approx_eq(&a, 0.0));
+        assert!(!a.approx_eq(&RleVec::linspace(0.0, 1.0, 21, 0.1), 1.0));
+
+        let nan = RleVec::linspace(f64::NAN, f64::NAN, 3, 0.0);
+        assert!(!nan.approx_eq(&nan, 1.0));
+        let inf = RleVec::linspace(f64::INFINITY, f64::INFINITY, 3, 0.0);
+        assert!(inf.approx_eq(&inf, 0.0));
+        assert!(inf.approx_eq_relative(&inf, 0.0));
+
+        assert!(b.approx_eq_relative(&b, 0.0));
+        assert!(!a.approx_eq_relative(&b, 0.01));
+        assert!(RleVec::new().approx_eq_relative(&RleVec::new(), 0.0));
+    }
+
+    #[test]
+    fn run_length_stats() {
+        let rle = RleVec::from(&[0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9][..]);
+        let lens: Vec<f64> = rle.runs().map(|r| r.len as f64).collect();
+        let mean = lens.iter().sum::<f64>() / lens.len() as f64;
+        let variance = lens.iter().map(|l| (l - mean) * (l - mean)).sum::<f64>() / lens.len() as f64;
+
+        let stats = rle.run_length_stats();
+        assert_eq!(stats.runs, 7);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.max, 7);
+        assert!((stats.mean - mean).abs() < 1e-12);
+        assert!((stats.variance - variance).abs() < 1e-12);
+        assert_eq!(&stats.histogram[..4], &[3, 3, 1, 0]);
+        assert_eq!(stats.histogram.iter().sum::<usize>(), 7);
+
+        let mut rle = RleVec::new();
+        rle.push_n(usize::MAX, 1u8);
+        let stats = rle.run_length_stats();
+        assert_eq!(stats.histogram[usize::BITS as usize - 1], 1);
+        assert_eq!(stats.variance, 0.0);
+
+        let stats = RleVec::<u8>::new().run_length_stats();
+        assert_eq!((stats.runs, stats.min, stats.max, stats.mean), (0, 0, 0, 0.0));
+    }
+
+    #[test]
+    fn sparse_operations() {
+        let a = vec![0,0,1,1,1,0,0,2,2,0,0,0,3];
+        let b = vec![5,0,0,4,4,4,0,0,2,2,0,0,3];
+        let (ra, rb) = (RleVec::from(&a[..]), RleVec::from(&b[..]));
+
+        let zip = |f: &dyn Fn(i32, i32) -> i32| -> Vec<i32> {
+            a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect()
+        };
+        let union = ra.sparse_union(&rb, &0);
+        assert_eq!(union.to_vec(), zip(&|x, y| if x != 0 { x } else { y }));
+        union.assert_valid();
+        let intersection = ra.sparse_intersection(&rb, &0);
+        assert_eq!(intersection.to_vec(), zip(&|x, y| if y != 0 { x } else { 0 }));
+        intersection.assert_valid();
+        let difference = ra.sparse_difference(&rb, &0);
+        assert_eq!(difference.to_vec(), zip(&|x, y| if y == 0 { x } else { 0 }));
+        difference.assert_valid();
+
+        let empty = RleVec::<i32>::new();
+        assert!(empty.sparse_union(&empty, &0).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn sparse_union_different_lengths() {
+        RleVec::from(&[1, 2][..]).sparse_union(&RleVec::from(&[1][..]), &0);
+    }
+
+    #[test]
+    fn encoded_size_estimate() {
+        let mut rle = RleVec::new();
+        rle.push_n(1, 0u8);
+        rle.push_n(127, 1u8);
+        rle.push_n(128, 0u8);
+        rle.push_n(1 << 14, 2u8);
+        let estimate = rle.encoded_size_estimate();
+        assert_eq!(estimate.dense_bytes, rle.len());
+        assert_eq!(estimate.rle_bytes, (1 + 1) + (1 + 1) + (2 + 1) + (3 + 1));
+        assert_eq!(estimate.distinct_values, 3);
+        assert!(estimate.entropy > 0.0 && estimate.entropy < 3f64.log2());
+
+        let estimate = RleVec::from(&[0u64, 1, 2, 3][..]).encoded_size_estimate();
+        assert_eq!(estimate.rle_bytes, 4 * 9);
+        assert_eq!(estimate.dense_bytes, 32);
+        assert_eq!(estimate.entropy, 2.0);
+
+        let estimate = RleVec::<u16>::new().encoded_size_estimate();
+        assert_eq!((estimate.dense_bytes, estimate.rle_bytes, estimate.distinct_values), (0, 0, 0));
+        assert_eq!(estimate.entropy, 0.0);
+
+        let mut huge = RleVec::new();
+        huge.push_n(usize::MAX / 2, 7u64);
+        assert_eq!(huge.encoded_size_estimate().dense_bytes, usize::MAX);
+
+        // the float sum must not depend on the order of a freshly seeded HashMap
+        let rle: RleVec<u32> = (0..5000u32).map(|i| (i * i) % 97 + i / 1000).collect();
+        let entropy = rle.encoded_size_estimate().entropy;
+        for _ in 0..50 {
+            assert_eq!(rle.encoded_size_estimate().entropy.to_bits(), entropy.to_bits());
+        }
+    }
+
+    #[test]
+    fn flatten() {
+        let parts = vec![
+            RleVec::from(&[1, 1, 2][..]),
+            RleVec::from(&[2, 2][..]),
+            RleVec::new(),
+            RleVec::from(&[2, 3][..]),
+            RleVec::from(&[3][..]),
+        ];
+        let expected: Vec<_> = parts.iter().flat_map(|p| p.to_vec()).collect();
+
+        let flat = flatten_iter(parts.clone());
+        assert_eq!(flat.to_vec(), expected);
+        assert_eq!(flat.runs_len(), 3);
+        flat.assert_valid();
+
+        let nested: RleVec<_> = parts.into_iter().collect();
+        assert_eq!(nested.clone().flatten(), flat);
+
+        let mut nested = RleVec::new();
+        nested.push_n(3, RleVec::from(&[4, 4][..]));
+        nested.push_n(2, RleVec::from(&[4, 5][..]));
+        let flat = nested.flatten();
+        assert_eq!(flat.to_vec(), vec![4, 4, 4, 4, 4, 4, 4, 5, 4, 5]);
+        flat.assert_valid();
+
+        assert!(RleVec::<RleVec<i32>>::new().flatten().is_empty());
+    }
+
+    #[test]
+    fn cross_type_eq() {
+        let owned: RleVec<String> = ["a", "a", "b", "c", "c"].iter().map(|s| s.to_string()).collect();
+        let borrowed = RleVec::from(&["a", "a", "b", "c", "c"][..]);
+        assert!(owned == borrowed);
+        assert!(owned == vec!["a", "a", "b", "c", "c"]);
+        assert!(owned == ["a", "a", "b", "c", "c"][..]);
+        let slice: &[&str] = &["a", "a", "b", "c", "c"];
+        assert!(owned == slice);
+
+        let shorter = RleVec::from(&["a", "a", "b", "c"][..]);
+        assert!(owned != shorter);
+        let shifted = RleVec::from(&["a", "b", "b", "c", "c"][..]);
+        assert!(owned != shifted);
+        assert!(owned != vec!["a", "a", "b", "c"]);
+        assert!(owned != vec!["a", "a", "b", "c", "d"]);
+
+        let rle = RleVec::from(&[1, 1, 2][..]);
+        assert_eq!(rle, vec![1, 1, 2]);
+        assert_eq!(RleVec::<i32>::new(), Vec::<i32>::new());
+        assert_eq!(RleVec::<i32>::new(), RleVec::<i32>::new());
+    }
+
+    #[test]
+    fn adjacent_pairs() {
+        let rle: RleVec<i32> = RleVec::new();
+        assert_eq!(rle.iter_adjacent_pairs().count(), 0);
+        let rle = RleVec::from(&[4, 4, 4][..]);
+        assert_eq!(rle.iter_adjacent_pairs().count(), 0);
+        assert_eq!(rle.count_adjacent_pairs_matching(|_, _| true), 0);
+
+        let rle = RleVec::from(&[0, 1, 1, 0, 2, 2, 2, 1][..]);
+        let pairs: Vec<_> = rle.iter_adjacent_pairs().collect();
+        assert_eq!(pairs, vec![(&0, &1), (&1, &0), (&0, &2), (&2, &1)]);
+
+        let mut transitions = HashMap::new();
+        rle.for_each_adjacent_pair(|&a, &b| *transitions.entry((a, b)).or_insert(0) += 1);
+        assert_eq!(transitions.len(), 4);
+        assert_eq!(rle.count_adjacent_pairs_matching(|a, b| a > b), 2);
+        assert_eq!(rle.count_adjacent_pairs_matching(|&a, _| a == 0), 2);
+    }
+
+    #[test]
+    fn dictionary() {
+        let values = ["on", "off", "off", "on", "idle", "idle", "on"];
+        let rle = RleVec::from(&values[..]);
+        let (codes, dict) = rle.to_dictionary();
+        assert_eq!(dict, vec!["on", "off", "idle"]);
+        assert_eq!(codes.to_vec(), vec![0, 1, 1, 0, 2, 2, 0]);
+        assert_eq!(codes.runs_len(), rle.runs_len());
+        assert_eq!(RleVec::from_dictionary(&codes, &dict), Ok(rle.clone()));
+
+        // codes only depend on the order of first appearance
+        let (again, _) = rle.clone().to_dictionary();
+        assert_eq!(again, codes);
+
+        let empty: RleVec<String> = RleVec::new();
+        let (codes, dict) = empty.to_dictionary();
+        assert!(codes.is_empty() && dict.is_empty());
+        assert_eq!(RleVec::from_dictionary(&codes, &dict), Ok(empty));
+
+        // duplicate dictionary entries merge the runs
+        let codes = RleVec::from(&[0u32, 1, 1, 2][..]);
+        let rle = RleVec::from_dictionary(&codes, &[5, 5, 7]).unwrap();
+        assert_eq!(rle.to_vec(), vec![5, 5, 5, 7]);
+        assert_eq!(rle.runs_len(), 2);
+
+        let err = RleVec::from_dictionary(&codes, &[5, 5]).unwrap_err();
+        assert_eq!(err, MissingCode { code: 2, dictionary_len: 2 });
+        assert_eq!(err.to_string(), "code 2 not found in dictionary of length 2");
+    }
+
+    #[test]
+    fn with_value_map() {
+        let rle = RleVec::from(&[1, 1, 2, 3, 3, 4][..]);
+        let mapped = rle.clone().with_value_map(|v| v * 10);
+        assert_eq!(mapped.to_vec(), vec![10, 10, 20, 30, 30, 40]);
+        assert_eq!(mapped.runs_len(), rle.runs_len());
+
+        let merged = rle.with_value_map(|v| v / 3);
+        assert_eq!(merged.to_vec(), vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(merged.runs_len(), 2);
+        merged.assert_valid();
+
+        let strings = RleVec::from(&["a", "a", "b"][..]).with_value_map(String::from);
+        let lens = strings.with_value_map(|s| s.len());
+        assert_eq!(lens.runs_len(), 1);
+        assert_eq!(lens.len(), 3);
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert!(empty.with_value_map(|v| v + 1).is_empty());
+    }
+
+    #[test]
+    fn replace() {
+        let mut rle = RleVec::from(&[1, 1, 1, 2, 3, 3][..]);
+        // no-op
+        assert_eq!(rle.replace(1, 1), 1);
+        assert_eq!(rle.runs_len(), 3);
+        // split
+        assert_eq!(rle.replace(1, 5), 1);
+        assert_eq!(rle.to_vec(), vec![1, 5, 1, 2, 3, 3]);
+        // size-1 run merging with both neighbours
+        assert_eq!(rle.replace(1, 1), 5);
+        assert_eq!(rle.runs_len(), 3);
+        // size-1 run merging with the previous run
+        assert_eq!(rle.replace(3, 1), 2);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 3, 3]);
+        // start of a run joining the previous run
+        assert_eq!(rle.replace(4, 1), 3);
+        // size-1 run replaced in place
+        assert_eq!(rle.replace(5, 4), 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 4]);
+        // start of the first run, then a size-1 run merging with the next run
+        assert_eq!(rle.replace(0, 9), 1);
+        assert_eq!(rle.replace(0, 1), 9);
+        // end of a run
+        assert_eq!(rle.replace(4, 4), 1);
+        assert_eq!(rle.replace(4, 1), 4);
+        assert_eq!(rle.replace(5, 7), 4);
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 7]);
+        assert_eq!(rle.runs_len(), 2);
+        rle.assert_valid();
+
+        let mut strings = RleVec::from(&["a".to_string(), "b".to_string(), "b".to_string()][..]);
+        assert_eq!(strings.replace(2, "a".to_string()), "b");
+        assert_eq!(strings.replace(0, "b".to_string()), "a");
+        assert_eq!(strings.to_vec(), vec!["b", "b", "a"]);
+    }
+
+    #[test]
+    fn drain_runs() {
+        let mut rle = RleVec::from(&[1, 2, 2, 3, 3, 3, 2, 4][..]);
+        assert_eq!(rle.drain_runs(2..2).count(), 0);
+        assert_eq!(rle.runs_len(), 5);
+
+        // draining a middle run merges its neighbours
+        let drained: Vec<_> = rle.drain_runs(2..3).collect();
+        assert_eq!(drained, vec![Run { len: 3, value: 3 }]);
+        assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 4]);
+        assert_eq!(rle.runs_len(), 3);
+        rle.assert_valid();
+
+        let drained: Vec<_> = rle.drain_runs(0..1).collect();
+        assert_eq!(drained, vec![Run { len: 1, value: 1 }]);
+        assert_eq!(rle.to_vec(), vec![2, 2, 2, 4]);
+
+        let drained: Vec<_> = rle.drain_runs(1..2).collect();
+        assert_eq!(drained, vec![Run { len: 1, value: 4 }]);
+        assert_eq!(rle.to_vec(), vec![2, 2, 2]);
+
+        // the runs are removed even if the iterator is not consumed
+        rle.drain_runs(0..1);
+        assert!(rle.is_empty());
+        rle.assert_valid();
+    }
+
+    #[test]
+    fn drain_runs_lazy() {
+        let values = [1, 2, 2, 3, 3, 3, 4, 2, 5];
+        let mut rle = RleVec::from(&values[..]);
+        {
+            let mut drained = rle.drain_runs(2..4);
+            assert_eq!(drained.len(), 2);
+            assert_eq!(drained.next_back(), Some(Run { len: 1, value: 4 }));
+            assert_eq!(drained.len(), 1);
+        }
+        assert_eq!(rle.to_vec(), vec![1, 2, 2, 2, 5]);
+        assert_eq!(rle.runs_len(), 3);
+        rle.assert_valid();
+
+        let mut rle = RleVec::from(&values[..]);
+        let drained: Vec<_> = rle.drain_runs(1..4).rev().collect();
+        assert_eq!(drained, vec![Run { len: 1, value: 4 }, Run { len: 3, value: 3 }, Run { len: 2, value: 2 }]);
+        assert_eq!(rle.to_vec(), vec![1, 2, 5]);
+
+        // leaking the iterator leaks the drained runs, the rest stays valid
+        let mut rle = RleVec::from(&values[..]);
+        std::mem::forget(rle.drain_runs(0..5));
+        assert_eq!(rle.to_vec(), vec![5]);
+        rle.assert_valid();
+
+        let mut rle = RleVec::from(&values[..]);
+        assert_eq!(rle.drain_runs(0..6).map(|run| run.len).sum::<usize>(), values.len());
+        assert!(rle.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn drain_runs_out_of_bounds() {
+        let mut rle = RleVec::from(&[1, 2][..]);
+        rle.drain_runs(1..3);
+    }
+
+    #[test]
+    fn clone_from() {
+        let baseline = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        let mut working = RleVec::from(&[4, 5, 6, 7, 8, 9, 10][..]);
+        let capacity = working.runs.capacity();
+        working.clone_from(&baseline);
+        assert_eq!(working, baseline.clone());
+        assert_eq!(working.runs_len(), 3);
+        assert_eq!(working.runs.capacity(), capacity);
+
+        let mut larger = RleVec::new();
+        larger.clone_from(&baseline);
+        assert_eq!(larger, baseline);
+        larger.clone_from(&RleVec::new());
+        assert!(larger.is_empty());
+    }
+
+    #[test]
+    fn access_index() {
+        let rle: RleVec<_> = (0..1000).map(|i| i / 7).collect();
+        for &k in &[1, 3, 7, 64, 1000, 5000] {
+            let indexed = rle.build_access_index(k);
+            assert_eq!(indexed.sample_interval(), k);
+            for i in 0..1000 {
+                assert_eq!(indexed[i], i / 7);
             }
-            self.runs.push(rle_last);
+            assert_eq!(indexed.get(1000), None);
+            assert!(indexed.iter_range(0..1000).eq(rle.iter()));
+            assert!(indexed.iter_range(13..500).rev().eq(rle.iter().skip(13).take(487).rev()));
+            assert_eq!(indexed.iter_range(20..20).count(), 0);
         }
+        assert!(rle.build_access_index(1).memory_overhead() > rle.build_access_index(64).memory_overhead());
+
+        let empty: RleVec<i32> = RleVec::new();
+        let indexed = empty.build_access_index(16);
+        assert_eq!(indexed.get(0), None);
+        assert_eq!(indexed.iter_range(0..0).count(), 0);
+        assert_eq!(indexed.memory_overhead(), 0);
+
+        // the sampled positions must not overflow near usize::MAX
+        let mut units = RleVec::new();
+        units.push_n(usize::MAX - 1, ());
+        let indexed = units.build_access_index(usize::MAX / 2 + 1);
+        assert_eq!(indexed.get(usize::MAX - 2), Some(&()));
+        assert_eq!(indexed.get(usize::MAX - 1), None);
     }
-}
 
-impl<T: Eq> Extend<Run<T>> for RleVec<T> {
-    fn extend<I>(&mut self, iter: I) where I: IntoIterator<Item=Run<T>> {
-        for Run{ len, value } in iter {
-            self.push_n(len, value)
+    #[test]
+    #[should_panic]
+    fn access_index_out_of_bounds() {
+        let rle = RleVec::from(&[1, 2, 3][..]);
+        let indexed = rle.build_access_index(2);
+        let _ = indexed[3];
+    }
+
+    #[test]
+    fn try_map() {
+        let rle = RleVec::from(&[1, 1, 2, 300, 300, 4][..]);
+        let mut calls = 0;
+        let result = rle.try_map(|&v| { calls += 1; u8::try_from(v) });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        let halved: Result<RleVec<i32>, ()> = rle.try_map(|&v| Ok(v / 2));
+        let halved = halved.unwrap();
+        assert_eq!(halved.to_vec(), vec![0, 0, 1, 150, 150, 2]);
+        assert_eq!(halved.runs_len(), 4);
+        halved.assert_valid();
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.try_map(|_| Err::<i32, _>("never called")), Ok(RleVec::new()));
+    }
+
+    #[test]
+    fn leading_trailing_count() {
+        let rle = RleVec::from(&[7, 7, 1, 7][..]);
+        assert_eq!(rle.leading_count(&7), 2);
+        assert_eq!(rle.trailing_count(&7), 1);
+        assert_eq!(rle.leading_count(&1), 0);
+        assert_eq!(rle.trailing_count(&1), 0);
+
+        let rle = RleVec::from(&[3, 3, 3][..]);
+        assert_eq!(rle.leading_count(&3), 3);
+        assert_eq!(rle.trailing_count(&3), 3);
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.leading_count(&0), 0);
+        assert_eq!(empty.trailing_count(&0), 0);
+    }
+
+    #[test]
+    fn strip_prefix_suffix() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 3, 3][..]);
+        assert_eq!(rle.strip_prefix(&[]), Some(rle.clone()));
+        assert_eq!(rle.strip_suffix(&[]), Some(rle.clone()));
+
+        // ending mid-run
+        let rest = rle.strip_prefix(&[1, 1]).unwrap();
+        assert_eq!(rest.to_vec(), vec![1, 2, 3, 3]);
+        assert_eq!(rest.runs_len(), 3);
+        let rest = rle.strip_suffix(&[3]).unwrap();
+        assert_eq!(rest.to_vec(), vec![1, 1, 1, 2, 3]);
+        rest.assert_valid();
+
+        // the whole vector
+        assert_eq!(rle.strip_prefix(&[1, 1, 1, 2, 3, 3]), Some(RleVec::new()));
+        assert_eq!(rle.strip_suffix(&[1, 1, 1, 2, 3, 3]), Some(RleVec::new()));
+
+        // longer than the vector
+        assert_eq!(rle.strip_prefix(&[1, 1, 1, 2, 3, 3, 3]), None);
+        assert_eq!(rle.strip_suffix(&[0, 1, 1, 1, 2, 3, 3]), None);
+
+        assert_eq!(rle.strip_prefix(&[1, 1, 1, 1]), None);
+        assert_eq!(rle.strip_suffix(&[2, 2, 3, 3]), None);
+    }
+
+    #[test]
+    fn merge_many() {
+        let a = RleVec::from(&[1, 1, 1, 2, 2, 2][..]);
+        let b = RleVec::from(&[0, 0, 3, 3, 3, 0][..]);
+        let c = RleVec::from(&[4, 4, 4, 4, 4, 4][..]);
+        let mut calls = 0;
+        let sum = RleVec::merge_many(&[&a, &b, &c], |values| {
+            calls += 1;
+            values.iter().cloned().sum::<i32>()
+        });
+        assert_eq!(sum.to_vec(), vec![5, 5, 8, 9, 9, 6]);
+        assert_eq!(calls, 4);
+        sum.assert_valid();
+
+        let max = RleVec::merge_many(&[&a, &b], |values| **values.iter().max().unwrap());
+        assert_eq!(max.to_vec(), vec![1, 1, 3, 3, 3, 2]);
+        assert_eq!(max.runs_len(), 3);
+
+        let single = RleVec::merge_many(&[&b], |values| *values[0]);
+        assert_eq!(single, b);
+
+        let none: RleVec<i32> = RleVec::merge_many(&[] as &[&RleVec<i32>], |_| 0);
+        assert!(none.is_empty());
+        let empty = RleVec::<i32>::new();
+        assert!(RleVec::merge_many(&[&empty, &empty], |_| 0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "equal length")]
+    fn merge_many_length_mismatch() {
+        let a = RleVec::from(&[1, 1][..]);
+        let b = RleVec::from(&[1][..]);
+        RleVec::merge_many(&[&a, &b], |_| 0);
+    }
+
+    #[test]
+    fn runs_split_at() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+        let lens = |b: &[usize]| rle.runs_split_at(b).unwrap().map(|r| r.len).collect::<Vec<_>>();
+        assert_eq!(lens(&[]), vec![3, 2, 1]);
+        assert_eq!(lens(&[0, 6]), vec![3, 2, 1]);
+        assert_eq!(lens(&[3]), vec![3, 2, 1]);
+        assert_eq!(lens(&[1, 1, 4, 5]), vec![1, 2, 1, 1, 1]);
+        assert_eq!(lens(&[1, 2, 3, 4, 5]), vec![1; 6]);
+
+        let values: Vec<_> = rle.runs_split_at(&[1, 4]).unwrap()
+            .flat_map(|r| std::iter::repeat(*r.value).take(r.len)).collect();
+        assert_eq!(values, rle.to_vec());
+
+        assert_eq!(rle.runs_split_at(&[7]).err(), Some(BoundaryError::OutOfBounds { boundary: 7, len: 6 }));
+        assert_eq!(rle.runs_split_at(&[1, 4, 3]).err(), Some(BoundaryError::Unsorted { index: 2 }));
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.runs_split_at(&[0, 0]).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn range_aggregates() {
+        let mut state = 12345u32;
+        let mut random = move |n: u32| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) % n
+        };
+        let values: Vec<u8> = (0..500).map(|_| random(4) as u8 * 60).collect();
+        let rle = RleVec::from(&values[..]);
+        for _ in 0..200 {
+            let a = random(501) as usize;
+            let b = random(501) as usize;
+            let range = cmp::min(a, b)..cmp::max(a, b);
+            let window = &values[range.clone()];
+            assert_eq!(rle.range_sum::<u64>(range.clone()), window.iter().map(|&v| v as u64).sum::<u64>());
+            assert_eq!(rle.range_min(range.clone()), window.iter().min());
+            assert_eq!(rle.range_max(range.clone()), window.iter().max());
         }
+
+        let floats = RleVec { runs: vec![InternalRun { end: 1, value: 2.5 }, InternalRun { end: 2, value: -1.0 }] };
+        assert_eq!(floats.range_min(0..3), Some(&-1.0));
+        assert_eq!(floats.range_max(0..3), Some(&2.5));
     }
-}
 
-impl io::Write for RleVec<u8> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.extend(buf.iter().cloned());
-        Ok(buf.len())
+    #[test]
+    fn add_range() {
+        let intervals = [(10, 40), (20, 30), (0, 5), (35, 60), (20, 30), (59, 60), (0, 60), (5, 10)];
+        let mut expected = vec![0u32; 60];
+        let mut coverage = RleVec::new();
+        coverage.push_n(60, 0u32);
+        for &(a, b) in &intervals {
+            coverage.add_range(a..b, 1);
+            for v in &mut expected[a..b] {
+                *v += 1;
+            }
+            assert_eq!(coverage.to_vec(), expected);
+            coverage.assert_valid();
+        }
+        coverage.add_range(7..7, 100);
+        assert_eq!(coverage.to_vec(), expected);
+
+        let mut rle = RleVec::from(&[250u8, 250, 10, 3][..]);
+        rle.saturating_add_range(0..3, 10);
+        assert_eq!(rle.to_vec(), vec![255, 255, 20, 3]);
+        assert_eq!(rle.checked_add_range(1..4, 1), Err(AddOverflow { index: 1 }));
+        assert_eq!(rle.to_vec(), vec![255, 255, 20, 3]);
+        assert_eq!(rle.checked_add_range(2..4, 1), Ok(()));
+        assert_eq!(rle.to_vec(), vec![255, 255, 21, 4]);
+
+        let mut signed = RleVec::from(&[-1i64, -1, 1][..]);
+        signed.add_range(2..3, -2);
+        assert_eq!(signed.runs_len(), 1);
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.extend(buf.iter().cloned());
-        Ok( () )
+    #[test]
+    fn strings() {
+        for &text in &["", "F", "FFFFFFF:::,,FF", "ääääxx😀😀y", "IIIIIIIIIIIIIIIIIIIIIIIIIII#"] {
+            let rle = RleVec::from_str_chars(text);
+            assert_eq!(rle.len(), text.chars().count());
+            assert_eq!(rle.collect_string(), text);
+            assert_eq!(rle.to_string(), text);
+            assert_eq!(format!("[{}]", rle), format!("[{}]", text));
+
+            let bytes = RleVec::from_bytes(text.as_bytes());
+            assert_eq!(bytes.to_vec(), text.as_bytes());
+        }
+        assert_eq!(RleVec::from_str_chars("aaabba").runs_len(), 3);
     }
 
-    fn flush(&mut self) -> io::Result<()> { Ok( () ) }
-}
+    #[test]
+    fn iter_current_run() {
+        let rle = RleVec::from(&[1, 1, 1, 2, 3, 3][..]);
+        let mut iter = rle.iter();
+        // fresh
+        assert_eq!(iter.current_value(), Some(&1));
+        assert_eq!(iter.current_run_remaining(), 3);
+        // mid-run
+        iter.next();
+        assert_eq!(iter.current_value(), Some(&1));
+        assert_eq!(iter.current_run_remaining(), 2);
+        // boundary
+        iter.next();
+        iter.next();
+        assert_eq!(iter.current_value(), Some(&2));
+        assert_eq!(iter.current_run_remaining(), 1);
+        // elements consumed from the back are not counted
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.current_value(), Some(&3));
+        assert_eq!(iter.current_run_remaining(), 1);
+        // exhausted
+        iter.next();
+        assert_eq!(iter.current_value(), None);
+        assert_eq!(iter.current_run_remaining(), 0);
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.iter().current_value(), None);
+        assert_eq!(empty.iter().current_run_remaining(), 0);
+    }
 
-/// Immutable `RelVec` iterator over references of values.
-///
-/// Can be obtained from the [`iter`](struct.RleVec.html#method.iter) or the `into_iter` methods.
-///
-/// # Example
-/// ```
-/// # use rle_vec::RleVec;
-/// let rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
-///
-/// let mut iterator = rle.iter();
-/// assert_eq!(iterator.next(), Some(&1));
-/// assert_eq!(iterator.next(), Some(&1));
-/// assert_eq!(iterator.next(), Some(&1));
-/// assert_eq!(iterator.next(), Some(&1));
-/// assert_eq!(iterator.next(), Some(&2));
-/// assert_eq!(iterator.next(), Some(&2));
-/// assert_eq!(iterator.next(), Some(&3));
-/// assert_eq!(iterator.next(), None);
-/// ```
-pub struct Iter<'a, T: 'a> {
-    rle: &'a RleVec<T>,
-    run_index: usize,
-    index: usize,
-    index_back: usize,
-    run_index_back: usize,
-}
+    #[test]
+    fn try_from_iter() {
+        assert_eq!(RleVec::try_from_iter(Vec::<i32>::new(), 0), Ok(RleVec::new()));
+        assert_eq!(RleVec::try_from_iter(vec![1], 0), Err(TooManyRuns { max_runs: 0, len: 0 }));
+        assert_eq!(RleVec::try_from_iter(vec![1, 1, 1], 1).unwrap().len(), 3);
+
+        // adversarial alternating input stops at the limit
+        let mut consumed = 0;
+        let alternating = (0..).map(|i| { consumed += 1; i % 2 });
+        let err = RleVec::try_from_iter(alternating, 100).unwrap_err();
+        assert_eq!(err, TooManyRuns { max_runs: 100, len: 100 });
+        assert_eq!(consumed, 101);
+        assert_eq!(err.to_string(), "more than 100 runs after 100 elements");
+
+        let mut rle = RleVec::from(&[1, 2][..]);
+        assert!(rle.push_bounded(2, 2).is_ok());
+        assert!(rle.push_bounded(3, 2).is_err());
+        assert!(rle.push_bounded(3, 3).is_ok());
+        assert_eq!(rle.to_vec(), vec![1, 2, 2, 3]);
+    }
 
-impl<'a, T: 'a> IntoIterator for &'a RleVec<T> {
-    type Item = &'a T;
-    type IntoIter = Iter<'a, T>;
+    #[test]
+    fn smooth_min_run() {
+        let alternating: RleVec<_> = (0..20).map(|i| i % 2).collect();
+        let smooth = alternating.smooth_min_run(2);
+        assert_eq!(smooth.runs_len(), 1);
+        assert_eq!(smooth.len(), 20);
+
+        // runs of exactly min_len are preserved
+        let rle = RleVec::from(&[1, 1, 1, 2, 2, 3, 3, 3][..]);
+        assert_eq!(rle.smooth_min_run(2), rle);
+        assert_eq!(rle.smooth_min_run(0), rle);
+
+        // ties go to the previous run, edges to their only neighbour
+        let rle = RleVec::from(&[1, 2, 2, 3, 4, 4, 5][..]);
+        assert_eq!(rle.smooth_min_run(2).to_vec(), vec![2, 2, 2, 2, 4, 4, 4]);
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        assert_eq!(rle.smooth_min_run(2).to_vec(), vec![1, 1, 1, 3, 3]);
+
+        // absorbing can make the neighbours merge
+        let rle = RleVec::from(&[7, 7, 7, 8, 7, 7, 7][..]);
+        let smooth = rle.smooth_min_run(3);
+        assert_eq!(smooth.to_vec(), vec![7; 7]);
+        assert_eq!(smooth.runs_len(), 1);
+
+        let short = RleVec::from(&[1, 2][..]);
+        assert_eq!(short.smooth_min_run(5).to_vec(), vec![2, 2]);
+        assert!(RleVec::<i32>::new().smooth_min_run(3).is_empty());
+
+        for min_len in 1..6 {
+            let values: Vec<_> = (0..200).map(|i| (i * i / 7) % 5).collect();
+            let smooth = RleVec::from(&values[..]).smooth_min_run(min_len);
+            assert_eq!(smooth.len(), 200);
+            assert!(smooth.runs().all(|run| run.len >= min_len));
+            smooth.assert_valid();
+        }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            rle: self,
-            run_index: 0,
-            index: 0,
-            run_index_back: self.runs.len().saturating_sub(1),
-            index_back: self.len(), // starts out of range
+    #[test]
+    fn drain_front_prepend() {
+        let mut rle = RleVec::from(&[1, 1, 2, 2, 2, 3][..]);
+        rle.drain_front(0);
+        assert_eq!(rle.len(), 6);
+        rle.drain_front(2);
+        assert_eq!(rle.to_vec(), vec![2, 2, 2, 3]);
+        rle.drain_front(1);
+        assert_eq!(rle.to_vec(), vec![2, 2, 3]);
+        rle.assert_valid();
+
+        rle.prepend_rle(&RleVec::new());
+        assert_eq!(rle.to_vec(), vec![2, 2, 3]);
+        rle.prepend_rle(&RleVec::from(&[5, 2][..]));
+        assert_eq!(rle.to_vec(), vec![5, 2, 2, 2, 3]);
+        assert_eq!(rle.runs_len(), 3);
+        rle.prepend_rle(&RleVec::from(&[4][..]));
+        assert_eq!(rle.to_vec(), vec![4, 5, 2, 2, 2, 3]);
+        rle.assert_valid();
+
+        rle.drain_front(6);
+        assert!(rle.is_empty());
+        rle.prepend_rle(&RleVec::from(&[7, 7][..]));
+        assert_eq!(rle.to_vec(), vec![7, 7]);
+
+        // sliding window over a stream
+        let mut window = RleVec::new();
+        let mut expected = Vec::new();
+        for block in 0..20 {
+            let chunk: Vec<_> = (0..7).map(|i| (block * 7 + i) / 5 % 3).collect();
+            window.prepend_rle(&RleVec::from(&chunk[..]));
+            expected.splice(0..0, chunk);
+            if window.len() > 20 {
+                let n = window.len() - 20;
+                window.drain_front(n);
+                expected.drain(..n);
+            }
+            assert_eq!(window.to_vec(), expected);
+            window.assert_valid();
         }
     }
-}
 
-impl<'a, T: 'a> Iterator for Iter<'a, T> {
-    type Item = &'a T;
+    #[test]
+    #[should_panic]
+    fn drain_front_out_of_bounds() {
+        let mut rle = RleVec::from(&[1, 2][..]);
+        rle.drain_front(3);
+    }
+
+    #[test]
+    fn try_to_vec() {
+        let rle = RleVec::from(&[1u64, 1, 2][..]);
+        assert_eq!(rle.try_to_vec(3), Ok(vec![1, 1, 2]));
+        assert_eq!(rle.try_to_vec(2), Err(ToVecError::TooLong { len: 3, max_len: 2 }));
+        assert_eq!(RleVec::<u64>::new().try_to_vec(0), Ok(vec![]));
+
+        let mut huge = RleVec::new();
+        huge.push_n(usize::MAX / 2, 0u64);
+        huge.push_n(5, 1u64);
+        assert_eq!(huge.runs_len(), 2);
+        assert_eq!(huge.try_to_vec(1 << 20), Err(ToVecError::TooLong { len: usize::MAX / 2 + 5, max_len: 1 << 20 }));
+        match huge.try_to_vec(usize::MAX) {
+            Err(ToVecError::Alloc(_)) => {}
+            other => panic!("expected an allocation error, got {:?}", other.map(|v| v.len())),
+        }
+    }
+
+    #[test]
+    fn runs_cloned() {
+        let rle = RleVec::from(&[1, 1, 2, 3, 3, 3][..]);
+        let owned: Vec<_> = rle.runs_cloned().collect();
+        let borrowed: Vec<_> = rle.runs().map(|r| Run { len: r.len, value: *r.value }).collect();
+        assert_eq!(owned, borrowed);
+        assert_eq!(rle.runs_cloned().rev().map(|r| r.value).collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(rle.runs_cloned().len(), 3);
+        assert_eq!(RleVec::<i32>::new().runs_cloned().next(), None);
+    }
+
+    #[test]
+    fn insert_into_empty() {
+        let mut rle = RleVec::new();
+        rle.insert(0, 4);
+        assert_eq!(rle.to_vec(), vec![4]);
+        rle.insert(1, 4);
+        rle.insert(0, 3);
+        assert_eq!(rle.to_vec(), vec![3, 4, 4]);
+        rle.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
+    fn insert_into_empty_out_of_bounds() {
+        let mut rle = RleVec::new();
+        rle.insert(1, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 2)")]
+    fn insert_out_of_bounds() {
+        let mut rle = RleVec::from(&[1, 2][..]);
+        rle.insert(4, 4);
+    }
+
+    #[test]
+    fn run_structure() {
+        let a = RleVec::from(&[1, 1, 2, 2, 2][..]);
+        let b = RleVec::from(&[5, 5, 1, 1, 1][..]);
+        assert!(a.same_run_structure(&b));
+        assert!(!a.identical_runs(&b));
+        assert!(a.identical_runs(&a.clone()));
+        assert!(!a.same_run_structure(&RleVec::from(&[1, 1, 2, 2][..])));
+        assert!(!a.same_run_structure(&RleVec::from(&[1, 2, 2, 2, 2][..])));
+        assert!(RleVec::<i32>::new().same_run_structure(&RleVec::<u8>::new()));
+    }
+
+    #[test]
+    fn extend_from_slice() {
+        let data: Vec<_> = (0..10_000).map(|i| i / 3 % 4).collect();
+        assert_eq!(RleVec::count_runs_in_slice(&data), RleVec::from(&data[..]).runs_len());
+        assert_eq!(RleVec::<i32>::count_runs_in_slice(&[]), 0);
+
+        let mut rle = RleVec::from(&[0, 0][..]);
+        rle.extend_from_slice(&data);
+        assert_eq!(rle.runs.capacity(), rle.runs_len());
+        assert_eq!(rle.len(), 10_002);
+        assert_eq!(rle.to_vec()[2..], data[..]);
+        rle.assert_valid();
+
+        rle.extend_from_slice(&[]);
+        rle.extend_from_slice(&[3, 5]);
+        assert_eq!(rle.len(), 10_004);
+        rle.assert_valid();
+    }
+
+    #[test]
+    fn change_sequence() {
+        let rle = RleVec::from(&[3, 3, 1, 4, 4, 4, 1][..]);
+        assert_eq!(rle.to_change_sequence(), vec![3, 1, 4, 1]);
+        assert_eq!(rle.change_sequence().rev().collect::<Vec<_>>(), vec![&1, &4, &1, &3]);
+        assert_eq!(rle.change_sequence().len(), 4);
+
+        let rebuilt = RleVec::from_changes_with_lengths(vec![3, 1, 4, 1], vec![2, 1, 3, 1]);
+        assert_eq!(rebuilt, rle);
+        let merged = RleVec::from_changes_with_lengths(vec![1, 1, 2, 1], vec![2, 3, 0, 1]);
+        assert_eq!(merged.to_vec(), vec![1, 1, 1, 1, 1, 1]);
+        assert_eq!(merged.runs_len(), 1);
+        assert!(RleVec::<i32>::from_changes_with_lengths(vec![], vec![]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "different lengths")]
+    fn from_changes_with_lengths_mismatch() {
+        RleVec::from_changes_with_lengths(vec![1, 2], vec![1]);
+    }
+
+    #[test]
+    fn min_max_by() {
+        let rle = RleVec::from(&[(2, 'a'), (2, 'a'), (5, 'b'), (1, 'c'), (5, 'd'), (1, 'e')][..]);
+        let mut calls = 0;
+        let max = rle.max_by_key(|v| { calls += 1; v.0 });
+        assert_eq!(max, Some(&(5, 'b')));
+        assert_eq!(calls, 5);
+        assert_eq!(rle.min_by_key(|v| v.0), Some(&(1, 'c')));
+        assert_eq!(rle.max_by(|a, b| a.0.cmp(&b.0)), Some(&(5, 'b')));
+        assert_eq!(rle.min_by(|a, b| a.0.cmp(&b.0)), Some(&(1, 'c')));
+
+        assert_eq!(rle.max_by_key_with_index(|v| v.0), Some((2, &(5, 'b'))));
+        assert_eq!(rle.min_by_key_with_index(|v| v.0), Some((3, &(1, 'c'))));
+        assert_eq!(rle.max_by_with_index(|a, b| a.1.cmp(&b.1)), Some((5, &(1, 'e'))));
+        assert_eq!(rle.min_by_with_index(|a, b| a.1.cmp(&b.1)), Some((0, &(2, 'a'))));
+
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.max_by_key(|&v| v), None);
+        assert_eq!(empty.min_by_with_index(|a, b| a.cmp(b)), None);
+    }
+
+    #[test]
+    fn retain_map_runs() {
+        let values = [1, 1, 2, 3, 3, 4, 4, 4, 5];
+        let mut rle = RleVec::from(&values[..]);
+        rle.retain_map_runs(|_, _| true);
+        assert_eq!(rle.to_vec(), values);
+
+        // neighbours becoming equal are merged
+        rle.retain_map_runs(|value, _| { *value /= 10; true });
+        assert_eq!(rle.to_vec(), vec![0; 9]);
+        assert_eq!(rle.runs_len(), 1);
+
+        let mut rle = RleVec::from(&values[..]);
+        rle.retain_map_runs(|value, len| *value != 2 && len < 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 3, 3, 5]);
+        rle.retain_map_runs(|_, _| false);
+        assert!(rle.is_empty());
+
+        // dropping a run can make its neighbours equal
+        let mut rle = RleVec::from(&[7, 8, 7][..]);
+        rle.retain_map_runs(|value, _| *value == 7);
+        assert_eq!(rle.runs_len(), 1);
+        assert_eq!(rle.len(), 2);
+    }
+
+    #[test]
+    fn retain_map_runs_panic() {
+        let mut rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            rle.retain_map_runs(|value, _| {
+                if *value == 3 { panic!("boom") }
+                *value = 0;
+                true
+            });
+        }));
+        assert!(result.is_err());
+        rle.assert_valid();
+        assert_eq!(rle.to_vec(), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        let values = vec![0, 0, 1, 4, 4, 4, 9];
+        let rle = RleVec::from_sorted_iter(values.clone());
+        assert_eq!(rle, RleVec::from(&values[..]));
+        assert_eq!(rle.runs_len(), 4);
+        assert_eq!(RleVec::try_from_sorted_iter(values), Ok(rle));
+        assert_eq!(RleVec::<i32>::from_sorted_iter(vec![]), RleVec::new());
+
+        let err = RleVec::try_from_sorted_iter(vec![1, 1, 5, 5, 4]).unwrap_err();
+        assert_eq!(err, NotSorted { index: 4 });
+        assert_eq!(err.to_string(), "value at index 4 is smaller than its predecessor");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not sorted")]
+    fn from_sorted_iter_unsorted() {
+        RleVec::from_sorted_iter(vec![2, 1]);
+    }
+
+    #[test]
+    fn runs_with_context() {
+        let empty: RleVec<i32> = RleVec::new();
+        assert_eq!(empty.runs_with_context().len(), 0);
+
+        let one = RleVec::from(&[4, 4][..]);
+        let items: Vec<_> = one.runs_with_context().collect();
+        assert_eq!(items, vec![(None, Run { len: 2, value: &4 }, None)]);
+
+        let two = RleVec::from(&[4, 4, 5][..]);
+        let items: Vec<_> = two.runs_with_context().collect();
+        assert_eq!(items, vec![
+            (None, Run { len: 2, value: &4 }, Some(Run { len: 1, value: &5 })),
+            (Some(Run { len: 2, value: &4 }), Run { len: 1, value: &5 }, None),
+        ]);
+
+        let rle = RleVec::from(&[1, 2, 2, 3, 3, 3][..]);
+        let mut iter = rle.runs_with_context();
+        assert_eq!(iter.len(), 3);
+        let (prev, run, next) = iter.nth(1).unwrap();
+        assert_eq!((prev.unwrap().value, run.len, next.unwrap().len), (&1, 2, 3));
+        assert_eq!(iter.len(), 1);
+    }
+
+    #[test]
+    fn is_canonical() {
+        assert!(RleVec::<i32>::new().is_canonical());
+        assert!(RleVec::from(&[1, 2, 1][..]).is_canonical());
+        let split = RleVec { runs: vec![InternalRun { end: 1, value: 1 }, InternalRun { end: 2, value: 1 }] };
+        assert!(!split.is_canonical());
+        let empty_run = RleVec { runs: vec![InternalRun { end: 1, value: 1 }, InternalRun { end: 1, value: 2 }] };
+        assert!(!empty_run.is_canonical());
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index == self.index_back {
-            return None
-        }
-        let run = &self.rle.runs[self.run_index];
-        self.index += 1;
-        if self.index > run.end {
-            self.run_index += 1;
+    #[test]
+    fn extract_runs_with_fill() {
+        let values = [0, 5, 5, 1, 0, 7, 7, 7, 2, 0];
+        let mut rle = RleVec::from(&values[..]);
+        let extracted = rle.extract_runs_with_fill(|&v| v >= 5, 0);
+        assert_eq!(extracted, vec![(1..3, 5), (5..8, 7)]);
+        assert_eq!(rle.to_vec(), vec![0, 0, 0, 1, 0, 0, 0, 0, 2, 0]);
+        assert_eq!(rle.runs_len(), 5);
+        rle.assert_valid();
+
+        for (range, value) in extracted {
+            assert!(values[range].iter().all(|&v| v == value));
         }
-        Some(&run.value)
+
+        assert!(rle.extract_runs_with_fill(|_| false, 9).is_empty());
+        let all = rle.extract_runs_with_fill(|_| true, 9);
+        assert_eq!(all.len(), 5);
+        assert_eq!(rle.to_vec(), vec![9; 10]);
+        assert_eq!(rle.runs_len(), 1);
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.rle.len() - self.index;
-        (len, Some(len))
+    #[test]
+    fn mutation_reports() {
+        let mut rle = RleVec::from(&[1, 1, 2, 2, 3][..]);
+
+        // a size 1 run joining both neighbors
+        rle.set(2, 9);
+        rle.set(3, 1);
+        assert_eq!(rle.to_vec(), vec![1, 1, 9, 1, 3]);
+        assert_eq!(rle.set_reporting(2, 1), MutationReport { run_delta: -2, splits: 0, merges: 2 });
+        assert_eq!(rle.set_reporting(0, 1), MutationReport::default());
+        assert_eq!(rle.set_reporting(3, 3), MutationReport { run_delta: 0, splits: 1, merges: 1 });
+
+        // inserting between two runs, at the end and into an empty vector
+        assert_eq!(rle.insert_reporting(3, 2), MutationReport { run_delta: 1, splits: 1, merges: 0 });
+        assert_eq!(rle.insert_reporting(6, 4), MutationReport { run_delta: 1, splits: 1, merges: 0 });
+        assert_eq!(rle.to_vec(), vec![1, 1, 1, 2, 3, 3, 4]);
+        let mut empty = RleVec::new();
+        assert_eq!(empty.insert_reporting(0, 1), MutationReport { run_delta: 1, splits: 0, merges: 0 });
+
+        // removing a size 1 run between different values, the end and the last element
+        assert_eq!(rle.remove_reporting(3), (2, MutationReport { run_delta: -1, splits: 0, merges: 1 }));
+        assert_eq!(rle.remove_reporting(5), (4, MutationReport { run_delta: -1, splits: 0, merges: 1 }));
+        assert_eq!(rle.remove_reporting(0), (1, MutationReport::default()));
+        assert_eq!(empty.remove_reporting(0), (1, MutationReport { run_delta: -1, splits: 0, merges: 0 }));
+        assert_eq!(rle.to_vec(), vec![1, 1, 3, 3]);
+
+        // a range covering whole runs, splitting a run and an empty range
+        let mut rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        assert_eq!(rle.set_range_reporting(2..5, 1), MutationReport { run_delta: -2, splits: 0, merges: 2 });
+        assert_eq!(rle.set_range_reporting(1..3, 4), MutationReport { run_delta: 2, splits: 2, merges: 0 });
+        assert_eq!(rle.set_range_reporting(3..3, 4), MutationReport::default());
+        assert_eq!(rle.set_range_reporting(0..5, 4), MutationReport { run_delta: -2, splits: 0, merges: 2 });
+        assert_eq!(rle.to_vec(), vec![4; 5]);
     }
 
-    fn count(self) -> usize {
-        // thanks to the ExactSizeIterator impl
-        self.len()
+    #[test]
+    fn unzip_from_iter() {
+        let pairs: Vec<_> = (0..100).map(|i| (i / 10, i % 7 < 3)).collect();
+        let (a, b) = RleVec::unzip_from_iter(pairs.iter().cloned());
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a, pairs.iter().map(|p| p.0).collect::<RleVec<_>>());
+        assert_eq!(b, pairs.iter().map(|p| p.1).collect::<RleVec<_>>());
+        assert_eq!(a.runs_len(), 10);
+
+        let (a, b) = RleVec::<u8>::unzip_from_iter(Vec::<(u8, u8)>::new());
+        assert!(a.is_empty() && b.is_empty());
     }
 
-    fn last(self) -> Option<Self::Item> {
-        if self.index == self.rle.len() {
-            return None
+    #[test]
+    fn compact_to_runs() {
+        let rle = RleVec::from(&[0, 0, 0, 1, 0, 0, 2, 2, 2, 2, 3, 3][..]);
+        assert_eq!(rle.compact_to_runs(5), rle);
+        // the run of 1 is absorbed first, joining both runs of 0
+        assert_eq!(rle.compact_to_runs(4).to_vec(), vec![0, 0, 0, 0, 0, 0, 2, 2, 2, 2, 3, 3]);
+        assert_eq!(rle.compact_to_runs(3), rle.compact_to_runs(4));
+        assert_eq!(rle.compact_to_runs(1).to_vec(), vec![2; 12]);
+        assert_eq!(rle.compact_to_runs(0).to_vec(), vec![2; 12]);
+        assert!(RleVec::<u8>::new().compact_to_runs(0).is_empty());
+
+        // ties go to the run closest to the front and to the previous neighbour
+        let rle = RleVec::from(&[1, 2, 2, 3, 4, 4][..]);
+        assert_eq!(rle.compact_to_runs(3).to_vec(), vec![2, 2, 2, 3, 4, 4]);
+        assert_eq!(rle.compact_to_runs(2).to_vec(), vec![2, 2, 2, 2, 4, 4]);
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        assert_eq!(rle.compact_to_runs(2).to_vec(), vec![1, 1, 1, 3, 3]);
+
+        let values: Vec<_> = (0..1000u32).map(|i| (i * i / 7) % 5).collect();
+        let rle = RleVec::from(&values[..]);
+        for max_runs in &[1, 10, 100, rle.runs_len()] {
+            let compact = rle.compact_to_runs(*max_runs);
+            assert!(compact.runs_len() <= *max_runs);
+            assert_eq!(compact.len(), rle.len());
+            compact.assert_valid();
         }
-        self.rle.last()
     }
 
-    fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        self.index = cmp::min(self.index + n, self.rle.len());
-        self.run_index = if self.index < self.rle.len() {
-            self.rle.run_index(self.index)
-        } else {
-            self.rle.runs.len() - 1
-        };
-        self.next()
+    #[test]
+    fn get() {
+        let values = [1, 1, 2, 3, 3, 3];
+        let rle = RleVec::from(&values[..]);
+        for (i, v) in values.iter().enumerate() {
+            assert_eq!(rle.get(i), Some(v));
+            assert_eq!(unsafe { rle.get_unchecked(i) }, v);
+        }
+        assert_eq!(rle.get(values.len()), None);
+        assert_eq!(rle.get(usize::MAX), None);
+        assert_eq!(RleVec::<u8>::new().get(0), None);
+
+        let last = values.len() - 1;
+        assert_eq!(rle.get_cloned(last), Some(3));
+        assert_eq!(rle.get_cloned(values.len()), None);
+        assert_eq!(rle.get_or(last, &9), &3);
+        assert_eq!(rle.get_or(values.len(), &9), &9);
+        assert_eq!(RleVec::new().get_or(0, &9), &9);
     }
-}
 
-impl<'a, T: 'a> ExactSizeIterator for Iter<'a, T> { }
+    #[test]
+    fn owned_into_iter() {
+        use std::rc::Rc;
+
+        let shared = Rc::new(5);
+        let rle: RleVec<_> = vec![Rc::new(1), shared.clone(), shared.clone(), shared.clone()].into_iter().collect();
+        // the two merged clones are dropped when collecting
+        assert_eq!(Rc::strong_count(&shared), 2);
+
+        let mut values = rle.into_iter();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values.next().as_deref(), Some(&1));
+        let rest: Vec<_> = values.by_ref().take(2).collect();
+        assert_eq!(Rc::strong_count(&shared), 4);
+        assert_eq!(values.len(), 1);
+        drop(rest);
+        // the last element of the run moves the value out
+        let last = values.next().unwrap();
+        assert_eq!(Rc::strong_count(&shared), 2);
+        drop(last);
+        assert_eq!(values.next(), None);
+        assert_eq!(Rc::strong_count(&shared), 1);
+
+        let mut total = 0;
+        for v in RleVec::from(&[1, 1, 2, 3, 3][..]) {
+            total += v;
+        }
+        assert_eq!(total, 10);
+        assert_eq!(RleVec::<u8>::new().into_iter().next(), None);
+    }
 
-impl<'a, T: 'a> DoubleEndedIterator for Iter<'a, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.index_back == self.index {
-            return None
+    #[test]
+    fn ref_into_iter() {
+        fn total<'a, I: IntoIterator<Item=&'a u32>>(values: I) -> u32 {
+            values.into_iter().sum()
         }
-        self.index_back -= 1;
-        if self.run_index_back > 0 && self.index_back <= self.rle.runs[self.run_index_back - 1].end {
-            self.run_index_back -= 1;
+
+        let rle = RleVec::from(&[1, 1, 2, 5][..]);
+        let mut seen = Vec::new();
+        for v in &rle {
+            seen.push(*v);
         }
-        Some(&self.rle.runs[self.run_index_back].value)
+        assert_eq!(seen, vec![1, 1, 2, 5]);
+        assert_eq!(total(&rle), 9);
+        assert_eq!(total(&RleVec::new()), 0);
     }
-}
 
-/// Immutable `RelVec` iterator over runs.
-///
-/// Can be obtained from the [`runs`](struct.RleVec.html#method.runs) method.
-/// Because internally runs are stored using the end values a new Run is
-/// allocated in each iteration.
-///
-/// # Example
-/// ```
-/// # use rle_vec::{RleVec, Run};
-/// let rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 3][..]);
-///
-/// let mut iterator = rle.runs();
-/// assert_eq!(iterator.next(), Some(Run{ len: 4, value: &1 }));
-/// assert_eq!(iterator.next(), Some(Run{ len: 2, value: &2 }));
-/// assert_eq!(iterator.next(), Some(Run{ len: 1, value: &3 }));
-/// assert_eq!(iterator.next(), None);
-/// ```
-pub struct Runs<'a, T:'a> {
-    rle: &'a RleVec<T>,
-    run_index: usize,
-    last_end: usize,
-}
+    #[test]
+    fn truncate() {
+        let values = [1, 1, 1, 2, 2, 3];
+        let rle = RleVec::from(&values[..]);
+        for len in 0..values.len() + 2 {
+            let mut truncated = rle.clone();
+            truncated.truncate(len);
+            assert_eq!(truncated.to_vec(), &values[..cmp::min(len, values.len())]);
+            truncated.assert_valid();
+        }
 
-impl<'a, T: 'a> Iterator for Runs<'a, T> {
-    type Item = Run<&'a T>;
+        // at a run boundary and in the middle of a run
+        let mut boundary = rle.clone();
+        boundary.truncate(3);
+        assert_eq!(boundary.runs_len(), 1);
+        let mut middle = rle.clone();
+        middle.truncate(4);
+        assert_eq!(middle.runs_len(), 2);
+        assert_eq!(middle.last_run(), Some(Run { len: 1, value: &2 }));
+
+        let mut empty = rle.clone();
+        empty.truncate(0);
+        assert_eq!(empty, RleVec::new());
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.run_index == self.rle.runs.len() {
-            return None
-        }
-        let &InternalRun { ref value, end } = self.rle.runs.index(self.run_index);
-        let len = end - self.last_end + 1;
-        self.run_index += 1;
-        self.last_end = end + 1;
-        Some(Run { len, value })
+    #[test]
+    fn resize() {
+        let mut rle = RleVec::from(&[1, 1, 2][..]);
+        rle.resize(3, 5);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2]);
+        rle.resize(5, 2);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 2, 2]);
+        assert_eq!(rle.runs_len(), 2);
+        rle.resize(6, 3);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 2, 2, 3]);
+        rle.resize(2, 3);
+        assert_eq!(rle.to_vec(), vec![1, 1]);
+        rle.resize(0, 3);
+        assert!(rle.is_empty());
+        rle.resize(0, 3);
+        assert!(rle.is_empty());
+        rle.resize(2, 3);
+        assert_eq!(rle.to_vec(), vec![3, 3]);
+        rle.assert_valid();
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.rle.runs.len() - self.run_index;
-        (len, Some(len))
+    #[test]
+    fn split_off() {
+        let values = [1, 1, 1, 2, 2, 3];
+        let rle = RleVec::from(&values[..]);
+        for at in 0..values.len() + 1 {
+            let mut head = rle.clone();
+            let tail = head.split_off(at);
+            assert_eq!(head.to_vec(), &values[..at]);
+            assert_eq!(tail.to_vec(), &values[at..]);
+            head.assert_valid();
+            tail.assert_valid();
+        }
+
+        let mut head = rle.clone();
+        assert_eq!(head.split_off(0), rle);
+        assert!(head.is_empty());
+        let mut head = rle.clone();
+        assert!(head.split_off(values.len()).is_empty());
+        assert_eq!(head, rle);
     }
 
-    fn count(self) -> usize {
-        // thanks to the ExactSizeIterator impl
-        self.len()
+    #[test]
+    #[should_panic(expected = "`at` split index (is 7) should be <= len (is 6)")]
+    fn split_off_out_of_bounds() {
+        let mut rle = RleVec::from(&[1, 1, 1, 2, 2, 3][..]);
+        rle.split_off(7);
     }
 
-    fn last(self) -> Option<Self::Item> {
-        if self.run_index == self.rle.runs.len() {
-            return None
+    #[test]
+    fn append() {
+        let cases: &[(&[i32], &[i32])] = &[
+            (&[], &[]), (&[1], &[]), (&[], &[1, 1]), (&[1, 1, 2], &[2, 3]), (&[1, 1, 2], &[3, 2, 2]),
+        ];
+        for &(a, b) in cases {
+            let (mut x, mut y) = (RleVec::from(a), RleVec::from(b));
+            x.append(&mut y);
+            assert_eq!(x.to_vec(), [a, b].concat());
+            assert_eq!(x.len(), a.len() + b.len());
+            assert!(y.is_empty());
+            x.assert_valid();
         }
-        self.rle.last_run()
     }
 
-    fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        self.run_index = cmp::min(self.run_index + n, self.rle.runs.len());
-        self.last_end = if self.run_index != 0 {
-            self.rle.runs[self.run_index - 1].end + 1
-        } else { 0 };
-        self.next()
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn append_overflow() {
+        let mut a = RleVec::new();
+        a.push_n(usize::MAX, ());
+        let mut b = RleVec::new();
+        b.push(());
+        a.append(&mut b);
     }
-}
 
-impl<'a, T: 'a> ExactSizeIterator for Runs<'a, T> { }
+    #[test]
+    fn pop() {
+        let mut values = vec![1, 1, 2, 3, 3, 3];
+        let mut rle = RleVec::from(&values[..]);
+        while let Some(value) = values.pop() {
+            assert_eq!(rle.pop(), Some(value));
+            assert_eq!(rle.to_vec(), values);
+            rle.assert_valid();
+        }
+        assert_eq!(rle.pop(), None);
+        assert!(rle.is_empty());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        rle.push(4);
+        assert_eq!(rle.pop(), Some(4));
+        assert_eq!(rle.runs_len(), 0);
+    }
 
     #[test]
-    fn rare_usage() {
-        // from slice
+    fn run_views() {
+        let rle = RleVec::from(&["a", "a", "b", "c", "c", "c"][..]);
+        let owned: Vec<Run<&str>> = rle.runs().map(Run::from).collect();
+        assert_eq!(owned, vec![Run { len: 2, value: "a" }, Run { len: 1, value: "b" }, Run { len: 3, value: "c" }]);
+        let borrowed: Vec<RunRef<&str>> = owned.iter().map(Run::as_ref).collect();
+        assert!(borrowed.into_iter().eq(rle.runs()));
+
+        // round trip through owned runs
+        let copy: RleVec<&str> = owned.into_iter().collect();
+        assert_eq!(copy, rle);
+        let upper: RleVec<String> = rle.runs().map(|run| run.map(|s| s.to_uppercase())).collect();
+        assert_eq!(upper.to_vec(), vec!["A", "A", "B", "C", "C", "C"]);
+    }
 
-        let rle: RleVec<i32> = RleVec::from(&[][..]);
-        assert_eq!(rle.to_vec(), vec![]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![]);
+    #[test]
+    fn extend_runs() {
+        let decoded = vec![(1, 2), (1, 0), (2, 0), (1, 3), (4, 1), (4, 2), (5, 0)];
+        let mut rle = RleVec::from(&[7, 1][..]);
+        rle.extend(decoded.iter().map(|&(value, len)| Run { len, value }));
+
+        let mut expected = RleVec::from(&[7, 1][..]);
+        for &(value, len) in &decoded {
+            expected.push_n(len, value);
+        }
+        assert_eq!(rle, expected);
+        assert_eq!(rle.to_vec(), vec![7, 1, 1, 1, 1, 1, 1, 4, 4, 4]);
+        assert_eq!(rle.runs_len(), 3);
+        rle.assert_valid();
+    }
 
-        let rle: RleVec<i32> = RleVec::from(&[1][..]);
-        assert_eq!(rle.to_vec(), vec![1]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 1, value: &1 }]);
+    #[test]
+    fn extend_empty_runs() {
+        let mut rle = RleVec::new();
+        rle.extend(vec![Run { len: 0, value: 1 }]);
+        assert!(rle.is_empty());
+        assert_eq!(rle.runs_len(), 0);
 
-        let rle: RleVec<i32> = RleVec::from(&[1, 2][..]);
-        assert_eq!(rle.to_vec(), vec![1, 2]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 1, value: &1 }, Run { len: 1, value: &2 }]);
+        rle.push(2);
+        rle.extend(vec![Run { len: 0, value: 3 }, Run { len: 0, value: 2 }]);
+        assert_eq!(rle.to_vec(), vec![2]);
+        assert_eq!(rle.runs_len(), 1);
+        rle.assert_valid();
+    }
 
-        let rle: RleVec<i32> = RleVec::from(&[1, 1][..]);
-        assert_eq!(rle.to_vec(), vec![1, 1]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 2, value: &1 }]);
+    #[test]
+    fn extend_from_other_rle() {
+        fn fill<E: Extend<u8>>(target: &mut E, values: &[u8]) {
+            target.extend(values.iter().cloned());
+        }
 
-        // from iter
+        let other = RleVec::from(&[3, 3, 4][..]);
+        let mut rle = RleVec::from(&[1, 3][..]);
+        rle.extend(other.iter().cloned());
+        assert_eq!(rle.to_vec(), vec![1, 3, 3, 3, 4]);
+        rle.extend(other.runs().map(Run::cloned));
+        assert_eq!(rle.to_vec(), vec![1, 3, 3, 3, 4, 3, 3, 4]);
+        assert_eq!(rle.runs_len(), 5);
 
-        let rle: RleVec<i32> = RleVec::from_iter(0..0);
-        assert_eq!(rle.to_vec(), vec![]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![]);
+        fill(&mut rle, &[4, 4]);
+        let mut vec = Vec::new();
+        fill(&mut vec, &[4, 4]);
+        assert_eq!(rle.to_vec()[8..], vec[..]);
+        assert_eq!(rle.runs_len(), 5);
+    }
 
-        let rle: RleVec<i32> = RleVec::from_iter(1..2);
-        assert_eq!(rle.to_vec(), vec![1]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 1, value: &1 }]);
+    #[test]
+    fn equality_of_construction_paths() {
+        let values = [1, 1, 2, 2, 2, 3, 1];
+        let from_slice = RleVec::from(&values[..]);
 
-        let rle: RleVec<i32> = RleVec::from_iter(1..3);
-        assert_eq!(rle.to_vec(), vec![1, 2]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 1, value: &1 }, Run { len: 1, value: &2 }]);
+        let mut pushed = RleVec::new();
+        for &v in &values {
+            pushed.push(v);
+        }
+        let mut inserted = RleVec::new();
+        for &v in values.iter().rev() {
+            inserted.insert(0, v);
+        }
+        let mut set = RleVec::from(&[0; 7][..]);
+        for (i, &v) in values.iter().enumerate() {
+            set.set(i, v);
+        }
 
-        use std::iter::repeat;
-        let rle: RleVec<i32> = RleVec::from_iter(repeat(1).take(2));
-        assert_eq!(rle.to_vec(), vec![1, 1]);
-        let runs: Vec<_> = rle.runs().collect();
-        assert_eq!(runs, vec![Run{ len: 2, value: &1 }]);
+        for rle in &[&pushed, &inserted, &set] {
+            assert_eq!(**rle, from_slice);
+            assert!(rle.identical_runs(&from_slice));
+        }
+        inserted.remove(2);
+        assert_ne!(inserted, from_slice);
     }
 
     #[test]
-    fn basic_usage() {
-        let mut rle = RleVec::<i64>::new();
-        rle.push(1);
-        rle.push(1);
-        rle.push(1);
-        rle.push(1);
-        rle.push(2);
-        rle.push(2);
-        rle.push(2);
-        rle.push(3);
-        rle.push(3);
-        rle.push(4);
-        assert_eq!(rle.len(), 10);
-        assert_eq!(rle.runs_len(), 4);
-
-        rle.push_n(3, 4);
-        assert_eq!(rle.len(), 13);
-        assert_eq!(rle.runs_len(), 4);
-        assert_eq!(rle.last(), Some(&4));
-        rle.push_n(3, 5);
-        assert_eq!(rle.len(), 16);
-        assert_eq!(rle.runs_len(), 5);
-        assert_eq!(rle.last(), Some(&5));
-        assert_eq!(rle.last_run(), Some(Run {value: &5, len: 3}));
-        rle.clear();
-        assert_eq!(rle.len(), 0);
-        assert_eq!(rle.runs_len(), 0);
-        assert_eq!(rle.last(), None);
-        assert_eq!(rle.last_run(), None);
-
-        let mut rle = RleVec::default();
-        rle.push(1);
-        assert_eq!(rle.len(), 1);
+    fn compare_with_vec_and_in_sets() {
+        use std::collections::HashSet;
+
+        let rle = RleVec::from(&[1, 1, 2][..]);
+        assert_eq!(rle, vec![1, 1, 2]);
+        assert_eq!(rle, [1, 1, 2][..]);
+        assert_ne!(rle, vec![1, 2]);
+        assert_ne!(rle, vec![1, 1, 3]);
+
+        let mut set = HashSet::new();
+        set.insert(rle.clone());
+        set.insert((0..3).map(|i| 1 + i / 2).collect());
+        set.insert(RleVec::from(&[1, 2, 2][..]));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&rle));
     }
 
     #[test]
-    fn setting_values() {
-        let mut rle = RleVec::<i64>::new();
-        rle.push(1);
-        rle.set(0, 10);
-        assert_eq!(rle.len(), 1);
+    fn insert_n() {
+        let mut v = vec![0, 0, 1, 1, 1, 2];
+        let mut rle = RleVec::from(&v[..]);
+        let inserts = [(0, 2, 0), (0, 1, 5), (3, 2, 1), (4, 3, 7), (14, 2, 2), (14, 1, 3), (5, 0, 9)];
+        for &(index, n, value) in inserts.iter() {
+            rle.insert_n(index, n, value);
+            v.splice(index..index, vec![value; n]);
+            assert_eq!(rle.to_vec(), v);
+            assert_eq!(rle, RleVec::from(&v[..]));
+        }
+
+        let mut rle = RleVec::new();
+        rle.insert_n(0, 0, 1);
+        assert!(rle.is_empty());
+        rle.insert_n(0, 3, 1);
         assert_eq!(rle.runs_len(), 1);
-        assert_eq!(rle[0], 10);
+        assert_eq!(rle.len(), 3);
+    }
 
-        let mut rle = RleVec::from(&[1, 1, 1, 1, 2, 2, 2, 3, 3, 4, 5][..]);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 3)")]
+    fn insert_n_out_of_bounds() {
+        let mut rle = RleVec::from(&[1, 2, 3][..]);
+        rle.insert_n(4, 0, 1);
+    }
 
-        //set no change
-        //run size > 1
-        rle.set(0, 1);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
-        rle.set(2, 1);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
-        rle.set(4, 2);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
-        rle.set(6, 2);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
-        //run size == 1
-        rle.set(9, 4);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
-        rle.set(10, 5);
-        assert_eq!(rle.to_vec(), vec![1,1,1,1,2,2,2,3,3,4, 5]);
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn insert_n_overflow() {
+        let mut rle = RleVec::new();
+        rle.push_n(usize::MAX - 1, 1);
+        rle.insert_n(0, 2, 1);
+    }
 
-        //set change no joins
-        //run size > 1
-        rle.set(0, 2);
-        assert_eq!(rle.to_vec(), vec![2,1,1,1,2,2,2,3,3,4, 5]);
-        rle.set(2, 2);
-        assert_eq!(rle.to_vec(), vec![2,1,2,1,2,2,2,3,3,4, 5]);
-        rle.set(4, 3);
-        assert_eq!(rle.to_vec(), vec![2,1,2,1,3,2,2,3,3,4, 5]);
-        rle.set(8, 7);
-        assert_eq!(rle.to_vec(), vec![2,1,2,1,3,2,2,3,7,4, 5]);
-        //run size == 1
-        rle.set(0, 3);
-        assert_eq!(rle.to_vec(), vec![3,1,2,1,3,2,2,3,7,4, 5]);
-        rle.set(3, 4);
-        assert_eq!(rle.to_vec(), vec![3,1,2,4,3,2,2,3,7,4, 5]);
-        rle.set(10, 7);
-        assert_eq!(rle.to_vec(), vec![3,1,2,4,3,2,2,3,7,4, 7]);
-        assert_eq!(rle.runs_len(), 10);
+    #[test]
+    fn hash_matches_equality() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashMap;
+        use std::hash::{Hash, Hasher};
+
+        fn hash<T: Hash>(rle: &RleVec<T>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            rle.hash(&mut hasher);
+            hasher.finish()
+        }
 
-        //set change, with join
-        rle.set(0, 1);
-        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,2,2,3,7,4, 7]);
-        assert_eq!(rle.runs_len(), 9);
-        rle.set(5, 3);
-        assert_eq!(rle.runs_len(), 9);
-        rle.set(6, 3);
-        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,3,3,3,7,4, 7]);
-        assert_eq!(rle.runs_len(), 7);
-        rle.set(10, 4);
-        assert_eq!(rle.to_vec(), vec![1,1,2,4,3,3,3,3,7,4, 4]);
-        assert_eq!(rle.runs_len(), 6);
+        let from_slice = RleVec::from(&[1, 1, 2, 2, 2, 3][..]);
+        let mut pushed = RleVec::new();
+        pushed.push_n(2, 1);
+        pushed.push_n(3, 2);
+        pushed.push(3);
+        let mut edited = RleVec::from(&[1, 5, 2, 7, 2, 3][..]);
+        edited.set(1, 1);
+        edited.insert(3, 2);
+        edited.remove(4);
+        let collected: RleVec<i32> = vec![Run { len: 2, value: 1 }, Run { len: 1, value: 2 },
+                                          Run { len: 2, value: 2 }, Run { len: 0, value: 4 },
+                                          Run { len: 1, value: 3 }].into_iter().collect();
+
+        for rle in &[&pushed, &edited, &collected] {
+            assert_eq!(**rle, from_slice);
+            assert_eq!(hash(rle), hash(&from_slice));
+        }
+        assert_ne!(hash(&from_slice), hash(&RleVec::from(&[1, 1, 2, 2, 3, 3][..])));
+
+        let mut cache = HashMap::new();
+        cache.insert(from_slice, "cached");
+        assert_eq!(cache.get(&edited), Some(&"cached"));
     }
 
     #[test]
-    fn removing_values() {
-        let mut rle = RleVec::from(&[1, 1, 1, 1, 1, 2, 1, 1, 1, 4, 4, 3, 3][..]);
-        assert_eq!(rle.len(), 13);
-        assert_eq!(rle.runs_len(), 5);
+    fn lexicographic_order() {
+        let mut vecs: Vec<Vec<u8>> = vec![vec![], vec![0], vec![1, 1, 2], vec![1, 2], vec![1, 2, 0]];
+        // simple lcg to get short sequences over few values
+        let mut state = 7u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        for _ in 0..100 {
+            let len = next() % 8;
+            vecs.push((0..len).map(|_| (next() % 3) as u8).collect());
+        }
+        for a in &vecs {
+            for b in &vecs {
+                let (x, y) = (RleVec::from(&a[..]), RleVec::from(&b[..]));
+                assert_eq!(x.cmp(&y), a.cmp(b), "{:?} {:?}", a, b);
+                assert_eq!(x.partial_cmp(&y), a.partial_cmp(b), "{:?} {:?}", a, b);
+            }
+        }
 
-        let value = rle.remove(5);
-        assert_eq!(value, 2);
-        assert_eq!(rle.len(), 12);
-        assert_eq!(rle.runs_len(), 3);
-        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 1, 4, 4, 3, 3]);
+        let mut sorted: Vec<RleVec<u8>> = vecs.iter().map(|v| RleVec::from(&v[..])).collect();
+        sorted.sort();
+        vecs.sort();
+        assert_eq!(sorted.iter().map(|rle| rle.to_vec()).collect::<Vec<_>>(), vecs);
 
-        let value = rle.remove(7);
-        assert_eq!(value, 1);
-        assert_eq!(rle.len(), 11);
-        assert_eq!(rle.runs_len(), 3);
-        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 4, 4, 3, 3]);
+        let nan = RleVec::linspace(f64::NAN, f64::NAN, 2, 0.0);
+        assert_eq!(nan.partial_cmp(&nan), None);
+        let ramp = RleVec::linspace(0.0, 1.0, 3, 0.0);
+        assert!(ramp < RleVec::linspace(0.0, 2.0, 2, 0.0));
+        assert!(ramp > RleVec::linspace(0.0, 0.0, 4, 0.0));
+    }
 
-        let value = rle.remove(10);
-        assert_eq!(value, 3);
-        assert_eq!(rle.len(), 10);
-        assert_eq!(rle.runs_len(), 3);
-        assert_eq!(rle.to_vec(), vec![1, 1, 1, 1, 1, 1, 1, 4, 4, 3]);
+    #[test]
+    fn gather() {
+        let v = vec![0, 0, 1, 1, 1, 2, 0, 0, 3];
+        let rle = RleVec::from(&v[..]);
+        let patterns: Vec<Vec<usize>> = vec![
+            vec![],
+            (0..v.len()).collect(),
+            (0..v.len()).rev().collect(),
+            vec![0, 8, 1, 7, 2, 6, 3, 5, 4],
+            vec![2, 2, 2, 4, 4, 0, 1, 6],
+            vec![8, 8, 8],
+        ];
+        for indices in patterns {
+            let gathered = rle.gather(indices.iter().cloned());
+            let expected: Vec<_> = indices.iter().map(|&i| v[i]).collect();
+            assert_eq!(gathered.to_vec(), expected);
+            assert_eq!(gathered, RleVec::from(&expected[..]));
+        }
+
+        assert_eq!(rle.try_gather(vec![1, 9, 10]), Err(OutOfBoundsError { index: 9, len: 9 }));
+        assert_eq!(RleVec::<u8>::new().try_gather(0..1), Err(OutOfBoundsError { index: 0, len: 0 }));
     }
 
     #[test]
-    fn inserting_values() {
-        let mut v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+    #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+    fn gather_out_of_bounds() {
+        RleVec::from(&[1, 2, 3][..]).gather(vec![0, 3]);
+    }
+
+    #[test]
+    fn default_is_empty() {
+        use std::collections::HashMap;
+
+        let rle: RleVec<u8> = Default::default();
+        assert!(rle.is_empty());
+        assert_eq!(rle.runs_len(), 0);
+        let parsed: Option<RleVec<u8>> = "".parse::<u8>().ok().map(|v| RleVec::from(&[v][..]));
+        assert_eq!(parsed.unwrap_or_default(), rle);
+
+        let mut tracks: HashMap<&str, RleVec<u8>> = HashMap::new();
+        tracks.entry("a").or_default().push_n(3, 1);
+        tracks.entry("a").or_default().push(2);
+        tracks.entry("b").or_default();
+        assert_eq!(tracks["a"].to_vec(), vec![1, 1, 1, 2]);
+        assert!(tracks["b"].is_empty());
+        assert_eq!(std::mem::take(tracks.get_mut("a").unwrap()).len(), 4);
+        assert!(tracks["a"].is_empty());
+    }
+
+    #[test]
+    fn scatter() {
+        let mut v = vec![0, 0, 1, 1, 1, 2, 0, 0, 3];
         let mut rle = RleVec::from(&v[..]);
-        rle.insert(0,1);
-        v.insert(0,1);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.len(),18);
-        rle.insert(18,9);
-        v.insert(18,9);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        rle.insert(19,10);
-        v.insert(19,10);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
+        let batches = vec![
+            vec![],
+            vec![(8, 0), (0, 5), (4, 7)],
+            vec![(3, 1), (3, 2), (3, 1)],
+            vec![(2, 0), (1, 0), (0, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0)],
+            vec![(4, 9), (5, 9), (3, 9), (4, 8), (0, 9)],
+        ];
+        for updates in batches {
+            for &(i, value) in &updates {
+                v[i] = value;
+            }
+            rle.scatter(updates);
+            assert_eq!(rle.to_vec(), v);
+            assert_eq!(rle, RleVec::from(&v[..]));
+        }
 
-        rle.insert(2,0);
-        v.insert(2,0);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.runs_len(), 9);
+        let before = rle.clone();
+        assert_eq!(rle.try_scatter(vec![(0, 1), (10, 1), (9, 1)]), Err(OutOfBoundsError { index: 10, len: 9 }));
+        assert_eq!(rle, before);
+        assert_eq!(RleVec::<u8>::new().try_scatter(vec![]), Ok(()));
+    }
 
-        rle.insert(8,0);
-        v.insert(8,0);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.runs_len(), 11);
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+    fn scatter_out_of_bounds() {
+        RleVec::from(&[1, 2, 3][..]).scatter(vec![(0, 1), (3, 1)]);
+    }
 
-        rle.insert(13,4);
-        v.insert(13,4);
-        assert_eq!((0..rle.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.runs_len(), 12);
+    #[test]
+    fn drain() {
+        let v = vec![0, 0, 1, 1, 1, 2, 0, 0, 3, 3];
+        for start in 0..=v.len() {
+            for end in start..=v.len() {
+                let mut rle = RleVec::from(&v[..]);
+                let mut expected = v.clone();
+                let drained: Vec<_> = rle.drain(start..end).collect();
+                assert_eq!(drained, expected.drain(start..end).collect::<Vec<_>>());
+                assert_eq!(rle.to_vec(), expected);
+                rle.assert_valid();
+            }
+        }
 
-        let v = vec![0,0,0,1,1,1,1,2,2,3];
-        let mut rle: RleVec<_> = v.into_iter().collect();
-        rle.set(1,2);
-        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,1,1,1,2,2,3]);
-        rle.insert(4,4);
-        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,2,2,3]);
-        rle.insert(7,1);
-        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,1,2,2,3]);
-        rle.insert(8,8);
-        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), vec![0,2,0,1,4,1,1,1,8,1,2,2,3]);
+        let mut rle = RleVec::from(&v[..]);
+        assert_eq!(rle.drain(..=1).len(), 2);
+        assert_eq!(rle.drain((Bound::Excluded(0), Bound::Unbounded)).collect::<Vec<_>>(), vec![1, 1, 2, 0, 0, 3, 3]);
+        assert_eq!(rle.to_vec(), vec![1]);
+
+        // the range is removed even if the iterator is leaked
+        let mut rle = RleVec::from(&v[..]);
+        std::mem::forget(rle.drain(3..7));
+        assert_eq!(rle.to_vec(), vec![0, 0, 1, 0, 3, 3]);
+        rle.assert_valid();
     }
 
     #[test]
-    fn from_slice() {
-        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
-        let rle = RleVec::from(&v[..]);
-        assert_eq!((0..v.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.len(),17);
+    #[should_panic(expected = "range end index 4 out of range for RleVec of length 3")]
+    fn drain_out_of_bounds() {
+        RleVec::from(&[1, 2, 3][..]).drain(1..4);
+    }
 
-        let v2: Vec<_> = rle.into();
-        assert_eq!(v2,v);
+    #[test]
+    fn extend_merges_into_last_run() {
+        let mut rle = RleVec::from(&[1, 5, 5][..]);
+        rle.extend(std::iter::repeat(5).take(1000));
+        assert_eq!(rle.len(), 1003);
+        assert_eq!(rle.runs_len(), 2);
+        assert_eq!(rle.runs().last(), Some(Run { len: 1002, value: &5 }));
+
+        rle.extend(vec![5, 6, 6]);
+        assert_eq!(rle.len(), 1006);
+        assert_eq!(rle.runs_len(), 3);
+        rle.extend(Vec::<i32>::new());
+        assert_eq!(rle.len(), 1006);
     }
 
     #[test]
-    fn iterators() {
-        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,123,0,90,90,99];
-        let rle = v.iter().cloned().collect::<RleVec<_>>();
-        assert_eq!((0..v.len()).map(|i| rle[i]).collect::<Vec<_>>(), v);
-        assert_eq!(rle.len(), 17);
+    fn range_forms() {
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        assert_eq!(rle.resolve_range(..), 0..5);
+        assert_eq!(rle.resolve_range(2..), 2..5);
+        assert_eq!(rle.resolve_range(..3), 0..3);
+        assert_eq!(rle.resolve_range(..=3), 0..4);
+        assert_eq!(rle.resolve_range(1..4), 1..4);
+        assert_eq!(rle.resolve_range(1..=4), 1..5);
+        assert_eq!(rle.resolve_range(5..), 5..5);
+        assert_eq!(rle.resolve_range((Bound::Excluded(1), Bound::Included(2))), 2..3);
+
+        let mut max = RleVec::new();
+        max.push_n(usize::MAX, ());
+        assert_eq!(max.resolve_range(1..), 1..usize::MAX);
+        assert_eq!(max.resolve_range(..usize::MAX), 0..usize::MAX);
+        assert_eq!(max.resolve_range(..=usize::MAX - 1), 0..usize::MAX);
+    }
 
-        assert_eq!(rle.iter().cloned().collect::<Vec<_>>(), v);
-        assert_eq!(RleVec::<i64>::new().iter().next(), None);
+    #[test]
+    fn slice_range_forms() {
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
+        let v = rle.to_vec();
+        assert_eq!(rle.slice(..).to_vec(), v[..].to_vec());
+        assert_eq!(rle.slice(2..).to_vec(), v[2..].to_vec());
+        assert_eq!(rle.slice(..3).to_vec(), v[..3].to_vec());
+        assert_eq!(rle.slice(..=3).to_vec(), v[..=3].to_vec());
+        assert_eq!(rle.slice(1..4).to_vec(), v[1..4].to_vec());
+        assert_eq!(rle.slice(1..=4).to_vec(), v[1..=4].to_vec());
+        assert!(rle.slice(5..).is_empty());
+        rle.slice(1..=2).assert_valid();
+
+        assert_eq!(rle.slice_checked(1..=4), Some(rle.slice(1..5)));
+        assert_eq!(rle.slice_checked((Bound::Included(3), Bound::Excluded(2))), None);
+        assert_eq!(rle.slice_checked(4..6), None);
+        assert_eq!(rle.slice_checked(..=usize::MAX), None);
+        assert_eq!(rle.slice_checked((Bound::Excluded(usize::MAX), Bound::Unbounded)), None);
+
+        let mut rle = rle;
+        rle.set_range(3.., 0);
+        rle.add_range(..=1, 1);
+        assert_eq!(rle.to_vec(), vec![2, 2, 2, 0, 0]);
+        assert_eq!(rle.range_sum::<i64>(..), 6);
+        assert_eq!(rle.range_max(2..=3), Some(&2));
+        assert!(rle.eq_range(..2, &RleVec::from(&[0, 2, 2][..]), 1..));
+        rle.swap_ranges(..1, 4..);
+        assert_eq!(rle.to_vec(), vec![0, 2, 2, 0, 2]);
+    }
 
-        let v2 = (0..100).collect::<Vec<usize>>();
-        let rle2 = v2.iter().cloned().collect::<RleVec<_>>();
-        assert_eq!(rle2.iter().cloned().collect::<Vec<_>>(), v2);
-        assert_eq!(rle2.iter().skip(0).cloned().collect::<Vec<_>>(), v2);
+    #[test]
+    #[should_panic(expected = "range end index 6 out of range for RleVec of length 5")]
+    fn slice_out_of_bounds() {
+        RleVec::from(&[1, 1, 2, 3, 3][..]).slice(2..=5);
+    }
 
-        assert_eq!(rle2.iter().nth(0), Some(&0));
-        assert_eq!(rle2.iter().nth(5), Some(&5));
-        assert_eq!(rle2.iter().nth(99), Some(&99));
-        assert_eq!(rle2.iter().nth(100), None);
-        let mut it = rle2.iter();
-        it.nth(0);
-        assert_eq!(it.nth(0), Some(&1));
+    #[test]
+    #[should_panic(expected = "attempted to index RleVec up to maximum usize")]
+    fn range_end_overflow() {
+        RleVec::from(&[1][..]).resolve_range(..=usize::MAX);
+    }
 
-        assert_eq!(rle.iter().nth(3), Some(&1));
-        assert_eq!(rle.iter().nth(14), Some(&90));
-        assert_eq!(rle.iter().nth(15), Some(&90));
+    #[test]
+    #[should_panic(expected = "attempted to index RleVec from after maximum usize")]
+    fn range_start_overflow() {
+        RleVec::from(&[1][..]).resolve_range((Bound::Excluded(usize::MAX), Bound::Unbounded));
+    }
 
-        assert_eq!(rle.iter().skip(2).next(), Some(&0));
-        assert_eq!(rle.iter().skip(3).next(), Some(&1));
+    #[test]
+    #[should_panic(expected = "slice index starts at 3 but ends at 2")]
+    fn range_start_after_end() {
+        RleVec::from(&[1, 2, 3][..]).resolve_range((Bound::Included(3), Bound::Excluded(2)));
+    }
 
-        assert_eq!(rle.iter().max(), Some(&123));
-        assert_eq!(rle.iter().min(), Some(&0));
-        assert_eq!(rle.iter().skip(13).max(), Some(&99));
-        assert_eq!(rle.iter().skip(13).min(), Some(&0));
-        assert_eq!(rle.iter().skip(13).take(2).max(), Some(&90));
-        assert_eq!(rle.iter().skip(13).take(2).min(), Some(&0));
+    #[test]
+    #[should_panic(expected = "range end index 4 out of range for RleVec of length 3")]
+    fn range_end_out_of_bounds() {
+        RleVec::from(&[1, 2, 3][..]).resolve_range(1..=3);
+    }
 
-        assert_eq!(rle.iter().count(), 17);
-        assert_eq!(rle.iter().skip(10).last(), Some(&99));
-        assert_eq!(rle.iter().skip(30).last(), None);
+    #[test]
+    fn retain() {
+        let mut calls = 0;
+        let mut rle = RleVec::from(&[9, 9, 1, 1, 9, 2, 9, 2, 2, 9][..]);
+        assert_eq!(rle.retain(|&v| { calls += 1; v != 9 }), 5);
+        assert_eq!(calls, 7);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2, 2, 2]);
+        assert_eq!(rle.runs_len(), 2);
+
+        // first run, last run and a run between equal neighbours
+        let v = [0, 0, 1, 2, 2, 1, 1, 3];
+        for dropped in &[0, 3, 2] {
+            let mut rle = RleVec::from(&v[..]);
+            rle.retain(|v| v != dropped);
+            let expected: Vec<_> = v.iter().cloned().filter(|v| v != dropped).collect();
+            assert_eq!(rle.to_vec(), expected);
+            assert_eq!(rle, RleVec::from(&expected[..]));
+        }
 
-        //runiters
-        assert_eq!(rle.runs().map(|r| r.value).collect::<Vec<_>>(), vec![&0,&1,&3,&123,&0,&90,&99]);
-        assert_eq!(rle.runs().map(|r| r.len).collect::<Vec<_>>(), vec![3,7,2,1,1,2,1]);
+        let mut rle = RleVec::from(&v[..]);
+        assert_eq!(rle.retain(|_| true), 0);
+        assert_eq!(rle.retain(|_| false), v.len());
+        assert!(rle.is_empty());
+    }
 
-        let mut copy = RleVec::new();
-        for r in rle.runs() {
-            copy.push_n(r.len, r.value.clone());
+    #[test]
+    fn take_range() {
+        let v = [0, 0, 1, 1, 1, 2, 0, 0, 3, 3];
+        let original = RleVec::from(&v[..]);
+        for start in 0..=v.len() {
+            for end in start..=v.len() {
+                let mut rle = original.clone();
+                let mut taken = rle.take_range(start..end);
+                assert_eq!(taken.to_vec(), &v[start..end]);
+                taken.assert_valid();
+                rle.assert_valid();
+
+                // head, taken and tail concatenate to the original
+                let mut tail = rle.split_off(start);
+                rle.append(&mut taken);
+                rle.append(&mut tail);
+                assert_eq!(rle, original);
+            }
         }
-        assert_eq!(copy.iter().cloned().collect::<Vec<_>>(), v);
-        let copy2: RleVec<i32> = rle.runs().map(|r| Run { value: r.value.clone(), len: r.len }).collect();
-        assert_eq!(copy2.iter().cloned().collect::<Vec<_>>(), v);
     }
 
     #[test]
-    fn back_iterators() {
-        let rle = RleVec::from(&[0,1,1,3,3,9,99][..]);
+    fn remove_all() {
+        let mut rle = RleVec::from(&[3, 1, 1, 3, 3, 2, 3][..]);
+        assert_eq!(rle.remove_all(&3), 4);
+        assert_eq!(rle.to_vec(), vec![1, 1, 2]);
+        assert_eq!(rle.remove_all(&1), 2);
+        assert_eq!(rle.remove_all(&1), 0);
+        assert_eq!(rle.remove_all(&2), 1);
+        assert!(rle.is_empty());
+    }
 
-        // only next_back()
+    #[test]
+    fn zero_sized_values() {
+        let big = usize::MAX / 2;
+        let mut rle = RleVec::new();
+        rle.push_n(big, ());
+        rle.push(());
+        assert_eq!(rle.len(), big + 1);
+        assert_eq!(rle.runs_len(), 1);
+        assert_eq!(rle[big], ());
+
+        assert_eq!(rle.iter().count(), big + 1);
+        assert_eq!(rle.iter().nth(big), Some(&()));
+        assert_eq!(rle.iter().nth(big + 1), None);
+        assert_eq!(rle.iter().nth(usize::MAX), None);
+        assert_eq!(rle.iter().last(), Some(&()));
         let mut iter = rle.iter();
-        assert_eq!(iter.next_back(), Some(&99));
-        assert_eq!(iter.next_back(), Some(&9));
-        assert_eq!(iter.next_back(), Some(&3));
-        assert_eq!(iter.next_back(), Some(&3));
-        assert_eq!(iter.next_back(), Some(&1));
-        assert_eq!(iter.next_back(), Some(&1));
-        assert_eq!(iter.next_back(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&()));
+        assert_eq!(iter.len(), big);
+        assert_eq!(iter.nth(big - 1), Some(&()));
+        assert_eq!(iter.next(), None);
         assert_eq!(iter.next_back(), None);
+        assert_eq!(rle.runs().collect::<Vec<_>>(), vec![Run { len: big + 1, value: &() }]);
+
+        rle.set(big / 2, ());
+        rle.insert(big / 2, ());
+        rle.insert(0, ());
+        assert_eq!(rle.len(), big + 3);
+        assert_eq!(rle.remove(big), ());
+        assert_eq!(rle.len(), big + 2);
+        assert_eq!(rle.runs_len(), 1);
+        rle.assert_valid();
+
+        let chunks = rle.partition_chunks(3);
+        assert_eq!(chunks.iter().map(RleVec::len).sum::<usize>(), big + 2);
+
+        let small: RleVec<()> = std::iter::repeat(()).take(1000).collect();
+        assert_eq!(small.to_vec(), vec![(); 1000]);
+
+        let mut max = RleVec::new();
+        max.push_n(usize::MAX, ());
+        assert_eq!(max.len(), usize::MAX);
+        assert_eq!(max.iter().len(), usize::MAX);
+        assert_eq!(max.iter().nth(usize::MAX - 1), Some(&()));
+        assert_eq!(max.remove(0), ());
+        max.insert(0, ());
+        assert_eq!(max.len(), usize::MAX);
+    }
 
-        // next_back() combine with next()
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn zero_sized_push_overflow() {
+        let mut rle = RleVec::new();
+        rle.push_n(usize::MAX, ());
+        rle.push(());
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn zero_sized_insert_overflow() {
+        let mut rle = RleVec::new();
+        rle.push_n(usize::MAX, ());
+        rle.insert(0, ());
+    }
+
+    #[test]
+    fn iter_after_next_back() {
+        let rle = RleVec::from(&[1, 1, 2, 3, 3][..]);
         let mut iter = rle.iter();
-        assert_eq!(iter.next_back(), Some(&99));
-        assert_eq!(iter.next(),      Some(&0));
-        assert_eq!(iter.next(),      Some(&1));
-        assert_eq!(iter.next_back(), Some(&9));
-        assert_eq!(iter.next_back(), Some(&3));
         assert_eq!(iter.next_back(), Some(&3));
-        assert_eq!(iter.next(),      Some(&1));
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(iter.nth(3), Some(&3));
+        assert_eq!(iter.next(), None);
+
+        let mut iter = rle.iter();
+        iter.next_back();
+        iter.next_back();
+        assert_eq!(iter.nth(3), None);
         assert_eq!(iter.next_back(), None);
-        assert_eq!(iter.next(),      None);
 
-        // rare usages of next_back() combine with next()
-        let rle = RleVec::from(&[0][..]);
         let mut iter = rle.iter();
-        assert_eq!(iter.next_back(), Some(&0));
-        assert_eq!(iter.next(),      None);
+        iter.next_back();
+        iter.next_back();
+        assert_eq!(iter.last(), Some(&2));
 
-        let rle = RleVec::<i32>::from(&[][..]);
         let mut iter = rle.iter();
-        assert_eq!(iter.next_back(), None);
-        assert_eq!(iter.next(),      None);
+        iter.next();
+        assert_eq!(iter.nth(usize::MAX), None);
+        assert_eq!(RleVec::<i32>::new().iter().nth(0), None);
+        assert_eq!(RleVec::<i32>::new().iter().last(), None);
     }
 
     #[test]
-    fn run_iters() {
-        let rle = RleVec::from(&[1,1,1,1,1,2,2,2,2,3,3,3,5,5,5,5][..]);
-
-        let mut iterator = rle.runs();
-
-        assert_eq!(iterator.next(), Some(Run{ len: 5, value: &1 }));
-        assert_eq!(iterator.next(), Some(Run{ len: 4, value: &2 }));
-        assert_eq!(iterator.next(), Some(Run{ len: 3, value: &3 }));
-        assert_eq!(iterator.next(), Some(Run{ len: 4, value: &5 }));
-        assert_eq!(iterator.next(), None);
-        assert_eq!(iterator.next(), None);
-
-        let mut iterator = rle.runs();
-
-        assert_eq!(iterator.nth(0), Some(Run{ len: 5, value: &1 }));
-        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &2 }));
-        assert_eq!(iterator.nth(0), Some(Run{ len: 3, value: &3 }));
-        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &5 }));
-        assert_eq!(iterator.nth(0), None);
-
-        let mut iterator = rle.runs();
+    fn partition_chunks() {
+        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
+        let rle = RleVec::from(&v[..]);
+        for n in 1..20 {
+            let pieces = rle.partition_chunks(n);
+            assert_eq!(pieces.len(), n);
+            for p in &pieces {
+                let len = p.len();
+                assert!(len == v.len() / n || len == v.len() / n + 1);
+            }
+            let joined: Vec<_> = pieces.iter().flat_map(|p| p.to_vec()).collect();
+            assert_eq!(joined, v);
+        }
 
-        assert_eq!(iterator.nth(0), Some(Run{ len: 5, value: &1 }));
-        assert_eq!(iterator.nth(1), Some(Run{ len: 3, value: &3 }));
-        assert_eq!(iterator.nth(0), Some(Run{ len: 4, value: &5 }));
-        assert_eq!(iterator.nth(0), None);
+        let pieces = rle.partition_chunks(1);
+        assert_eq!(pieces[0].runs_len(), rle.runs_len());
 
-        assert_eq!(rle.runs().count(), 4);
-        assert_eq!(rle.runs().last(), Some(Run{ len: 4, value: &5 }));
-        assert_eq!(rle.runs().skip(10).last(), None);
+        // a single giant run must still be balanced
+        let mut rle = RleVec::new();
+        rle.push_n(1_000_000, 7u8);
+        let pieces = rle.partition_chunks(4);
+        assert!(pieces.iter().all(|p| p.len() == 250_000 && p.runs_len() == 1));
 
+        let rle = RleVec::<i32>::new();
+        assert!(rle.partition_chunks(3).iter().all(|p| p.is_empty()));
     }
 
+    #[cfg(feature = "rayon")]
     #[test]
-    fn starts_ends() {
-        let v = vec![0,0,0,1,1,1,1,1,1,1,3,3,1,0,99,99,9];
-        let rle = v.iter().cloned().collect::<RleVec<_>>();
-        assert_eq!(rle.starts(), vec![0,3,10,12,13,14,16]);
-        assert_eq!(rle.ends(),   vec![2,9,11,12,13,15,16]);
+    fn parallel_construction() {
+        use rayon::prelude::*;
+
+        // simple lcg to get some runs of random length
+        let mut state = 42u64;
+        let mut v = Vec::new();
+        while v.len() < 500_000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let len = (state >> 33) as usize % 50;
+            let value = (state >> 60) as u8;
+            v.extend(std::iter::repeat(value).take(len));
+        }
 
-        let rle = RleVec::<i64>::new();
-        assert!(rle.starts().is_empty());
-        assert!(rle.ends().is_empty());
+        let rle = RleVec::from(&v[..]);
+        assert_eq!(RleVec::from_par_slice(&v), rle);
+        assert_eq!(v.par_iter().cloned().collect::<RleVec<_>>(), rle);
+        assert_eq!(RleVec::from_par_slice(&v[..1]), RleVec::from(&v[..1]));
+        assert_eq!(RleVec::<u8>::from_par_slice(&[]), RleVec::new());
+
+        let rle = RleVec::from_par_slice(&vec![1; 100_000]);
+        assert_eq!(rle.runs_len(), 1);
+        assert_eq!(rle.len(), 100_000);
     }
 
     #[test]
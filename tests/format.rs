@@ -0,0 +1,114 @@
+//! Golden fixtures of the serialized form of `RleVec`.
+//!
+//! Every fixture in `tests/fixtures/format` is named after the format version that wrote it. All
+//! fixtures up to `FORMAT_VERSION` must keep decoding to the same `RleVec` in every release, the
+//! fixtures of the current version must also be reproduced exactly by encoding. When the format
+//! changes, bump `FORMAT_VERSION` and add fixtures for the new version next to the old ones.
+//!
+//! The `.bincode` fixtures cover a format that is not self-describing. It reads the fields by
+//! position, so unversioned data from before version 1 cannot be read with it.
+#![cfg(feature = "serde")]
+
+extern crate bincode;
+extern crate rle_vec;
+extern crate serde;
+extern crate serde_json;
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+
+use rle_vec::{RleVec, Run, FORMAT_VERSION};
+
+const FIXTURES: &[&str] = &["v0_legacy.bincode", "v0_legacy.json", "v1_empty.json", "v1_integers.bincode",
+                            "v1_integers.json", "v1_strings.json", "v2_unsupported.json"];
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/format").join(name);
+    fs::read_to_string(path).unwrap().trim_end().to_string()
+}
+
+fn binary_fixture(name: &str) -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/format").join(name)).unwrap()
+}
+
+fn version(name: &str) -> u32 {
+    name[1..name.find('_').unwrap()].parse().unwrap()
+}
+
+fn check<T>(name: &str, expected: RleVec<T>)
+    where T: Debug + Eq + serde::Serialize + serde::de::DeserializeOwned
+{
+    let json = fixture(name);
+    let decoded: RleVec<T> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, expected, "{}", name);
+    if version(name) == FORMAT_VERSION {
+        assert_eq!(serde_json::to_string(&expected).unwrap(), json, "{}", name);
+    }
+}
+
+#[test]
+fn all_fixtures_are_checked() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/format");
+    let mut found: Vec<_> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    found.sort();
+    assert_eq!(found, FIXTURES);
+}
+
+#[test]
+fn supported_versions_decode() {
+    check("v0_legacy.json", RleVec::from(&[1, 1, 1, 2, 2][..]));
+    check("v1_empty.json", RleVec::<u32>::new());
+    check("v1_integers.json", RleVec::from(&[1, 1, 1, 2, 2, 1][..]));
+
+    let mut strings = RleVec::new();
+    strings.push("a".to_string());
+    strings.push_n(99, "b".to_string());
+    check("v1_strings.json", strings);
+}
+
+#[test]
+fn binary_fixtures() {
+    let expected = RleVec::from(&[1u32, 1, 1, 2, 2, 1][..]);
+    let bytes = binary_fixture("v1_integers.bincode");
+    assert_eq!(bincode::deserialize::<RleVec<u32>>(&bytes).unwrap(), expected);
+    assert_eq!(bincode::serialize(&expected).unwrap(), bytes);
+
+    // without a version field the run count is read as the version and the rest is misaligned
+    assert!(bincode::deserialize::<RleVec<u32>>(&binary_fixture("v0_legacy.bincode")).is_err());
+}
+
+#[test]
+fn non_canonical_runs_are_refused() {
+    let cases = [
+        (r#"{"version":1,"runs":[{"end":2,"value":1},{"end":2,"value":2}]}"#, "run 1 has a length of zero"),
+        (r#"{"version":1,"runs":[{"end":2,"value":1},{"end":4,"value":2},{"end":3,"value":3}]}"#,
+         "run 2 has a length of zero"),
+        (r#"{"version":1,"runs":[{"end":2,"value":1},{"end":4,"value":1}]}"#, "runs 0 and 1 have equal values"),
+        (r#"{"runs":[{"end":0,"value":5},{"end":1,"value":5}]}"#, "runs 0 and 1 have equal values"),
+    ];
+    for &(json, message) in cases.iter() {
+        let err = serde_json::from_str::<RleVec<u32>>(json).unwrap_err();
+        assert!(err.to_string().starts_with(message), "{}: {}", json, err);
+    }
+}
+
+#[test]
+fn runs_round_trip() {
+    let run = Run { len: 1_000_000, value: 'x' };
+    let json = serde_json::to_string(&run).unwrap();
+    assert_eq!(json, r#"{"len":1000000,"value":"x"}"#);
+    assert_eq!(serde_json::from_str::<Run<char>>(&json).unwrap(), run);
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    for name in FIXTURES.iter().filter(|name| version(name) > FORMAT_VERSION) {
+        let err = serde_json::from_str::<RleVec<u32>>(&fixture(name)).unwrap_err();
+        let message = format!("unsupported RleVec format version {}, the newest supported version is {}",
+                              version(name), FORMAT_VERSION);
+        assert!(err.to_string().starts_with(&message), "{}: {}", name, err);
+    }
+}
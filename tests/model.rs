@@ -0,0 +1,308 @@
+//! Model based tests: random sequences of operations are applied to an `RleVec` and to a `Vec`
+//! model, which must hold the same values after every step. The `RleVec` must also stay in
+//! canonical form.
+//!
+//! A failing sequence is shrunk to a minimal failing start vector and sequence of operations
+//! before it is reported. Set `PROPTEST_RNG_SEED` to replay a run and `PROPTEST_CASES` to run
+//! more cases. Every new mutating method should get an `Op` here.
+
+extern crate proptest;
+extern crate rle_vec;
+
+use proptest::prelude::*;
+use rle_vec::RleVec;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Set(usize, u8),
+    SetReporting(usize, u8),
+    SetRange(usize, usize, u8),
+    SetRangeReporting(usize, usize, u8),
+    Replace(usize, u8),
+    Insert(usize, u8),
+    InsertReporting(usize, u8),
+    InsertN(usize, usize, u8),
+    Scatter(Vec<(usize, u8)>),
+    Drain(usize, usize),
+    TakeRange(usize, usize),
+    RemoveAll(u8),
+    Remove(usize),
+    RemoveReporting(usize),
+    Pop,
+    PushN(usize, u8),
+    ExtendFromSlice(Vec<u8>),
+    Prepend(Vec<u8>),
+    Append(Vec<u8>),
+    Resize(usize, u8),
+    DrainFront(usize),
+    Truncate(usize),
+    SplitOff(usize),
+    SwapRanges(usize, usize, usize),
+    SaturatingAddRange(usize, usize, u8),
+    Retain(u8),
+    RetainMap(u8),
+    ExtractWithFill(u8, u8),
+}
+
+fn values() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(0..4u8, 0..64),
+        (0..4u8, 0..64usize).prop_map(|(v, n)| vec![v; n]),
+        (0..64usize).prop_map(|n| (0..n).map(|i| (i % 2) as u8).collect()),
+        Just(vec![]),
+        (0..4u8).prop_map(|v| vec![v]),
+    ]
+}
+
+fn ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![
+        (any::<usize>(), 0..4u8).prop_map(|(i, v)| Op::Set(i, v)),
+        (any::<usize>(), 0..4u8).prop_map(|(i, v)| Op::SetReporting(i, v)),
+        (any::<usize>(), any::<usize>(), 0..4u8).prop_map(|(a, b, v)| Op::SetRange(a, b, v)),
+        (any::<usize>(), any::<usize>(), 0..4u8).prop_map(|(a, b, v)| Op::SetRangeReporting(a, b, v)),
+        (any::<usize>(), 0..4u8).prop_map(|(i, v)| Op::Replace(i, v)),
+        (any::<usize>(), 0..4u8).prop_map(|(i, v)| Op::Insert(i, v)),
+        (any::<usize>(), 0..4u8).prop_map(|(i, v)| Op::InsertReporting(i, v)),
+        (any::<usize>(), 0..4usize, 0..4u8).prop_map(|(i, n, v)| Op::InsertN(i, n, v)),
+        prop::collection::vec((any::<usize>(), 0..4u8), 0..8).prop_map(Op::Scatter),
+        any::<(usize, usize)>().prop_map(|(a, b)| Op::Drain(a, b)),
+        any::<(usize, usize)>().prop_map(|(a, b)| Op::TakeRange(a, b)),
+        (0..4u8).prop_map(Op::RemoveAll),
+        any::<usize>().prop_map(Op::Remove),
+        any::<usize>().prop_map(Op::RemoveReporting),
+        Just(Op::Pop),
+        (0..4usize, 0..4u8).prop_map(|(n, v)| Op::PushN(n, v)),
+        values().prop_map(Op::ExtendFromSlice),
+        values().prop_map(Op::Prepend),
+        values().prop_map(Op::Append),
+        (0..96usize, 0..4u8).prop_map(|(n, v)| Op::Resize(n, v)),
+        any::<usize>().prop_map(Op::DrainFront),
+        any::<usize>().prop_map(Op::Truncate),
+        any::<usize>().prop_map(Op::SplitOff),
+        (any::<usize>(), any::<usize>(), 0..3u8).prop_map(|(a, b, d)| Op::SaturatingAddRange(a, b, d * 127)),
+        any::<(usize, usize, usize)>().prop_map(|(l, a, b)| Op::SwapRanges(l, a, b)),
+        (0..4u8).prop_map(Op::Retain),
+        (0..4u8).prop_map(Op::RetainMap),
+        (0..4u8, 0..4u8).prop_map(|(v, fill)| Op::ExtractWithFill(v, fill)),
+    ];
+    prop::collection::vec(op, 0..32)
+}
+
+// a range within `0..len` from two arbitrary bounds
+fn range(len: usize, a: usize, b: usize) -> std::ops::Range<usize> {
+    let (a, b) = (a % (len + 1), b % (len + 1));
+    std::cmp::min(a, b)..std::cmp::max(a, b)
+}
+
+proptest! {
+    #[test]
+    fn mutations_match_vec(start in values(), ops in ops()) {
+        let mut expected = start.clone();
+        let mut rle = RleVec::from(&start[..]);
+        prop_assert_eq!(rle.to_vec(), expected.clone());
+
+        for op in ops {
+            match op {
+                Op::Set(i, v) if !expected.is_empty() => {
+                    let i = i % expected.len();
+                    rle.set(i, v);
+                    expected[i] = v;
+                }
+                Op::SetReporting(i, v) if !expected.is_empty() => {
+                    let i = i % expected.len();
+                    let runs = rle.runs_len() as isize;
+                    let report = rle.set_reporting(i, v);
+                    prop_assert_eq!(report.run_delta, rle.runs_len() as isize - runs);
+                    prop_assert_eq!(report.run_delta, report.splits as isize - report.merges as isize);
+                    expected[i] = v;
+                }
+                Op::SetRange(a, b, v) => {
+                    let range = range(expected.len(), a, b);
+                    rle.set_range(range.clone(), v);
+                    expected[range].fill(v);
+                }
+                Op::SetRangeReporting(a, b, v) => {
+                    let range = range(expected.len(), a, b);
+                    let runs = rle.runs_len() as isize;
+                    let report = rle.set_range_reporting(range.clone(), v);
+                    prop_assert_eq!(report.run_delta, rle.runs_len() as isize - runs);
+                    prop_assert_eq!(report.run_delta, report.splits as isize - report.merges as isize);
+                    expected[range].fill(v);
+                }
+                Op::Replace(i, v) if !expected.is_empty() => {
+                    let i = i % expected.len();
+                    prop_assert_eq!(rle.replace(i, v), std::mem::replace(&mut expected[i], v));
+                }
+                Op::Insert(i, v) => {
+                    let i = i % (expected.len() + 1);
+                    rle.insert(i, v);
+                    expected.insert(i, v);
+                }
+                Op::InsertReporting(i, v) => {
+                    let i = i % (expected.len() + 1);
+                    let runs = rle.runs_len() as isize;
+                    let report = rle.insert_reporting(i, v);
+                    prop_assert_eq!(report.run_delta, rle.runs_len() as isize - runs);
+                    expected.insert(i, v);
+                }
+                Op::InsertN(i, n, v) => {
+                    let i = i % (expected.len() + 1);
+                    rle.insert_n(i, n, v);
+                    expected.splice(i..i, vec![v; n]);
+                }
+                Op::Scatter(updates) => {
+                    // index `len` is out of bounds and must leave the vector unchanged
+                    let updates: Vec<_> = updates.into_iter().map(|(i, v)| (i % (expected.len() + 1), v)).collect();
+                    let result = rle.try_scatter(updates.clone());
+                    if updates.iter().any(|&(i, _)| i == expected.len()) {
+                        prop_assert!(result.is_err());
+                    } else {
+                        prop_assert_eq!(result, Ok(()));
+                        for (i, v) in updates {
+                            expected[i] = v;
+                        }
+                    }
+                }
+                Op::Drain(a, b) => {
+                    let range = range(expected.len(), a, b);
+                    let drained: Vec<_> = rle.drain(range.clone()).collect();
+                    prop_assert_eq!(drained, expected.drain(range).collect::<Vec<_>>());
+                }
+                Op::TakeRange(a, b) => {
+                    let range = range(expected.len(), a, b);
+                    let taken = rle.take_range(range.clone());
+                    prop_assert!(taken.is_canonical());
+                    prop_assert_eq!(taken.to_vec(), expected.drain(range).collect::<Vec<_>>());
+                }
+                Op::Remove(i) if !expected.is_empty() => {
+                    let i = i % expected.len();
+                    prop_assert_eq!(rle.remove(i), expected.remove(i));
+                }
+                Op::RemoveReporting(i) if !expected.is_empty() => {
+                    let i = i % expected.len();
+                    let runs = rle.runs_len() as isize;
+                    let (value, report) = rle.remove_reporting(i);
+                    prop_assert_eq!(value, expected.remove(i));
+                    prop_assert_eq!(report.run_delta, rle.runs_len() as isize - runs);
+                }
+                Op::Pop => {
+                    prop_assert_eq!(rle.pop(), expected.pop());
+                }
+                Op::PushN(n, v) => {
+                    rle.push_n(n, v);
+                    expected.resize(expected.len() + n, v);
+                }
+                Op::ExtendFromSlice(values) => {
+                    rle.extend_from_slice(&values);
+                    expected.extend(values);
+                }
+                Op::Prepend(values) => {
+                    rle.prepend_rle(&RleVec::from(&values[..]));
+                    expected.splice(0..0, values);
+                }
+                Op::Append(values) => {
+                    let mut other = RleVec::from(&values[..]);
+                    rle.append(&mut other);
+                    prop_assert!(other.is_empty());
+                    expected.extend(values);
+                }
+                Op::Resize(n, v) => {
+                    rle.resize(n, v);
+                    expected.resize(n, v);
+                }
+                Op::DrainFront(n) => {
+                    let n = n % (expected.len() + 1);
+                    rle.drain_front(n);
+                    expected.drain(..n);
+                }
+                Op::Truncate(n) => {
+                    let n = n % (expected.len() + 2);
+                    rle.truncate(n);
+                    expected.truncate(n);
+                }
+                Op::SplitOff(at) => {
+                    let at = at % (expected.len() + 1);
+                    let tail = rle.split_off(at);
+                    prop_assert_eq!(tail.to_vec(), expected.split_off(at));
+                    prop_assert!(tail.is_canonical());
+                }
+                Op::SwapRanges(l, a, b) => {
+                    let len = expected.len();
+                    let l = l % (len / 2 + 1);
+                    let a = a % (len - 2 * l + 1);
+                    let b = a + l + b % (len - a - 2 * l + 1);
+                    rle.swap_ranges(a..a + l, b..b + l);
+                    for k in 0..l {
+                        expected.swap(a + k, b + k);
+                    }
+                }
+                Op::SaturatingAddRange(a, b, delta) => {
+                    let range = range(expected.len(), a, b);
+                    rle.saturating_add_range(range.clone(), delta);
+                    for v in &mut expected[range] {
+                        *v = v.saturating_add(delta);
+                    }
+                }
+                Op::Retain(dropped) => {
+                    let len = expected.len();
+                    expected.retain(|&v| v != dropped);
+                    prop_assert_eq!(rle.retain(|&v| v != dropped), len - expected.len());
+                }
+                Op::RemoveAll(dropped) => {
+                    let len = expected.len();
+                    expected.retain(|&v| v != dropped);
+                    prop_assert_eq!(rle.remove_all(&dropped), len - expected.len());
+                }
+                Op::RetainMap(dropped) => {
+                    rle.retain_map_runs(|v, _| {
+                        let keep = *v != dropped;
+                        *v /= 2;
+                        keep
+                    });
+                    expected.retain(|&v| v != dropped);
+                    for v in &mut expected {
+                        *v /= 2;
+                    }
+                }
+                Op::ExtractWithFill(target, fill) => {
+                    let extracted = rle.extract_runs_with_fill(|&v| v == target, fill);
+                    for (range, value) in extracted {
+                        prop_assert_eq!(value, target);
+                        prop_assert!(expected[range.clone()].iter().all(|&v| v == target));
+                        for v in &mut expected[range] {
+                            *v = fill;
+                        }
+                    }
+                    prop_assert!(expected.iter().all(|&v| v != target || v == fill));
+                }
+                _ => continue,
+            }
+            prop_assert_eq!(rle.to_vec(), expected.clone());
+            prop_assert_eq!(rle.len(), expected.len());
+            prop_assert!(rle.is_canonical());
+            rle.assert_valid();
+        }
+    }
+
+    #[test]
+    fn window_equality_matches_slices(a in values(), b in values(), bounds in any::<[usize; 4]>()) {
+        let (rle_a, rle_b) = (RleVec::from(&a[..]), RleVec::from(&b[..]));
+        let range_a = range(a.len(), bounds[0], bounds[1]);
+        let range_b = range(b.len(), bounds[2], bounds[3]);
+
+        prop_assert_eq!(rle_a.eq_range(range_a.clone(), &rle_b, range_b.clone()), a[range_a.clone()] == b[range_b.clone()]);
+        prop_assert_eq!(rle_a.eq_range_slice(range_a.clone(), &b[range_b.clone()]), a[range_a.clone()] == b[range_b.clone()]);
+        prop_assert!(rle_a.eq_range(range_a.clone(), &rle_a, range_a.clone()));
+
+        let shifted = RleVec::from(&[&b[..], &a[..]].concat()[..]);
+        let offset = b.len();
+        prop_assert!(rle_a.eq_range(range_a.clone(), &shifted, range_a.start + offset..range_a.end + offset));
+    }
+
+    #[test]
+    fn order_matches_vec(a in values(), b in values()) {
+        let (rle_a, rle_b) = (RleVec::from(&a[..]), RleVec::from(&b[..]));
+        prop_assert_eq!(rle_a.cmp(&rle_b), a.cmp(&b));
+        prop_assert_eq!(rle_a.partial_cmp(&rle_b), a.partial_cmp(&b));
+    }
+}